@@ -0,0 +1,6 @@
+//! Prints the program IDL as JSON.
+
+fn main() {
+    let idl = spool_api::idl::generate();
+    println!("{}", serde_json::to_string_pretty(&idl).unwrap());
+}
@@ -15,6 +15,10 @@ pub const SPOOL:    &[u8] = b"spool";
 pub const TREASURY: &[u8] = b"treasury";
 pub const MINT:     &[u8] = b"mint";
 pub const METADATA: &[u8] = b"metadata";
+pub const EDITION:  &[u8] = b"edition";
+pub const OWNERSHIP: &[u8] = b"ownership";
+pub const FAUCET:   &[u8] = b"faucet";
+pub const FAUCET_CONFIG: &[u8] = b"faucet_config";
 
 /// Mint PDA seed (raw bytes)
 pub const MINT_SEED: &[u8] = &[152, 68, 212, 200, 25, 113, 221, 71];
@@ -27,6 +31,10 @@ pub const METADATA_NAME:   &str = "SPOOL";
 pub const METADATA_SYMBOL: &str = "SPOOL";
 pub const METADATA_URI:    &str = "https://spoolnet.io/metadata.json";
 
+/// On-chain metadata for spool ownership tokens (the URI is suffixed with the spool address)
+pub const OWNERSHIP_SYMBOL:     &str = "SPOOLNFT";
+pub const OWNERSHIP_URI_PREFIX: &str = "https://spoolnet.io/spool/";
+
 // ====================================================================
 // Merkle Tree Configuration
 // ====================================================================
@@ -97,6 +105,19 @@ pub const EMPTY_SEGMENT: [u8; SEGMENT_SIZE] = [0; SEGMENT_SIZE];
 /// Empty Merkle proof for spools that don't have minimum rent
 pub const EMPTY_PROOF: [[u8; 32]; SEGMENT_PROOF_LEN] = [[0; 32]; SEGMENT_PROOF_LEN];
 
+// ====================================================================
+// Faucet Constants (airdrop feature only)
+// ====================================================================
+// These are the defaults until the admin sets the faucet config account.
+/// Maximum amount a wallet can claim from the faucet per window
+pub const FAUCET_CAP: u64 = 10_000 * ONE_SPOOL;
+/// Length of a faucet window in seconds (~1 day)
+pub const FAUCET_WINDOW_SECONDS: i64 = 60 * 60 * 24;
+/// Minimum time between two faucet claims in seconds
+pub const FAUCET_COOLDOWN_SECONDS: i64 = 60;
+/// Number of the genesis spool, whose authority is the faucet admin
+pub const GENESIS_SPOOL_NUMBER: u64 = 1;
+
 // ====================================================================
 // Miscellaneous
 // ====================================================================
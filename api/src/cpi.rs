@@ -0,0 +1,250 @@
+//! Typed CPI wrappers for calling the spool program from another program.
+//!
+//! Every wrapper takes `signer_seeds` and calls `invoke_signed`, so the authority can be a PDA
+//! owned by the calling program. Pass `&[]` when the authority signed the outer transaction.
+//!
+//! Wrappers for instructions that accept an ownership token also take `ownership_info`, the
+//! holder's token account. Pass `None` until an ownership token has been minted for the spool.
+
+use steel::*;
+use solana_program::program::invoke_signed;
+use crate::{
+    consts::*,
+    error::*,
+    instruction::spool::*,
+    state::*,
+    types::*,
+    utils::*,
+};
+
+/// Creates a new spool (and its writer) owned by `authority_info`.
+#[allow(clippy::too_many_arguments)]
+pub fn create<'info>(
+    payer_info: &AccountInfo<'info>,
+    authority_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    writer_info: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    rent_sysvar_info: &AccountInfo<'info>,
+    spool_program_info: &AccountInfo<'info>,
+    name: &str,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &build_create_ix(
+            *payer_info.key,
+            *authority_info.key,
+            name,
+        ),
+        &[
+            payer_info.clone(),
+            authority_info.clone(),
+            spool_info.clone(),
+            writer_info.clone(),
+            system_program_info.clone(),
+            rent_sysvar_info.clone(),
+            spool_program_info.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Appends `data` to the spool.
+#[allow(clippy::too_many_arguments)]
+pub fn write<'info>(
+    authority_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    writer_info: &AccountInfo<'info>,
+    ownership_info: Option<&AccountInfo<'info>>,
+    spool_program_info: &AccountInfo<'info>,
+    data: &[u8],
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_with_ownership(
+        build_write_ix(
+            *authority_info.key,
+            *spool_info.key,
+            *writer_info.key,
+            data,
+        ),
+        &[
+            authority_info.clone(),
+            spool_info.clone(),
+            writer_info.clone(),
+            spool_program_info.clone(),
+        ],
+        ownership_info,
+        signer_seeds,
+    )
+}
+
+/// Replaces a previously written segment.
+#[allow(clippy::too_many_arguments)]
+pub fn update<'info>(
+    authority_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    writer_info: &AccountInfo<'info>,
+    ownership_info: Option<&AccountInfo<'info>>,
+    spool_program_info: &AccountInfo<'info>,
+    segment_number: u64,
+    old_data: [u8; SEGMENT_SIZE],
+    new_data: [u8; SEGMENT_SIZE],
+    proof: ProofPath,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_with_ownership(
+        build_update_ix(
+            *authority_info.key,
+            *spool_info.key,
+            *writer_info.key,
+            segment_number,
+            old_data,
+            new_data,
+            proof,
+        ),
+        &[
+            authority_info.clone(),
+            spool_info.clone(),
+            writer_info.clone(),
+            spool_program_info.clone(),
+        ],
+        ownership_info,
+        signer_seeds,
+    )
+}
+
+/// Sets the opaque header of the spool.
+pub fn set_header<'info>(
+    authority_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    ownership_info: Option<&AccountInfo<'info>>,
+    spool_program_info: &AccountInfo<'info>,
+    header: &[u8; HEADER_SIZE],
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_with_ownership(
+        build_set_header_ix(
+            *authority_info.key,
+            *spool_info.key,
+            header,
+        ),
+        &[
+            authority_info.clone(),
+            spool_info.clone(),
+            spool_program_info.clone(),
+        ],
+        ownership_info,
+        signer_seeds,
+    )
+}
+
+/// Transfers `amount` SPOOL from `ata_info` to the treasury as rent for the spool. Anyone can
+/// subsidize any spool; `authority_info` here is the owner of the token account.
+#[allow(clippy::too_many_arguments)]
+pub fn subsidize<'info>(
+    authority_info: &AccountInfo<'info>,
+    ata_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    treasury_ata_info: &AccountInfo<'info>,
+    token_program_info: &AccountInfo<'info>,
+    spool_program_info: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &build_subsidize_ix(
+            *authority_info.key,
+            *ata_info.key,
+            *spool_info.key,
+            amount,
+        ),
+        &[
+            authority_info.clone(),
+            ata_info.clone(),
+            spool_info.clone(),
+            treasury_ata_info.clone(),
+            token_program_info.clone(),
+            spool_program_info.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Finalizes the spool, closing the writer and returning its rent to `payer_info`.
+#[allow(clippy::too_many_arguments)]
+pub fn finalize<'info>(
+    payer_info: &AccountInfo<'info>,
+    authority_info: &AccountInfo<'info>,
+    spool_info: &AccountInfo<'info>,
+    writer_info: &AccountInfo<'info>,
+    archive_info: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    rent_sysvar_info: &AccountInfo<'info>,
+    ownership_info: Option<&AccountInfo<'info>>,
+    spool_program_info: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_with_ownership(
+        build_finalize_ix(
+            *payer_info.key,
+            *authority_info.key,
+            *spool_info.key,
+            *writer_info.key,
+        ),
+        &[
+            payer_info.clone(),
+            authority_info.clone(),
+            spool_info.clone(),
+            writer_info.clone(),
+            archive_info.clone(),
+            system_program_info.clone(),
+            rent_sysvar_info.clone(),
+            spool_program_info.clone(),
+        ],
+        ownership_info,
+        signer_seeds,
+    )
+}
+
+/// Invokes `ix`, appending the ownership token account to it when one is given.
+fn invoke_with_ownership<'info>(
+    ix: Instruction,
+    account_infos: &[AccountInfo<'info>],
+    ownership_info: Option<&AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    match ownership_info {
+        Some(ownership_info) => {
+            let ix = with_ownership_token(ix, *ownership_info.key);
+            let mut account_infos = account_infos.to_vec();
+            account_infos.push(ownership_info.clone());
+            invoke_signed(&ix, &account_infos, signer_seeds)
+        }
+        None => invoke_signed(&ix, account_infos, signer_seeds),
+    }
+}
+
+/// Checks that `data` is segment `segment_number` of the spool, against the spool's current
+/// merkle root. This reads the spool account directly, no CPI is made.
+pub fn verify_segment(
+    spool_info: &AccountInfo<'_>,
+    segment_number: u64,
+    data: &[u8; SEGMENT_SIZE],
+    proof: &ProofPath,
+) -> ProgramResult {
+    let spool = spool_info.as_account::<Spool>(&crate::ID)?;
+
+    check_condition(
+        segment_number < spool.total_segments,
+        SpoolError::InvalidSegmentProof,
+    )?;
+
+    let leaf = compute_leaf(segment_number, data);
+
+    check_condition(
+        brine_tree::verify(spool.merkle_root, proof.as_slice(), leaf),
+        SpoolError::InvalidSegmentProof,
+    )?;
+
+    Ok(())
+}
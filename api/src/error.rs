@@ -1,7 +1,7 @@
 use steel::*;
 
 #[repr(u32)]
-#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 pub enum SpoolError {
     #[error("Unknown error")]
     UnknownError = 0,
@@ -14,6 +14,10 @@ pub enum SpoolError {
     SpoolTooLong            = 0x12,
     #[error("The spool does not have enough rent")]
     InsufficientRent        = 0x13,
+    #[error("The provided ownership token does not control the spool")]
+    InvalidOwnershipToken   = 0x14,
+    #[error("The provided segment does not match the spool")]
+    InvalidSegmentProof     = 0x15,
 
     #[error("The provided hash is invalid")]
     SolutionInvalid         = 0x20,
@@ -36,6 +40,11 @@ pub enum SpoolError {
     ReelTooManySpools       = 0x32,
     #[error("Reel commit failed")]
     ReelCommitFailed        = 0x33,
+
+    #[error("The faucet allowance is used up or the cooldown has not passed")]
+    FaucetRateLimited       = 0x40,
+    #[error("The faucet config is invalid")]
+    InvalidFaucetConfig     = 0x41,
 }
 
 error!(SpoolError);
@@ -0,0 +1,638 @@
+//! Anchor-compatible (spec 0.1.0) IDL for the spool program.
+//!
+//! The IDL is generated from the Rust definitions: layouts are checked against the `#[repr(C)]`
+//! structs with `offset_of!`/`size_of`, account ordering comes from the `build_*_ix` functions,
+//! and discriminators, error codes and event types come from their enums. Regenerate the
+//! checked-in copy with `cargo run -p spool-api --bin spool-idl > api/idl/spool.json`.
+
+use serde_json::{json, Value};
+use steel::*;
+
+use crate::{
+    consts::*,
+    error::SpoolError,
+    event::*,
+    instruction::{miner, program, reel, spool},
+    pda::*,
+    state::*,
+    types::*,
+};
+
+/// The checked-in IDL, kept in sync by the drift test below.
+pub const IDL_JSON: &str = include_str!("../idl/spool.json");
+
+/// An IDL field type, with enough information to compute its size.
+enum Ty {
+    U8,
+    U64,
+    I64,
+    Pubkey,
+    Array(Box<Ty>, usize),
+    Defined(&'static str, usize),
+}
+
+impl Ty {
+    fn size(&self) -> usize {
+        match self {
+            Ty::U8 => 1,
+            Ty::U64 | Ty::I64 => 8,
+            Ty::Pubkey => 32,
+            Ty::Array(ty, len) => ty.size() * len,
+            Ty::Defined(_, size) => *size,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Ty::U8 => json!("u8"),
+            Ty::U64 => json!("u64"),
+            Ty::I64 => json!("i64"),
+            Ty::Pubkey => json!("pubkey"),
+            Ty::Array(ty, len) => json!({ "array": [ty.to_json(), len] }),
+            Ty::Defined(name, _) => json!({ "defined": { "name": name } }),
+        }
+    }
+}
+
+fn bytes(len: usize) -> Ty {
+    Ty::Array(Box::new(Ty::U8), len)
+}
+
+fn hashes(len: usize) -> Ty {
+    Ty::Array(Box::new(bytes(32)), len)
+}
+
+fn defined<T>(name: &'static str) -> Ty {
+    Ty::Defined(name, core::mem::size_of::<T>())
+}
+
+/// Lists the fields of a `#[repr(C)]` struct, checking each declared IDL type against the
+/// field's actual offset and the struct's size.
+macro_rules! fields {
+    ($ty:ty { $($field:ident: $idl:expr),* $(,)? }) => {
+        check_layout::<$ty>(
+            stringify!($ty),
+            vec![$((stringify!($field), core::mem::offset_of!($ty, $field), $idl)),*],
+        )
+    };
+}
+
+fn check_layout<T>(name: &str, fields: Vec<(&str, usize, Ty)>) -> Vec<Value> {
+    let mut offset = 0;
+    let fields = fields
+        .into_iter()
+        .map(|(field, actual, ty)| {
+            assert_eq!(actual, offset, "{name}.{field} is not at its IDL offset");
+            offset += ty.size();
+            json!({ "name": field, "type": ty.to_json() })
+        })
+        .collect();
+
+    assert_eq!(
+        offset,
+        core::mem::size_of::<T>(),
+        "{name} has fields missing from the IDL"
+    );
+
+    fields
+}
+
+fn struct_type(name: &str, fields: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "type": { "kind": "struct", "fields": fields },
+    })
+}
+
+/// Dummy keys for the variable accounts passed to the instruction builders.
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+/// Accounts whose address is fixed, regardless of the builder's inputs.
+fn known_address(address: &Pubkey) -> bool {
+    let (metadata_address, _metadata_bump) = metadata_pda(MINT_ADDRESS);
+
+    [
+        crate::ID,
+        system_program::ID,
+        spl_token::ID,
+        spl_associated_token_account::ID,
+        mpl_token_metadata::ID,
+        sysvar::rent::ID,
+        sysvar::slot_hashes::ID,
+        ARCHIVE_ADDRESS,
+        EPOCH_ADDRESS,
+        BLOCK_ADDRESS,
+        MINT_ADDRESS,
+        TREASURY_ADDRESS,
+        TREASURY_ATA,
+        metadata_address,
+    ]
+    .contains(address)
+}
+
+/// Builds an instruction entry, taking account order and flags from the builder output.
+fn instruction(
+    name: &str,
+    docs: &[&str],
+    ix: Instruction,
+    accounts: &[&str],
+    args: Vec<Value>,
+    discriminator: u8,
+) -> Value {
+    assert_eq!(
+        ix.data[0], discriminator,
+        "{name} builder uses the wrong discriminator"
+    );
+    assert_eq!(
+        ix.accounts.len(),
+        accounts.len(),
+        "{name} builder and IDL disagree on the number of accounts"
+    );
+
+    let accounts: Vec<Value> = ix
+        .accounts
+        .iter()
+        .zip(accounts)
+        .map(|(meta, name)| {
+            let mut account = json!({ "name": name });
+            if meta.is_writable {
+                account["writable"] = json!(true);
+            }
+            if meta.is_signer {
+                account["signer"] = json!(true);
+            }
+            if known_address(&meta.pubkey) {
+                account["address"] = json!(meta.pubkey.to_string());
+            }
+            account
+        })
+        .collect();
+
+    let mut entry = json!({
+        "name": name,
+        "discriminator": [discriminator],
+        "accounts": accounts,
+        "args": args,
+    });
+    if !docs.is_empty() {
+        entry["docs"] = json!(docs);
+    }
+    entry
+}
+
+fn instructions() -> Vec<Value> {
+    use miner::MinerInstruction;
+    use program::ProgramInstruction;
+    use reel::ReelInstruction;
+    use spool::SpoolInstruction;
+
+    const OWNERSHIP_DOCS: &str =
+        "Once an ownership token is minted, the holder's token account is appended as a trailing account.";
+
+    vec![
+        instruction(
+            "initialize",
+            &[],
+            program::build_initialize_ix(key(1)),
+            &[
+                "signer", "archive", "epoch", "block", "metadata", "mint", "treasury",
+                "treasury_ata", "spool", "writer", "spool_program", "system_program",
+                "token_program", "associated_token_program", "metadata_program", "rent",
+                "slot_hashes",
+            ],
+            fields!(program::Initialize {}),
+            ProgramInstruction::Initialize as u8,
+        ),
+        instruction(
+            "airdrop",
+            &["Only available when the program is built with the `airdrop` feature."],
+            program::build_airdrop_ix(key(1), key(2), 0),
+            &[
+                "signer", "beneficiary", "faucet_claim", "faucet_config", "mint", "treasury",
+                "token_program", "system_program",
+            ],
+            fields!(program::Airdrop { amount: Ty::U64 }),
+            ProgramInstruction::Airdrop as u8,
+        ),
+        instruction(
+            "configure_faucet",
+            &[
+                "Only available when the program is built with the `airdrop` feature.",
+                "The signer must be the genesis spool authority.",
+            ],
+            program::build_configure_faucet_ix(key(1), 0, 0, 0),
+            &["signer", "genesis_spool", "faucet_config", "system_program"],
+            fields!(program::ConfigureFaucet {
+                cap: Ty::U64,
+                window_seconds: Ty::I64,
+                cooldown_seconds: Ty::I64,
+            }),
+            ProgramInstruction::ConfigureFaucet as u8,
+        ),
+        instruction(
+            "spool_create",
+            &[],
+            spool::build_create_ix(key(1), key(2), "spool"),
+            &["payer", "authority", "spool", "writer", "system_program", "rent"],
+            fields!(spool::Create { name: bytes(NAME_LEN) }),
+            SpoolInstruction::Create as u8,
+        ),
+        instruction(
+            "spool_write",
+            &[
+                "The data to write follows the discriminator and is split into SEGMENT_SIZE segments.",
+                OWNERSHIP_DOCS,
+            ],
+            spool::build_write_ix(key(1), key(2), key(3), &[]),
+            &["authority", "spool", "writer"],
+            fields!(spool::Write {}),
+            SpoolInstruction::Write as u8,
+        ),
+        instruction(
+            "spool_update",
+            &[OWNERSHIP_DOCS],
+            spool::build_update_ix(key(1), key(2), key(3), 0, [0; SEGMENT_SIZE], [0; SEGMENT_SIZE], ProofPath::default()),
+            &["authority", "spool", "writer"],
+            fields!(spool::Update {
+                segment_number: Ty::U64,
+                old_data: bytes(SEGMENT_SIZE),
+                new_data: bytes(SEGMENT_SIZE),
+                proof: defined::<ProofPath>("ProofPath"),
+            }),
+            SpoolInstruction::Update as u8,
+        ),
+        instruction(
+            "spool_finalize",
+            &[OWNERSHIP_DOCS],
+            spool::build_finalize_ix(key(1), key(2), key(3), key(4)),
+            &["payer", "authority", "spool", "writer", "archive", "system_program", "rent"],
+            fields!(spool::Finalize {}),
+            SpoolInstruction::Finalize as u8,
+        ),
+        instruction(
+            "spool_set_header",
+            &[OWNERSHIP_DOCS],
+            spool::build_set_header_ix(key(1), key(2), &[0; HEADER_SIZE]),
+            &["authority", "spool"],
+            fields!(spool::SetHeader { header: bytes(HEADER_SIZE) }),
+            SpoolInstruction::SetHeader as u8,
+        ),
+        instruction(
+            "spool_subsidize",
+            &[],
+            spool::build_subsidize_ix(key(1), key(2), key(3), 0),
+            &["signer", "ata", "spool", "treasury_ata", "token_program"],
+            fields!(spool::Subsidize { amount: Ty::U64 }),
+            SpoolInstruction::Subsidize as u8,
+        ),
+        instruction(
+            "spool_mint_ownership_token",
+            &[],
+            spool::build_mint_ownership_token_ix(key(1), key(2), key(3)),
+            &[
+                "payer", "authority", "spool", "mint", "metadata", "edition", "ata",
+                "system_program", "token_program", "associated_token_program",
+                "metadata_program", "rent",
+            ],
+            fields!(spool::MintOwnershipToken {}),
+            SpoolInstruction::MintOwnershipToken as u8,
+        ),
+        instruction(
+            "miner_register",
+            &[],
+            miner::build_register_ix(key(1), "miner"),
+            &["signer", "miner", "system_program", "rent", "slot_hashes"],
+            fields!(miner::Register { name: bytes(32) }),
+            MinerInstruction::Register as u8,
+        ),
+        instruction(
+            "miner_unregister",
+            &[],
+            miner::build_close_ix(key(1), key(2)),
+            &["signer", "miner", "system_program"],
+            fields!(miner::Unregister {}),
+            MinerInstruction::Unregister as u8,
+        ),
+        instruction(
+            "miner_mine",
+            &[],
+            miner::build_mine_ix(key(1), key(2), key(3), PoW::zeroed(), PoA::zeroed()),
+            &["signer", "epoch", "block", "miner", "spool", "archive", "slot_hashes"],
+            fields!(miner::Mine {
+                pow: defined::<PoW>("PoW"),
+                poa: defined::<PoA>("PoA"),
+            }),
+            MinerInstruction::Mine as u8,
+        ),
+        instruction(
+            "miner_claim",
+            &[],
+            miner::build_claim_ix(key(1), key(2), key(3), 0),
+            &["signer", "beneficiary", "miner", "treasury", "treasury_ata", "token_program"],
+            fields!(miner::Claim { amount: Ty::U64 }),
+            MinerInstruction::Claim as u8,
+        ),
+        instruction(
+            "reel_create",
+            &[],
+            reel::build_create_ix(key(1), key(2), 0),
+            &["signer", "miner", "reel", "system_program", "rent"],
+            fields!(reel::Create { number: Ty::U64 }),
+            ReelInstruction::Create as u8,
+        ),
+        instruction(
+            "reel_destroy",
+            &[],
+            reel::build_destroy_ix(key(1), key(2), 0),
+            &["signer", "miner", "reel", "system_program"],
+            fields!(reel::Destroy { number: Ty::U64 }),
+            ReelInstruction::Destroy as u8,
+        ),
+        instruction(
+            "reel_pack",
+            &[],
+            reel::build_pack_ix(key(1), key(2), key(3), [0; 32]),
+            &["signer", "reel", "spool"],
+            fields!(reel::Pack { value: bytes(32) }),
+            ReelInstruction::Pack as u8,
+        ),
+        instruction(
+            "reel_unpack",
+            &[],
+            reel::build_unpack_ix(key(1), key(2), 0, [[0; 32]; SPOOL_PROOF_LEN], [0; 32]),
+            &["signer", "reel"],
+            fields!(reel::Unpack {
+                index: Ty::U64,
+                proof: hashes(SPOOL_PROOF_LEN),
+                value: bytes(32),
+            }),
+            ReelInstruction::Unpack as u8,
+        ),
+        instruction(
+            "reel_commit",
+            &[],
+            reel::build_commit_ix(key(1), key(2), key(3), 0, ProofPath::default(), [0; 32]),
+            &["signer", "miner", "reel"],
+            fields!(reel::Commit {
+                index: Ty::U64,
+                proof: defined::<ProofPath>("ProofPath"),
+                value: bytes(32),
+            }),
+            ReelInstruction::Commit as u8,
+        ),
+    ]
+}
+
+/// State accounts, as (name, account type, fields).
+fn accounts() -> Vec<(&'static str, AccountType, Vec<Value>)> {
+    vec![
+        ("Archive", AccountType::Archive, fields!(Archive {
+            spools_stored: Ty::U64,
+            segments_stored: Ty::U64,
+        })),
+        ("Reel", AccountType::Reel, fields!(Reel {
+            number: Ty::U64,
+            authority: Ty::Pubkey,
+            state: defined::<SpoolTree>("SpoolTree"),
+            contains: bytes(32),
+            total_spools: Ty::U64,
+            last_proof_block: Ty::U64,
+            last_proof_at: Ty::I64,
+        })),
+        ("Writer", AccountType::Writer, fields!(Writer {
+            spool: Ty::Pubkey,
+            state: defined::<SegmentTree>("SegmentTree"),
+        })),
+        ("Spool", AccountType::Spool, fields!(Spool {
+            number: Ty::U64,
+            state: Ty::U64,
+            authority: Ty::Pubkey,
+            name: bytes(NAME_LEN),
+            merkle_root: bytes(32),
+            header: bytes(HEADER_SIZE),
+            first_slot: Ty::U64,
+            tail_slot: Ty::U64,
+            balance: Ty::U64,
+            last_rent_block: Ty::U64,
+            total_segments: Ty::U64,
+        })),
+        ("Miner", AccountType::Miner, fields!(Miner {
+            authority: Ty::Pubkey,
+            name: bytes(NAME_LEN),
+            unclaimed_rewards: Ty::U64,
+            challenge: bytes(32),
+            commitment: bytes(32),
+            multiplier: Ty::U64,
+            last_proof_block: Ty::U64,
+            last_proof_at: Ty::I64,
+            total_proofs: Ty::U64,
+            total_rewards: Ty::U64,
+        })),
+        ("Epoch", AccountType::Epoch, fields!(Epoch {
+            number: Ty::U64,
+            progress: Ty::U64,
+            mining_difficulty: Ty::U64,
+            packing_difficulty: Ty::U64,
+            target_participation: Ty::U64,
+            reward_rate: Ty::U64,
+            duplicates: Ty::U64,
+            last_epoch_at: Ty::I64,
+        })),
+        ("Block", AccountType::Block, fields!(Block {
+            number: Ty::U64,
+            progress: Ty::U64,
+            challenge: bytes(32),
+            challenge_set: Ty::U64,
+            last_proof_at: Ty::I64,
+            last_block_at: Ty::I64,
+        })),
+        ("Treasury", AccountType::Treasury, fields!(Treasury {})),
+        ("FaucetClaim", AccountType::FaucetClaim, fields!(FaucetClaim {
+            authority: Ty::Pubkey,
+            window_start: Ty::I64,
+            claimed: Ty::U64,
+            last_claim_at: Ty::I64,
+        })),
+        ("FaucetConfig", AccountType::FaucetConfig, fields!(FaucetConfig {
+            cap: Ty::U64,
+            window_seconds: Ty::I64,
+            cooldown_seconds: Ty::I64,
+        })),
+    ]
+}
+
+/// Events, as (name, event type, fields).
+fn events() -> Vec<(&'static str, EventType, Vec<Value>)> {
+    vec![
+        ("WriteEvent", EventType::WriteEvent, fields!(WriteEvent {
+            num_added: Ty::U64,
+            num_total: Ty::U64,
+            prev_slot: Ty::U64,
+            address: Ty::Pubkey,
+        })),
+        ("UpdateEvent", EventType::UpdateEvent, fields!(UpdateEvent {
+            segment_number: Ty::U64,
+            prev_slot: Ty::U64,
+            address: Ty::Pubkey,
+        })),
+        ("FinalizeEvent", EventType::FinalizeEvent, fields!(FinalizeEvent {
+            spool: Ty::U64,
+            address: Ty::Pubkey,
+        })),
+    ]
+}
+
+/// Shared types referenced by instructions and accounts. Merkle trees are opaque byte arrays.
+fn types() -> Vec<Value> {
+    vec![
+        struct_type("PoW", fields!(PoW {
+            digest: bytes(16),
+            nonce: bytes(8),
+        })),
+        struct_type("PoA", fields!(PoA {
+            bump: Ty::U8,
+            seed: bytes(16),
+            nonce: bytes(128),
+            path: defined::<ProofPath>("ProofPath"),
+        })),
+        json!({
+            "name": "ProofPath",
+            "type": { "kind": "type", "alias": hashes(SEGMENT_PROOF_LEN).to_json() },
+        }),
+        json!({
+            "name": "SegmentTree",
+            "type": { "kind": "type", "alias": bytes(core::mem::size_of::<SegmentTree>()).to_json() },
+        }),
+        json!({
+            "name": "SpoolTree",
+            "type": { "kind": "type", "alias": bytes(core::mem::size_of::<SpoolTree>()).to_json() },
+        }),
+    ]
+}
+
+fn errors() -> Vec<Value> {
+    (0..=u8::MAX as u32)
+        .filter_map(|code| SpoolError::try_from(code).ok())
+        .map(|err| {
+            json!({
+                "code": u32::from(err),
+                "name": format!("{err:?}"),
+                "msg": err.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Account and event discriminators are 8 bytes, with the type in the first byte.
+fn discriminator8(value: u8) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator[0] = value;
+    discriminator
+}
+
+/// Generates the IDL from the Rust definitions.
+pub fn generate() -> Value {
+    let accounts = accounts();
+    let events = events();
+
+    let mut types = Vec::new();
+    types.extend(accounts.iter().map(|(name, _, fields)| struct_type(name, fields.clone())));
+    types.extend(events.iter().map(|(name, _, fields)| struct_type(name, fields.clone())));
+    types.extend(self::types());
+
+    json!({
+        "address": crate::ID.to_string(),
+        "metadata": {
+            "name": "spool",
+            "version": env!("CARGO_PKG_VERSION"),
+            "spec": "0.1.0",
+            "description": env!("CARGO_PKG_DESCRIPTION"),
+        },
+        "instructions": instructions(),
+        "accounts": accounts
+            .iter()
+            .map(|(name, ty, _)| json!({ "name": name, "discriminator": discriminator8(*ty as u8) }))
+            .collect::<Vec<_>>(),
+        "events": events
+            .iter()
+            .map(|(name, ty, _)| json!({ "name": name, "discriminator": discriminator8(*ty as u8) }))
+            .collect::<Vec<_>>(),
+        "errors": errors(),
+        "types": types,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        miner::MinerInstruction,
+        program::ProgramInstruction,
+        reel::ReelInstruction,
+        spool::SpoolInstruction,
+    };
+
+    fn discriminators(idl: &Value, key: &str) -> Vec<u64> {
+        idl[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["discriminator"][0].as_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_idl_matches_checked_in_copy() {
+        let checked_in: Value = serde_json::from_str(IDL_JSON).unwrap();
+        assert!(
+            generate() == checked_in,
+            "api/idl/spool.json is out of date, regenerate it with \
+             `cargo run -p spool-api --bin spool-idl > api/idl/spool.json`"
+        );
+    }
+
+    #[test]
+    fn test_idl_covers_every_instruction() {
+        let idl = generate();
+        let covered = discriminators(&idl, "instructions");
+
+        for value in 0..=u8::MAX {
+            let exists = ProgramInstruction::try_from(value)
+                .is_ok_and(|ix| ix != ProgramInstruction::Unknown)
+                || SpoolInstruction::try_from(value).is_ok()
+                || MinerInstruction::try_from(value).is_ok()
+                || ReelInstruction::try_from(value).is_ok();
+
+            assert_eq!(
+                exists,
+                covered.contains(&(value as u64)),
+                "instruction {value:#x} is missing from (or stale in) the IDL"
+            );
+        }
+    }
+
+    #[test]
+    fn test_idl_covers_every_account_and_event() {
+        let idl = generate();
+
+        let accounts = discriminators(&idl, "accounts");
+        for value in 1..=u8::MAX {
+            assert_eq!(
+                AccountType::try_from(value).is_ok(),
+                accounts.contains(&(value as u64)),
+                "account type {value} is missing from the IDL"
+            );
+        }
+
+        let events = discriminators(&idl, "events");
+        for value in 1..=u8::MAX {
+            assert_eq!(
+                EventType::try_from(value).is_ok(),
+                events.contains(&(value as u64)),
+                "event type {value} is missing from the IDL"
+            );
+        }
+    }
+}
@@ -11,10 +11,12 @@ pub enum ProgramInstruction {
     Unknown = 0,
     Initialize, // Initialize the program, setting up necessary accounts
     Airdrop,    // Airdrop tokens to the fee payer (devnet/localnet only)
+    ConfigureFaucet, // Set the faucet limits (admin only, devnet/localnet only)
 }
 
 instruction!(ProgramInstruction, Initialize);
 instruction!(ProgramInstruction, Airdrop);
+instruction!(ProgramInstruction, ConfigureFaucet);
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
@@ -26,6 +28,14 @@ pub struct Airdrop {
     pub amount: [u8; 8],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ConfigureFaucet {
+    pub cap: [u8; 8],
+    pub window_seconds: [u8; 8],
+    pub cooldown_seconds: [u8; 8],
+}
+
 pub fn build_initialize_ix(
     signer: Pubkey
 ) -> Instruction {
@@ -81,18 +91,51 @@ pub fn build_airdrop_ix(
 ) -> Instruction {
     let (mint_pda, _mint_bump) = mint_pda();
     let (treasury_pda, _treasury_bump) = treasury_pda();
+    let (faucet_claim_pda, _faucet_claim_bump) = faucet_claim_pda(signer);
+    let (faucet_config_pda, _faucet_config_bump) = faucet_config_pda();
 
     Instruction {
         program_id: crate::ID,
         accounts: vec![
             AccountMeta::new(signer, true),
             AccountMeta::new(beneficiary, false),
+            AccountMeta::new(faucet_claim_pda, false),
+            AccountMeta::new_readonly(faucet_config_pda, false),
             AccountMeta::new(mint_pda, false),
             AccountMeta::new(treasury_pda, false),
             AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
         ],
         data: Airdrop {
             amount: amount.to_le_bytes(),
         }.to_bytes(),
     }
 }
+
+/// Sets the faucet limits. The signer must be the genesis spool authority, the wallet that
+/// initialized the program.
+pub fn build_configure_faucet_ix(
+    signer: Pubkey,
+    cap: u64,
+    window_seconds: i64,
+    cooldown_seconds: i64,
+) -> Instruction {
+    let name = utils::to_name("genesis");
+    let (genesis_spool_pda, _genesis_spool_bump) = spool_pda(signer, &name);
+    let (faucet_config_pda, _faucet_config_bump) = faucet_config_pda();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(signer, true),
+            AccountMeta::new_readonly(genesis_spool_pda, false),
+            AccountMeta::new(faucet_config_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ConfigureFaucet {
+            cap: cap.to_le_bytes(),
+            window_seconds: window_seconds.to_le_bytes(),
+            cooldown_seconds: cooldown_seconds.to_le_bytes(),
+        }.to_bytes(),
+    }
+}
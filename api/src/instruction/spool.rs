@@ -9,12 +9,13 @@ use crate::{
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
 pub enum SpoolInstruction {
-    Create = 0x10,  // Create a new spool account
-    Write,          // Create a write head that can be used to write to the spool
-    Update,         // Update a segment of the spool
-    Finalize,       // Finalize the spool, making it immutable, ready for mining
-    SetHeader,      // Set the opaque header of the spool
-    Subsidize,      // Incentivize miners to store the spool on spoolnet
+    Create = 0x10,      // Create a new spool account
+    Write,              // Create a write head that can be used to write to the spool
+    Update,             // Update a segment of the spool
+    Finalize,           // Finalize the spool, making it immutable, ready for mining
+    SetHeader,          // Set the opaque header of the spool
+    Subsidize,          // Incentivize miners to store the spool on spoolnet
+    MintOwnershipToken, // Mint a 1-of-1 NFT that carries the spool authority
 }
 
 instruction!(SpoolInstruction, Create);
@@ -23,6 +24,7 @@ instruction!(SpoolInstruction, Update);
 instruction!(SpoolInstruction, Finalize);
 instruction!(SpoolInstruction, SetHeader);
 instruction!(SpoolInstruction, Subsidize);
+instruction!(SpoolInstruction, MintOwnershipToken);
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
@@ -61,20 +63,26 @@ pub struct Subsidize {
     pub amount: [u8; 8],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct MintOwnershipToken {}
+
 
 pub fn build_create_ix(
-    signer: Pubkey,
+    payer: Pubkey,
+    authority: Pubkey,
     name: &str,
 ) -> Instruction {
     let name = utils::to_name(name);
 
-    let (spool_address, _spool_bump) = spool_pda(signer, &name);
+    let (spool_address, _spool_bump) = spool_pda(authority, &name);
     let (writer_address, _writer_bump) = writer_pda(spool_address);
 
     Instruction {
         program_id: crate::ID,
         accounts: vec![
-            AccountMeta::new(signer, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(authority, true),
             AccountMeta::new(spool_address, false),
             AccountMeta::new(writer_address, false),
             AccountMeta::new_readonly(solana_program::system_program::ID, false),
@@ -153,7 +161,8 @@ pub fn build_update_ix(
 }
 
 pub fn build_finalize_ix(
-    signer: Pubkey, 
+    payer: Pubkey, 
+    authority: Pubkey, 
     spool: Pubkey,
     writer: Pubkey,
 ) -> Instruction {
@@ -161,7 +170,8 @@ pub fn build_finalize_ix(
     Instruction {
         program_id: crate::ID,
         accounts: vec![
-            AccountMeta::new(signer, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(authority, true),
             AccountMeta::new(spool, false),
             AccountMeta::new(writer, false),
             AccountMeta::new(ARCHIVE_ADDRESS, false),
@@ -193,3 +203,47 @@ pub fn build_subsidize_ix(
         }.to_bytes(),
     }
 }
+
+pub fn build_mint_ownership_token_ix(
+    payer: Pubkey,
+    authority: Pubkey,
+    spool: Pubkey,
+) -> Instruction {
+
+    let (mint_address, _mint_bump) = ownership_pda(spool);
+    let (metadata_address, _metadata_bump) = metadata_pda(mint_address);
+    let (edition_address, _edition_bump) = edition_pda(mint_address);
+    let ata_address = spl_associated_token_account::get_associated_token_address(
+        &authority, 
+        &mint_address
+    );
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new(spool, false),
+            AccountMeta::new(mint_address, false),
+            AccountMeta::new(metadata_address, false),
+            AccountMeta::new(edition_address, false),
+            AccountMeta::new(ata_address, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: MintOwnershipToken {}.to_bytes(),
+    }
+}
+
+/// Appends the holder's ownership token account to a write, update, set header or finalize
+/// instruction. Required once the spool authority has moved to an ownership token.
+pub fn with_ownership_token(
+    mut ix: Instruction,
+    token_account: Pubkey,
+) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(token_account, false));
+    ix
+}
@@ -1,6 +1,9 @@
 pub mod consts;
+pub mod cpi;
 pub mod error;
 pub mod event;
+#[cfg(not(target_os = "solana"))]
+pub mod idl;
 pub mod instruction;
 pub mod loaders;
 pub mod pda;
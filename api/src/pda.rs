@@ -83,6 +83,13 @@ pub fn metadata_pda(mint: Pubkey) -> (Pubkey, u8) {
     )
 }
 
+pub fn edition_pda(mint: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[METADATA, mpl_token_metadata::ID.as_ref(), mint.as_ref(), EDITION],
+        &mpl_token_metadata::ID,
+    )
+}
+
 pub fn spool_pda(authority: Pubkey, name: &[u8; NAME_LEN]) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[SPOOL, authority.as_ref(), name.as_ref()], &crate::id())
 }
@@ -91,6 +98,18 @@ pub fn writer_pda(spool: Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[WRITER, spool.as_ref()], &crate::id())
 }
 
+pub fn ownership_pda(spool: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OWNERSHIP, spool.as_ref()], &crate::id())
+}
+
+pub fn faucet_claim_pda(authority: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FAUCET, authority.as_ref()], &crate::id())
+}
+
+pub fn faucet_config_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FAUCET_CONFIG], &crate::id())
+}
+
 pub fn miner_pda(authority: Pubkey, name: [u8; NAME_LEN]) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[MINER, authority.as_ref(), name.as_ref()], &crate::id())
 }
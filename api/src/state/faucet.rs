@@ -0,0 +1,161 @@
+use steel::*;
+use crate::consts::*;
+use crate::state;
+use super::AccountType;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct FaucetClaim {
+    pub authority: Pubkey,
+
+    pub window_start:  i64,
+    pub claimed:       u64,
+    pub last_claim_at: i64,
+}
+
+/// Faucet limits set by the admin (the genesis spool authority).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct FaucetConfig {
+    pub cap:              u64,
+    pub window_seconds:   i64,
+    pub cooldown_seconds: i64,
+}
+
+impl FaucetConfig {
+    /// Limits that apply until the admin sets the faucet config.
+    pub const DEFAULT: Self = Self {
+        cap:              FAUCET_CAP,
+        window_seconds:   FAUCET_WINDOW_SECONDS,
+        cooldown_seconds: FAUCET_COOLDOWN_SECONDS,
+    };
+
+    /// Check the limits are usable: windows must have a length and cooldowns can't be negative.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.window_seconds > 0 && self.cooldown_seconds >= 0
+    }
+}
+
+impl FaucetClaim {
+    /// Check if the current window has ended, so the allowance resets.
+    #[inline]
+    pub fn window_expired(&self, config: &FaucetConfig, now: i64) -> bool {
+        now >= self.window_start.saturating_add(config.window_seconds)
+    }
+
+    /// Amount that can still be claimed in the window containing `now`.
+    #[inline]
+    pub fn remaining_allowance(&self, config: &FaucetConfig, now: i64) -> u64 {
+        if self.window_expired(config, now) {
+            config.cap
+        } else {
+            config.cap.saturating_sub(self.claimed)
+        }
+    }
+
+    /// Earliest time the next claim is allowed.
+    #[inline]
+    pub fn next_claim_at(&self, config: &FaucetConfig) -> i64 {
+        if self.last_claim_at == 0 {
+            0
+        } else {
+            self.last_claim_at.saturating_add(config.cooldown_seconds)
+        }
+    }
+
+    /// Check if `amount` can be claimed at `now`.
+    #[inline]
+    pub fn can_claim(&self, config: &FaucetConfig, amount: u64, now: i64) -> bool {
+        now >= self.next_claim_at(config) && amount <= self.remaining_allowance(config, now)
+    }
+
+    /// Record a claim of `amount` at `now`, starting a new window if needed.
+    #[inline]
+    pub fn record_claim(&mut self, config: &FaucetConfig, amount: u64, now: i64) {
+        if self.window_expired(config, now) {
+            self.window_start = now;
+            self.claimed = 0;
+        }
+
+        self.claimed = self.claimed.saturating_add(amount);
+        self.last_claim_at = now;
+    }
+}
+
+state!(AccountType, FaucetClaim);
+state!(AccountType, FaucetConfig);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim_at(window_start: i64, claimed: u64, last_claim_at: i64) -> FaucetClaim {
+        FaucetClaim {
+            authority: Pubkey::default(),
+            window_start,
+            claimed,
+            last_claim_at,
+        }
+    }
+
+    const CONFIG: FaucetConfig = FaucetConfig::DEFAULT;
+
+    #[test]
+    fn first_claim_has_full_allowance() {
+        let claim = FaucetClaim::zeroed();
+        assert_eq!(claim.remaining_allowance(&CONFIG, 1_000), FAUCET_CAP);
+        assert!(claim.can_claim(&CONFIG, FAUCET_CAP, 1_000));
+        assert!(!claim.can_claim(&CONFIG, FAUCET_CAP + 1, 1_000));
+    }
+
+    #[test]
+    fn cooldown_blocks_early_claims() {
+        let claim = claim_at(1_000, ONE_SPOOL, 1_000);
+        assert!(!claim.can_claim(&CONFIG, ONE_SPOOL, 1_000 + FAUCET_COOLDOWN_SECONDS - 1));
+        assert!(claim.can_claim(&CONFIG, ONE_SPOOL, 1_000 + FAUCET_COOLDOWN_SECONDS));
+    }
+
+    #[test]
+    fn cap_applies_within_window() {
+        let now = 1_000 + FAUCET_COOLDOWN_SECONDS;
+        let claim = claim_at(1_000, FAUCET_CAP - ONE_SPOOL, 1_000);
+        assert_eq!(claim.remaining_allowance(&CONFIG, now), ONE_SPOOL);
+        assert!(claim.can_claim(&CONFIG, ONE_SPOOL, now));
+        assert!(!claim.can_claim(&CONFIG, ONE_SPOOL + 1, now));
+    }
+
+    #[test]
+    fn allowance_resets_after_window() {
+        let now = 1_000 + FAUCET_WINDOW_SECONDS;
+        let mut claim = claim_at(1_000, FAUCET_CAP, 1_000);
+        assert_eq!(claim.remaining_allowance(&CONFIG, now), FAUCET_CAP);
+
+        claim.record_claim(&CONFIG, ONE_SPOOL, now);
+        assert_eq!(claim.window_start, now);
+        assert_eq!(claim.claimed, ONE_SPOOL);
+        assert_eq!(claim.last_claim_at, now);
+    }
+
+    #[test]
+    fn configured_limits_apply() {
+        let config = FaucetConfig {
+            cap: 5 * ONE_SPOOL,
+            window_seconds: 600,
+            cooldown_seconds: 0,
+        };
+
+        let claim = claim_at(1_000, 4 * ONE_SPOOL, 1_000);
+        assert_eq!(claim.remaining_allowance(&config, 1_000), ONE_SPOOL);
+        assert!(claim.can_claim(&config, ONE_SPOOL, 1_000));
+        assert!(!claim.can_claim(&config, 2 * ONE_SPOOL, 1_000));
+        assert_eq!(claim.remaining_allowance(&config, 1_600), 5 * ONE_SPOOL);
+    }
+
+    #[test]
+    fn config_validation() {
+        assert!(FaucetConfig::DEFAULT.is_valid());
+        assert!(!FaucetConfig { window_seconds: 0, ..FaucetConfig::DEFAULT }.is_valid());
+        assert!(!FaucetConfig { cooldown_seconds: -1, ..FaucetConfig::DEFAULT }.is_valid());
+    }
+}
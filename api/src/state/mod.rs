@@ -6,6 +6,7 @@ mod treasury;
 mod writer;
 mod miner;
 mod reel;
+mod faucet;
 
 pub use archive::*;
 pub use epoch::*;
@@ -15,6 +16,7 @@ pub use treasury::*;
 pub use writer::*;
 pub use miner::*;
 pub use reel::*;
+pub use faucet::*;
 
 use steel::*;
 
@@ -30,4 +32,6 @@ pub enum AccountType {
     Epoch,
     Block,
     Treasury,
+    FaucetClaim,
+    FaucetConfig,
 }
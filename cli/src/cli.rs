@@ -47,13 +47,65 @@ pub enum Commands {
         #[arg(help = "Spool account to read")]
         spool: String,
 
-        #[arg(short = 'o', long = "output", help = "Output file")]
+        #[arg(short = 'o', long = "output", help = "Output file (or directory, for directory spools)")]
         output: Option<String>,
+
+        #[arg(
+            long = "key",
+            value_name = "KEY_FILE",
+            help = "Decrypt the spool with a key file (or, without a value, a passphrase). Spools shared with your wallet need no key"
+        )]
+        key: Option<Option<String>>,
+
+        #[arg(
+            long = "strict",
+            help = "Fail instead of warning if the data read doesn't match the spool's merkle root"
+        )]
+        strict: bool,
+
+        #[arg(
+            long = "archive",
+            value_name = "[MINER@]URL",
+            help = "Archive node web RPC to read segments from before walking blocks (repeatable). Nodes that serve packed segments need their miner pubkey"
+        )]
+        archives: Vec<String>,
+
+        #[arg(
+            long = "strategy",
+            value_name = "STRATEGY",
+            default_value = "blocks",
+            help = "How to find segments on-chain: blocks (follow the write chain back from the tail slot) or signatures (fetch every transaction of the spool in parallel)"
+        )]
+        strategy: String,
+
+        #[arg(
+            long = "at-slot",
+            value_name = "SLOT",
+            conflicts_with_all = ["archives", "strict"],
+            help = "Read the spool as it was at the end of a slot. Not checked against the merkle root, which only matches the latest data"
+        )]
+        at_slot: Option<u64>,
+    },
+
+    History {
+        #[arg(help = "Spool account to list the changes of")]
+        spool: String,
+
+        #[arg(long = "segment", value_name = "NUMBER", help = "Only list the versions of one segment")]
+        segment: Option<u64>,
+
+        #[arg(
+            long = "strategy",
+            value_name = "STRATEGY",
+            default_value = "blocks",
+            help = "How to find segments on-chain: blocks or signatures (see read)"
+        )]
+        strategy: String,
     },
 
     Write {
         #[arg(
-            help = "File to write, message text, or remote URL",
+            help = "File or directory to write, message text, or remote URL",
             required_unless_present_any = ["filename", "message", "remote"],
             conflicts_with_all = ["message", "remote"]
         )]
@@ -67,8 +119,84 @@ pub enum Commands {
 
         #[arg(short = 'n', long = "spool-name", help = "Custom name for the spool (defaults to timestamp)")]
         spool_name: Option<String>,
+
+        #[arg(
+            long = "resume",
+            value_name = "SPOOL",
+            help = "Resume an interrupted upload of the same input (optionally, of a given spool)",
+            conflicts_with = "spool_name"
+        )]
+        resume: Option<Option<String>>,
+
+        #[arg(
+            long = "compression",
+            value_name = "ALGO",
+            default_value = "auto",
+            help = "Compression: auto, none, gzip, zstd, brotli or lz4"
+        )]
+        compression: String,
+
+        #[arg(long = "level", help = "Compression level (defaults to the codec's)")]
+        level: Option<u8>,
+
+        #[arg(
+            long = "encrypt",
+            value_name = "ALGO",
+            help = "Encrypt the data (aes-256-gcm, the default, or xchacha20-poly1305)"
+        )]
+        encrypt: Option<Option<String>>,
+
+        #[arg(
+            long = "key-file",
+            value_name = "KEY_FILE",
+            help = "Encryption key file (32 raw bytes or 64 hex characters), instead of a passphrase"
+        )]
+        key_file: Option<String>,
+
+        #[arg(
+            long = "recipient",
+            value_name = "PUBKEY",
+            help = "Encrypt for this wallet (repeatable), instead of a passphrase or key file",
+            requires = "encrypt",
+            conflicts_with = "key_file"
+        )]
+        recipients: Vec<String>,
+
+        #[arg(
+            long = "redundancy",
+            value_name = "DATA:PARITY",
+            help = "Add PARITY erasure-coded segments every DATA segments (e.g. 16:4), so reads can rebuild up to PARITY missing segments of each"
+        )]
+        redundancy: Option<String>,
+
+        #[arg(
+            long = "ignore",
+            value_name = "PATTERN",
+            help = "When writing a directory, skip files matching this gitignore-style pattern (repeatable). .gitignore and .spoolignore files are respected too",
+            requires = "filename"
+        )]
+        ignore: Vec<String>,
     },
 
+    Share {
+        #[arg(help = "Encrypted spool to share")]
+        spool: String,
+
+        #[arg(long = "recipient", value_name = "PUBKEY", required = true, help = "Wallet to share the spool with (repeatable)")]
+        recipients: Vec<String>,
+
+        #[arg(short = 'n', long = "spool-name", help = "Custom name for the envelope spool (defaults to timestamp)")]
+        spool_name: Option<String>,
+    },
+
+    Submit {
+        #[arg(help = "Signed upload bundle to broadcast (see offline plan)")]
+        bundle: String,
+    },
+
+    #[command(subcommand)]
+    Offline(OfflineCommands),
+
 
     // Miner Commands
 
@@ -117,6 +245,16 @@ pub enum Commands {
         amount: u64,
     },
 
+    #[command(hide = true)]
+    ConfigureFaucet {
+        #[arg(help = "Maximum amount of tokens a wallet can claim per window")]
+        cap: u64,
+        #[arg(help = "Length of a window in seconds")]
+        window: i64,
+        #[arg(help = "Minimum time between two claims in seconds")]
+        cooldown: i64,
+    },
+
     // Store Management Commands
 
     #[command(subcommand)]
@@ -177,6 +315,60 @@ pub enum SnapshotCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum OfflineCommands {
+    Plan {
+        #[arg(help = "File to write")]
+        filename: String,
+
+        #[arg(long = "authority", value_name = "PUBKEY", help = "Wallet that will own the spool and sign the bundle offline")]
+        authority: String,
+
+        #[arg(short = 'o', long = "output", help = "Output bundle file (defaults to <spool-name>.bundle.json)")]
+        output: Option<String>,
+
+        #[arg(short = 'n', long = "spool-name", help = "Custom name for the spool (defaults to timestamp)")]
+        spool_name: Option<String>,
+
+        #[arg(
+            long = "compression",
+            value_name = "ALGO",
+            default_value = "auto",
+            help = "Compression: auto, none, gzip, zstd, brotli or lz4"
+        )]
+        compression: String,
+
+        #[arg(long = "level", help = "Compression level (defaults to the codec's)")]
+        level: Option<u8>,
+
+        #[arg(
+            long = "encrypt",
+            value_name = "ALGO",
+            help = "Encrypt the data (aes-256-gcm, the default, or xchacha20-poly1305)"
+        )]
+        encrypt: Option<Option<String>>,
+
+        #[arg(
+            long = "key-file",
+            value_name = "KEY_FILE",
+            help = "Encryption key file (32 raw bytes or 64 hex characters), instead of a passphrase"
+        )]
+        key_file: Option<String>,
+
+        #[arg(
+            long = "redundancy",
+            value_name = "DATA:PARITY",
+            help = "Add PARITY erasure-coded segments every DATA segments (e.g. 16:4)"
+        )]
+        redundancy: Option<String>,
+    },
+
+    Sign {
+        #[arg(help = "Upload bundle to sign with the keypair, in place")]
+        bundle: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum InfoCommands {
     Spool {
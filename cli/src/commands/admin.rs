@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone};
 use solana_sdk::signer::Signer;
 
 use crate::cli::{Cli, Context, Commands};
@@ -6,7 +7,7 @@ use crate::log;
 
 use spool_api::consts::ONE_SPOOL;
 use spool_client::{
-    program::{initialize, airdrop_tokens}, 
+    program::{initialize, airdrop_tokens, configure_faucet, get_faucet_allowance, FaucetAllowance}, 
     utils::create_ata
 };
 
@@ -29,15 +30,44 @@ pub async fn handle_admin_commands(cli:Cli, context: Context) -> Result<()> {
                 .await
                 .map_err(|e| anyhow!("Failed to create/ensure ATA for payer {}: {}", context.payer().pubkey(), e))?;
 
-            let sig = airdrop_tokens(
+            let res = airdrop_tokens(
                 context.rpc(),
                 context.payer(),
                 beneficiary_ata,
                 amount * ONE_SPOOL,
-            ).await?;
+            ).await;
+
+            let allowance = get_faucet_allowance(context.rpc(), context.payer().pubkey()).await?;
+
+            // Show the allowance either way, so a rate-limited wallet knows when to retry
+            let sig = match res {
+                Ok(sig) => sig,
+                Err(e) => {
+                    log::print_section_header("Airdrop Failed");
+                    print_allowance(&allowance);
+                    log::print_divider();
+                    return Err(e);
+                }
+            };
 
             log::print_section_header("Airdrop Completed");
             log::print_message(&format!("Signature: {sig}"));
+            print_allowance(&allowance);
+            log::print_divider();
+        },
+        Commands::ConfigureFaucet { cap, window, cooldown } => {
+
+            let sig = configure_faucet(
+                context.rpc(),
+                context.payer(),
+                cap * ONE_SPOOL,
+                window,
+                cooldown,
+            ).await?;
+
+            log::print_section_header("Faucet Configured");
+            log::print_message(&format!("Signature: {sig}"));
+            log::print_message(&format!("Cap: {cap} SPOOL per {window}s, cooldown {cooldown}s"));
             log::print_divider();
         },
         _ => {}
@@ -46,3 +76,24 @@ pub async fn handle_admin_commands(cli:Cli, context: Context) -> Result<()> {
     Ok(())
 }
 
+fn print_allowance(allowance: &FaucetAllowance) {
+    log::print_message(&format!(
+        "Remaining allowance: {} SPOOL",
+        allowance.remaining / ONE_SPOOL
+    ));
+
+    if let Some(resets_at) = allowance.resets_at {
+        log::print_message(&format!("Allowance resets at: {}", format_time(resets_at)));
+    }
+
+    log::print_message(&format!("Next claim allowed at: {}", format_time(allowance.next_claim_at)));
+}
+
+fn format_time(unix_timestamp: i64) -> String {
+    Local
+        .timestamp_opt(unix_timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| unix_timestamp.to_string())
+}
+
@@ -0,0 +1,337 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use indicatif::{ProgressBar, ProgressStyle};
+use mime_guess::MimeGuess;
+use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use spool_api::prelude::*;
+use spool_client::{
+    decode_spool_with_key, directory_file_name, get_ata_address, get_spool_account,
+    get_token_balance, max_payload_len, needs_manifest, open_envelope, prefixed_segment_count,
+    Directory, DirectoryEntry, ErasureParams, MimeType, SpoolClient, SpoolFlags, SpoolHeader,
+    SpoolKey, UploadJournal,
+};
+
+use crate::cli::{Cli, Commands, Context};
+use crate::commands::read::SpoolReader;
+use crate::commands::write::{
+    confirm_proceed, encode_payload, new_header, print_split_resume_hint, setup_progress_bar,
+    split_rent,
+};
+use crate::log;
+use crate::utils::{encryption_key, journal_dir, mime_to_type};
+
+/// Ignore files honored when walking a directory, next to `.gitignore`.
+const SPOOLIGNORE: &str = ".spoolignore";
+
+/// Spools read at the same time when restoring a directory.
+const RESTORE_CONCURRENCY: usize = 8;
+
+/// Uploads every file of a directory to a spool of its own (identical files share one), then a
+/// directory spool listing them by path.
+pub async fn handle_directory_write(cli: Cli, context: Context) -> Result<()> {
+    if let Commands::Write {
+        filename: Some(ref root),
+        ref spool_name,
+        ref resume,
+        ref compression,
+        level,
+        ref encrypt,
+        ref key_file,
+        ref recipients,
+        ref redundancy,
+        ref ignore,
+        ..
+    } = cli.command
+    {
+        if resume.is_some() {
+            bail!("Directory uploads are resumed by writing them again with the same --spool-name");
+        }
+
+        let root = Path::new(root);
+        let mut files = collect_files(root, ignore)?;
+        if files.is_empty() {
+            bail!("No files to write in {}", root.display());
+        }
+
+        // Identical files are uploaded once
+        let mut unique: Vec<&(DirectoryEntry, PathBuf)> = Vec::new();
+        for file in &files {
+            if !unique.iter().any(|(entry, _)| entry.hash == file.0.hash) {
+                unique.push(file);
+            }
+        }
+
+        let mut recipients = recipients
+            .iter()
+            .map(|r| Pubkey::from_str(r).map_err(|_| anyhow!("Invalid recipient: {}", r)))
+            .collect::<Result<Vec<_>>>()?;
+        if !recipients.is_empty() {
+            // The payer is always a recipient, so it can share the spools later
+            recipients.insert(0, context.payer.pubkey());
+        }
+
+        let spool_name = spool_name
+            .clone()
+            .unwrap_or_else(|| Utc::now().timestamp().to_string());
+
+        log::print_section_header("Directory Write");
+        log::print_message(&format!("Source: {}", root.display()));
+        log::print_message(&format!("Spool Name: {spool_name}"));
+        log::print_count(&format!("Files: {} ({} unique)", files.len(), unique.len()));
+        log::print_count(&format!(
+            "Total Size: {} bytes",
+            files.iter().map(|(entry, _)| entry.len).sum::<u64>()
+        ));
+        log::print_divider();
+
+        if !confirm_proceed()? {
+            log::print_error("Write operation cancelled");
+            return Ok(());
+        }
+
+        // One key for the whole tree: a passphrase is asked once and its salt shared by all spools
+        let mut template = new_header(MimeType::Unknown, compression, level, encrypt, redundancy.as_deref())?;
+        let key = match recipients.is_empty() {
+            true => encryption_key(&mut template, key_file.as_deref())?,
+            false => None,
+        };
+
+        let Context { rpc, payer, .. } = context;
+        let client = SpoolClient::new(rpc, payer);
+
+        let required_rent: u64 = unique
+            .iter()
+            .map(|(entry, _)| estimated_rent(entry.len as usize, template.erasure()))
+            .sum::<u64>()
+            + estimated_rent(
+                files.iter().map(|(entry, _)| entry.path.len() + 75).sum(),
+                template.erasure(),
+            );
+
+        let payer_ata = get_ata_address(&client.pubkey());
+        if get_token_balance(client.rpc(), &payer_ata).await? < required_rent {
+            log::print_error("Insufficient SPOOL tokens in payer's ATA to pay for rent.");
+            return Ok(());
+        }
+
+        let journal_dir = journal_dir();
+        let pb = setup_progress_bar(unique.len() as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
+                .expect("Failed to set progress style"),
+        );
+
+        let mut addresses: HashMap<[u8; 32], Pubkey> = HashMap::new();
+        for (entry, source) in unique {
+            pb.set_message(entry.path.clone());
+
+            let data = fs::read(source)?;
+            entry
+                .check(&data)
+                .map_err(|_| anyhow!("{} changed during the upload", source.display()))?;
+
+            let mut header = template;
+            header.mime_type = entry.mime_type.into();
+            let encoded = encode_payload(&data, &mut header, client.signer(), &recipients, key.as_ref())?;
+
+            let name = directory_file_name(&spool_name, &entry.hash);
+            let address = upload_spool(&client, &journal_dir, &name, &header, &encoded)
+                .await
+                .inspect_err(|_| {
+                    pb.abandon();
+                    print_split_resume_hint(&spool_name);
+                })?;
+
+            addresses.insert(entry.hash, address);
+            pb.inc(1);
+        }
+
+        pb.set_message("writing directory spool...");
+        for (entry, _) in files.iter_mut() {
+            entry.address = addresses[&entry.hash];
+        }
+        let directory = Directory::new(files.into_iter().map(|(entry, _)| entry).collect())?;
+
+        let mut header = template;
+        header.flags |= SpoolFlags::Directory as u8;
+        let encoded = encode_payload(&directory.to_bytes(), &mut header, client.signer(), &recipients, key.as_ref())?;
+
+        let spool_address = upload_spool(&client, &journal_dir, &spool_name, &header, &encoded)
+            .await
+            .inspect_err(|_| {
+                pb.abandon();
+                print_split_resume_hint(&spool_name);
+            })?;
+        pb.finish_with_message("");
+
+        log::print_divider();
+        log::print_count(&format!("Directory Spool: {spool_address}"));
+        log::print_info("To restore the directory, run:");
+        log::print_title(&format!("spoolnet read {spool_address} -o <DIR>"));
+        log::print_divider();
+    }
+    Ok(())
+}
+
+/// Restores the files of a directory spool under `output`, reading up to `RESTORE_CONCURRENCY`
+/// spools at a time. Files shared with the payer wallet are opened with its keypair, the others
+/// with `key`.
+pub async fn restore_directory(
+    reader: &SpoolReader,
+    payer: &Keypair,
+    directory: &Directory,
+    output: Option<String>,
+    key: Option<&SpoolKey>,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let root = PathBuf::from(
+        output.ok_or_else(|| anyhow!("The spool is a directory, pass -o <DIR> to restore it"))?,
+    );
+
+    // Every path is checked before anything is written
+    let targets = directory
+        .entries
+        .iter()
+        .map(|entry| entry.path_under(&root))
+        .collect::<Result<Vec<_>>>()?;
+
+    let addresses = directory.addresses();
+    pb.set_length(addresses.len() as u64);
+    pb.set_position(0);
+    pb.set_message("reading file spools...");
+
+    let semaphore = Arc::new(Semaphore::new(RESTORE_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for address in addresses {
+        let reader = reader.clone();
+        let semaphore = semaphore.clone();
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let (spool_data, _) = get_spool_account(&reader.rpc, &address).await?;
+            let header = *SpoolHeader::try_from_bytes(&spool_data.header)?;
+            let (header, data) = reader.read_payload(&address, &header, &ProgressBar::hidden()).await?;
+            Ok::<_, anyhow::Error>((address, header, data))
+        });
+    }
+
+    let mut contents: HashMap<Pubkey, Vec<u8>> = HashMap::new();
+    while let Some(result) = tasks.join_next().await {
+        let (address, header, data) = result??;
+
+        let file_key = match header.has_flag(SpoolFlags::Envelope) {
+            true => Some(open_envelope(data.clone(), &header, payer)?.0),
+            false => key.cloned(),
+        };
+        contents.insert(address, decode_spool_with_key(data, &header, file_key.as_ref())?);
+        pb.inc(1);
+    }
+
+    for (entry, target) in directory.entries.iter().zip(&targets) {
+        let data = &contents[&entry.address];
+        entry.check(data)?;
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, data)?;
+    }
+    pb.finish_and_clear();
+
+    log::print_divider();
+    log::print_message(&format!(
+        "Restored {} files ({} bytes) to: {}",
+        directory.entries.len(),
+        directory.total_len(),
+        root.display()
+    ));
+
+    Ok(())
+}
+
+/// Walks `root`, skipping hidden files, files ignored by `.gitignore` or `.spoolignore` files and
+/// files matching one of the `ignore` patterns. Entries are returned with their source path and
+/// without a spool address yet, sorted by path.
+fn collect_files(root: &Path, ignore: &[String]) -> Result<Vec<(DirectoryEntry, PathBuf)>> {
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in ignore {
+        overrides.add(&format!("!{pattern}"))?;
+    }
+
+    let walker = WalkBuilder::new(root)
+        .overrides(overrides.build()?)
+        .add_custom_ignore_filename(SPOOLIGNORE)
+        .require_git(false)
+        .build();
+
+    let mut files = Vec::new();
+    for dir_entry in walker {
+        let dir_entry = dir_entry?;
+        if !dir_entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+            continue;
+        }
+
+        let source = dir_entry.into_path();
+        let relative = source.strip_prefix(root)?;
+        let path = relative
+            .components()
+            .map(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Path is not valid UTF-8: {}", relative.display()))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join("/");
+
+        let data = fs::read(&source)?;
+        let mime_type = mime_to_type(&MimeGuess::from_path(&source).first_or_octet_stream());
+        files.push((DirectoryEntry::new(path, Pubkey::default(), mime_type, &data), source));
+    }
+
+    files.sort_by(|a, b| a.0.path.cmp(&b.0.path));
+    Ok(files)
+}
+
+/// Uploads an encoded payload to the spool `name`, split across part spools if it is too large.
+/// Spools finalized by an earlier run are kept as they are, unfinished ones are resumed.
+async fn upload_spool(
+    client: &SpoolClient,
+    journal_dir: &Path,
+    name: &str,
+    header: &SpoolHeader,
+    encoded: &[u8],
+) -> Result<Pubkey> {
+    let (spool_address, _) = spool_pda(client.pubkey(), &to_name(name));
+    if let Some(spool) = client.get_spool_account_if_exists(&spool_address).await? {
+        if spool.state == u64::from(SpoolState::Finalized) {
+            return Ok(spool_address);
+        }
+    }
+
+    if needs_manifest(header.data_len as usize, header.erasure()) {
+        client.upload_manifest(journal_dir, name, header, encoded, |_| {}).await
+    } else if UploadJournal::path(journal_dir, &spool_address).exists() {
+        client.resume_upload(journal_dir, &spool_address, encoded, |_| {}).await
+    } else {
+        client.upload(journal_dir, name, header, encoded, |_| {}).await
+    }
+}
+
+/// Rent for a file of `len` bytes, before compression, with `erasure` coding.
+fn estimated_rent(len: usize, erasure: Option<ErasureParams>) -> u64 {
+    if needs_manifest(len, erasure) {
+        split_rent(len, len.div_ceil(max_payload_len(erasure)), erasure)
+    } else {
+        min_finalization_rent(prefixed_segment_count(len.max(1) as u64, erasure))
+    }
+}
@@ -1,6 +1,9 @@
 pub mod admin;
 pub mod read;
 pub mod write;
+pub mod share;
+pub mod offline;
+pub mod directory;
 pub mod info;
 pub mod snapshot;
 pub mod network;
@@ -0,0 +1,160 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use std::path::Path;
+use std::str::FromStr;
+
+use spool_client::{needs_manifest, SpoolBundle, SpoolClient, UploadProgress};
+
+use crate::cli::{Cli, Commands, Context, OfflineCommands};
+use crate::commands::write::{confirm_proceed, encode_payload, new_header, process_input, setup_progress_bar};
+use crate::log;
+use crate::utils::{encryption_key, mime_to_type};
+
+pub async fn handle_offline_commands(cli: Cli, context: Context) -> Result<()> {
+    if let Commands::Offline(ref command) = cli.command {
+        match command {
+            OfflineCommands::Plan {
+                filename,
+                authority,
+                output,
+                spool_name,
+                compression,
+                level,
+                encrypt,
+                key_file,
+                redundancy,
+            } => {
+                let authority = Pubkey::from_str(authority)
+                    .map_err(|_| anyhow!("Invalid authority: {}", authority))?;
+                let spool_name = spool_name
+                    .clone()
+                    .unwrap_or_else(|| Utc::now().timestamp().to_string());
+                let output = output
+                    .clone()
+                    .unwrap_or_else(|| format!("{spool_name}.bundle.json"));
+
+                let (data, source, mime) = process_input(Some(filename.clone()), None, None).await?;
+                let mut header =
+                    new_header(mime_to_type(&mime), compression, *level, encrypt, redundancy.as_deref())?;
+                let key = encryption_key(&mut header, key_file.as_deref())?;
+                let encoded = encode_payload(&data, &mut header, &context.payer, &[], key.as_ref())?;
+
+                if needs_manifest(encoded.len(), header.erasure()) {
+                    bail!("{} doesn't fit in a single spool, which is all a bundle can upload", source);
+                }
+
+                let Context { rpc, payer, .. } = context;
+                let client = SpoolClient::new(rpc, payer);
+                let transactions = client.offline_transaction_count(authority, &spool_name, encoded.len());
+
+                log::print_message(&format!("Source: {source}"));
+                log::print_message(&format!("Spool Name: {spool_name}"));
+                log::print_message(&format!("Authority: {authority}"));
+                log::print_count(&format!("Transactions: {transactions} (one nonce account each)"));
+                log::print_divider();
+
+                if !confirm_proceed()? {
+                    log::print_error("Offline plan cancelled");
+                    return Ok(());
+                }
+
+                let pb = setup_progress_bar(0);
+                pb.set_message("Creating nonce accounts (please wait)...");
+                let res = async {
+                    let nonce_accounts = client.create_nonce_accounts(authority, transactions).await?;
+                    pb.set_message("Planning transactions...");
+                    client
+                        .plan_offline_upload(authority, &spool_name, &header, &encoded, &nonce_accounts)
+                        .await
+                }
+                .await;
+
+                let bundle = match res {
+                    Ok(bundle) => bundle,
+                    Err(e) => {
+                        pb.abandon();
+                        return Err(e);
+                    }
+                };
+                bundle.save(Path::new(&output))?;
+                pb.finish_with_message("");
+
+                log::print_divider();
+                log::print_info(&format!("Bundle written to {output}, sign it with the authority's keypair:"));
+                log::print_title(&format!("spoolnet offline sign {output} -k <AUTHORITY_KEYPAIR>"));
+                log::print_divider();
+            }
+
+            OfflineCommands::Sign { bundle: path } => {
+                let mut bundle = SpoolBundle::load(Path::new(path))?;
+                let signer: &dyn Signer = &context.payer;
+
+                let signed = bundle.sign(signer)?;
+                if signed == 0 {
+                    bail!("No transaction of the bundle needs a signature from {}", signer.pubkey());
+                }
+                bundle.save(Path::new(path))?;
+                log::print_message(&format!("Signed {signed} transaction(s)"));
+
+                let missing = bundle.missing_signers()?;
+                log::print_divider();
+                if missing.is_empty() {
+                    log::print_info("The bundle is fully signed, to broadcast it run:");
+                    log::print_title(&format!("spoolnet submit {path}"));
+                } else {
+                    for signer in missing {
+                        log::print_info(&format!("Still needs a signature from {signer}"));
+                    }
+                }
+                log::print_divider();
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_submit_command(cli: Cli, context: Context) -> Result<()> {
+    if let Commands::Submit { bundle } = cli.command {
+        let bundle = SpoolBundle::load(Path::new(&bundle))?;
+        let missing = bundle.missing_signers()?;
+        if !missing.is_empty() {
+            bail!("The bundle still needs signatures from: {:?}", missing);
+        }
+
+        log::print_message(&format!("Submitting {} transaction(s) for spool {}", bundle.transactions.len(), bundle.spool_address));
+        log::print_divider();
+
+        let Context { rpc, payer, .. } = context;
+        let client = SpoolClient::new(rpc, payer);
+
+        let pb = setup_progress_bar(0);
+        pb.set_message("Creating spool (please wait)...");
+
+        let progress_pb = pb.clone();
+        let progress = move |progress| match progress {
+            UploadProgress::Created => progress_pb.set_message("writing segments..."),
+            UploadProgress::Waiting => progress_pb.set_message("waiting for writes to land..."),
+            UploadProgress::Verifying => progress_pb.set_message("verifying uploaded data..."),
+            UploadProgress::Finalizing => progress_pb.set_message("finalizing spool..."),
+            _ => {}
+        };
+
+        let spool_address = match client.submit_bundle(&bundle, progress).await {
+            Ok(address) => address,
+            Err(e) => {
+                pb.abandon();
+                log::print_info("Submitting the same bundle again skips the transactions that landed");
+                return Err(e);
+            }
+        };
+
+        pb.finish_with_message("");
+
+        log::print_divider();
+        log::print_info("To read the spool, run:");
+        log::print_title(&format!("spoolnet read {spool_address}"));
+        log::print_divider();
+    }
+    Ok(())
+}
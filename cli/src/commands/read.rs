@@ -1,50 +1,89 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use num_enum::TryFromPrimitive;
-use solana_sdk::pubkey::Pubkey;
+use reqwest::Client as HttpClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::hash, pubkey::Pubkey};
 use std::str::FromStr;
-use tokio::{task, time::Duration};
+use std::sync::Arc;
+use tokio::{task::{self, JoinSet}, time::Duration};
 
 use crate::cli::{Cli, Context, Commands};
+use crate::commands::directory::restore_directory;
 use crate::log;
-use crate::utils::write_output;
+use crate::utils::{decryption_key, write_output};
 
+use spool_api::state::Spool;
 use spool_client::{
-    decode_spool, finalize_read, get_spool_account, init_read, process_next_block, MimeType,
+    collect_spool_segments, collect_spool_segments_at, decode_spool, decode_spool_with_key,
+    finalize_read, get_spool_account, open_envelope, read_manifest, read_spool_history,
+    recover_segments, verify_read, ArchiveNode, Directory, MimeType, ReadStrategy, SegmentChange, SpoolFlags,
     SpoolHeader,
 };
 
 pub async fn handle_read_command(cli: Cli, context: Context) -> Result<()> {
-    if let Commands::Read { spool, output } = cli.command {
+    if let Commands::Read { spool, output, key, strict, archives, strategy, at_slot } = cli.command {
         let spool_address = Pubkey::from_str(&spool)
-            .map_err(|_| anyhow::anyhow!("Invalid spool address: {}", spool))?;
+            .map_err(|_| anyhow!("Invalid spool address: {}", spool))?;
 
-        log::print_message("Reading spool...");
+        let reader = SpoolReader {
+            rpc: context.rpc().clone(),
+            http: HttpClient::new(),
+            archives: archives
+                .iter()
+                .map(|node| ArchiveNode::from_str(node))
+                .collect::<Result<_>>()?,
+            strategy: ReadStrategy::from_str(&strategy)?,
+            strict,
+            at_slot,
+        };
+
+        match at_slot {
+            Some(slot) => log::print_message(&format!("Reading spool as of slot {slot}...")),
+            None => log::print_message("Reading spool..."),
+        }
         log::print_divider();
 
         let pb = setup_progress_bar();
 
         pb.set_message("Fetching spool metadata...");
         let (spool_data, _) = get_spool_account(context.rpc(), &spool_address).await?;
-        let header = SpoolHeader::try_from_bytes(&spool_data.header)?;
+        let mut header = *SpoolHeader::try_from_bytes(&spool_data.header)?;
 
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
-                .expect("Failed to set progress style"),
-        );
-        pb.set_length(spool_data.total_segments);
-        pb.set_position(0);
-        pb.set_message("");
+        // Spools shared with the payer wallet are opened with its keypair
+        let use_envelope = header.has_flag(SpoolFlags::Envelope) && key.is_none();
+
+        // Ask for the key before the (possibly long) read
+        let mut key = if use_envelope {
+            None
+        } else {
+            pb.suspend(|| decryption_key(&header, key))?
+        };
 
-        let mut state = init_read(spool_data.tail_slot);
+        let (payload_header, mut data) = reader.read_payload(&spool_address, &header, &pb).await?;
+        header = payload_header;
 
-        while process_next_block(context.rpc(), &spool_address, &mut state).await? {
-            pb.set_position(state.segments_len() as u64);
+        if use_envelope {
+            let (content_key, reference) = open_envelope(data.clone(), &header, &context.payer)?;
+
+            if let Some(reference) = reference {
+                pb.println(format!("Shared spool: {reference}"));
+                let (reference_data, _) = get_spool_account(context.rpc(), &reference).await?;
+                let reference_header = *SpoolHeader::try_from_bytes(&reference_data.header)?;
+                (header, data) = reader.read_payload(&reference, &reference_header, &pb).await?;
+            }
+            key = Some(content_key);
         }
 
-        let data = finalize_read(state)?;
-        let result = decode_spool(data, header)?;
+        let result = decode_spool_with_key(data, &header, key.as_ref()).map_err(|e| match at_slot {
+            Some(slot) => anyhow!("Failed to decode the spool as of slot {} (was it fully written then?): {}", slot, e),
+            None => e,
+        })?;
+
+        if header.has_flag(SpoolFlags::Directory) {
+            let directory = Directory::parse(&result)?;
+            return restore_directory(&reader, &context.payer, &directory, output, key.as_ref(), &pb).await;
+        }
 
         let mime_type_enum =
             MimeType::try_from_primitive(header.mime_type).unwrap_or(MimeType::Unknown);
@@ -57,6 +96,215 @@ pub async fn handle_read_command(cli: Cli, context: Context) -> Result<()> {
     Ok(())
 }
 
+pub async fn handle_history_command(cli: Cli, context: Context) -> Result<()> {
+    if let Commands::History { spool, segment, strategy } = cli.command {
+        let spool_address = Pubkey::from_str(&spool)
+            .map_err(|_| anyhow!("Invalid spool address: {}", spool))?;
+        let strategy = ReadStrategy::from_str(&strategy)?;
+
+        log::print_message("Reading spool history...");
+        log::print_divider();
+
+        let pb = setup_progress_bar();
+        let (spool_data, _) = get_spool_account(context.rpc(), &spool_address).await?;
+
+        let history = read_spool_history(context.rpc(), strategy, &spool_address, &spool_data, |versions| {
+            pb.set_message(format!("{versions} segment versions found"))
+        })
+        .await?;
+        pb.finish_and_clear();
+
+        let segments: Vec<u64> = match segment {
+            Some(segment) => vec![segment],
+            None => history.segment_numbers().collect(),
+        };
+
+        for segment_number in segments {
+            log::print_section_header(&format!("Segment {segment_number}"));
+
+            let versions = history.versions(segment_number);
+            if versions.is_empty() {
+                log::print_info("No versions found");
+            }
+
+            for version in versions {
+                let change = match version.change {
+                    SegmentChange::Write => "write",
+                    SegmentChange::Update => "update",
+                };
+                log::print_message(&format!(
+                    "Slot {} (tx {}): {:<6} {}",
+                    version.slot,
+                    version.tx_index,
+                    change,
+                    hash(&version.data)
+                ));
+            }
+        }
+
+        log::print_divider();
+        log::print_count(&format!(
+            "{} versions of {} segments over {} slots",
+            history.len(),
+            history.segment_numbers().count(),
+            history.slots().len()
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the raw segments of spools from archive nodes and the chain.
+#[derive(Clone)]
+pub(crate) struct SpoolReader {
+    pub(crate) rpc: Arc<RpcClient>,
+    http: HttpClient,
+    archives: Vec<ArchiveNode>,
+    strategy: ReadStrategy,
+    strict: bool,
+    at_slot: Option<u64>,
+}
+
+impl SpoolReader {
+    /// Reads the raw data of a spool with its `header`, showing progress on `pb`. Manifest
+    /// spools are resolved: their payload is stitched from the part spools and returned with the
+    /// header to decode it with.
+    pub(crate) async fn read_payload(
+        &self,
+        spool_address: &Pubkey,
+        header: &SpoolHeader,
+        pb: &ProgressBar,
+    ) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (spool_data, _) = get_spool_account(&self.rpc, spool_address).await?;
+
+        set_bar_style(pb);
+        pb.set_length(spool_data.total_segments);
+        pb.set_position(0);
+        pb.set_message("");
+
+        let data = self
+            .read(spool_address, &spool_data, pb, |segments| pb.set_position(segments as u64))
+            .await?;
+
+        if !header.has_flag(SpoolFlags::Manifest) {
+            return Ok((*header, data));
+        }
+
+        let manifest = read_manifest(data, header)?;
+        pb.set_length(manifest.parts.len() as u64);
+        pb.set_position(0);
+        pb.set_message("reading part spools...");
+
+        let mut tasks = JoinSet::new();
+        for (index, part) in manifest.parts.iter().enumerate() {
+            let reader = self.clone();
+            let pb = pb.clone();
+            let part_address = part.address;
+
+            tasks.spawn(async move {
+                let (part_data, _) = get_spool_account(&reader.rpc, &part_address).await?;
+                let part_header = *SpoolHeader::try_from_bytes(&part_data.header)?;
+                if part_header.has_flag(SpoolFlags::Manifest) {
+                    bail!("Part spool {} is a manifest itself", part_address);
+                }
+
+                let data = reader.read(&part_address, &part_data, &pb, |_| {}).await?;
+                Ok((index, decode_spool(data, &part_header)?))
+            });
+        }
+
+        let mut parts = vec![Vec::new(); manifest.parts.len()];
+        while let Some(result) = tasks.join_next().await {
+            let (index, data) = result??;
+            parts[index] = data;
+            pb.inc(1);
+        }
+
+        Ok((manifest.payload_header(header), manifest.stitch(parts)?))
+    }
+
+    /// Reads the raw segments of a spool and checks them against the merkle root of the spool.
+    /// Mismatches are reported on `pb`, or fail the read if `strict`. Reads at a slot only use
+    /// the chain and aren't checked. Segments missing from erasure-coded spools are rebuilt from
+    /// parity, and reported on `pb`.
+    async fn read(
+        &self,
+        spool_address: &Pubkey,
+        spool_data: &Spool,
+        pb: &ProgressBar,
+        on_progress: impl Fn(usize),
+    ) -> Result<Vec<u8>> {
+        let data = self.read_segments(spool_address, spool_data, pb, on_progress).await?;
+
+        let header = SpoolHeader::try_from_bytes(&spool_data.header)?;
+        if !header.has_flag(SpoolFlags::ErasureCoded) {
+            return Ok(data);
+        }
+
+        let recovery = recover_segments(&data, header)?;
+        if !recovery.recovered.is_empty() {
+            pb.println(format!(
+                "Recovered {} missing segments of spool {} from parity",
+                recovery.recovered.len(),
+                spool_address
+            ));
+        }
+        Ok(recovery.data)
+    }
+
+    async fn read_segments(
+        &self,
+        spool_address: &Pubkey,
+        spool_data: &Spool,
+        pb: &ProgressBar,
+        on_progress: impl Fn(usize),
+    ) -> Result<Vec<u8>> {
+        if let Some(slot) = self.at_slot {
+            let state = collect_spool_segments_at(
+                &self.rpc,
+                self.strategy,
+                spool_address,
+                spool_data,
+                slot,
+                on_progress,
+            )
+            .await?;
+            return finalize_read(state);
+        }
+
+        let state = collect_spool_segments(
+            &self.rpc,
+            &self.http,
+            &self.archives,
+            self.strategy,
+            spool_address,
+            spool_data,
+            on_progress,
+        )
+        .await?;
+
+        let report = verify_read(spool_address, spool_data, &state.segments)?;
+        if !report.is_ok() {
+            if self.strict {
+                bail!("Spool {} doesn't match its merkle root:\n{}", spool_address, report);
+            }
+            pb.suspend(|| {
+                log::print_error(&format!("Spool {spool_address} doesn't match its merkle root:"));
+                log::print_info(&report.to_string());
+            });
+        }
+
+        finalize_read(state)
+    }
+}
+
+fn set_bar_style(pb: &ProgressBar) {
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
+            .expect("Failed to set progress style"),
+    );
+}
+
 fn setup_progress_bar() -> ProgressBar {
     let pb = ProgressBar::new_spinner();
     pb.set_style(
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use indicatif::{ProgressBar, ProgressStyle};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tokio::{task, time::Duration};
+
+use spool_client::{SpoolClient, UploadProgress};
+
+use crate::cli::{Cli, Commands, Context};
+use crate::log;
+use crate::utils::journal_dir;
+
+pub async fn handle_share_command(cli: Cli, context: Context) -> Result<()> {
+    if let Commands::Share {
+        spool,
+        recipients,
+        spool_name,
+    } = cli.command
+    {
+        let spool_address = Pubkey::from_str(&spool)
+            .map_err(|_| anyhow!("Invalid spool address: {}", spool))?;
+        let recipients = recipients
+            .iter()
+            .map(|r| Pubkey::from_str(r).map_err(|_| anyhow!("Invalid recipient: {}", r)))
+            .collect::<Result<Vec<_>>>()?;
+        let spool_name = spool_name.unwrap_or_else(|| Utc::now().timestamp().to_string());
+
+        log::print_message(&format!("Sharing spool {spool_address} with {} wallet(s)...", recipients.len()));
+        log::print_divider();
+
+        let Context { rpc, payer, .. } = context;
+        let client = SpoolClient::new(rpc, payer);
+
+        let pb = setup_progress_bar();
+        pb.set_message("Writing key envelope (please wait)...");
+
+        let progress_pb = pb.clone();
+        let progress = move |progress| {
+            if progress == UploadProgress::Finalizing {
+                progress_pb.set_message("finalizing spool...");
+            }
+        };
+
+        let envelope_address = match client
+            .share_spool(&journal_dir(), &spool_address, &spool_name, &recipients, progress)
+            .await
+        {
+            Ok(address) => address,
+            Err(e) => {
+                pb.abandon();
+                return Err(e);
+            }
+        };
+
+        pb.finish_with_message("");
+
+        log::print_divider();
+        log::print_info("Recipients can read the spool with:");
+        log::print_title(&format!("spoolnet read {envelope_address}"));
+        log::print_divider();
+    }
+    Ok(())
+}
+
+fn setup_progress_bar() -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {wide_msg}")
+            .expect("Failed to set progress style"),
+    );
+
+    let pb_clone = pb.clone();
+    task::spawn(async move {
+        while !pb_clone.is_finished() {
+            pb_clone.tick();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    });
+    pb
+}
@@ -1,37 +1,31 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use chrono::Utc;
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use indicatif::{ProgressBar, ProgressStyle};
 use mime_guess::Mime;
 use mime_guess::MimeGuess;
 use reqwest;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signer::Signer;
-use solana_sdk::{
-    pubkey::Pubkey,
-    signature::{Keypair, Signature},
-};
-use spool_client::subsidize_spool;
+use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}};
 use spool_client::{get_ata_address, get_token_balance};
 use std::io::Read;
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::path::Path;
+use std::str::FromStr;
 use tokio::{task, time::Duration};
 
 use spool_api::prelude::*;
 use spool_client::{
-    create_spool, encode_spool, finalize_spool, set_header, write_to_spool, CompressionAlgo, EncryptionAlgo,
-    SpoolFlags, SpoolHeader,
+    consts::SEGMENTS_PER_TX, encode_spool_for_recipients, encode_spool_with_key, max_payload_len, needs_manifest,
+    prefixed_segment_count, CompressionAlgo, EncryptionAlgo, ErasureParams, Manifest, ManifestPart, MimeType,
+    SpoolClient, SpoolFlags, SpoolHeader, SpoolKey, UploadJournal, UploadProgress,
 };
 
 use crate::cli::Context;
+use crate::commands::directory;
 use crate::cli::{Cli, Commands};
 use crate::log;
-use crate::utils::{mime_to_type, default_octet};
+use crate::utils::{mime_to_type, default_octet, encryption_key, journal_dir};
 
-const SEGMENTS_PER_TX: usize = 7; // 7 x 128 = 896 bytes
 const SAFE_SIZE: usize = SEGMENT_SIZE * SEGMENTS_PER_TX;
-const MAX_CONCURRENT: usize = 10;
 
 pub async fn handle_write_command(cli: Cli, context: Context) -> Result<()> {
     if let Commands::Write {
@@ -39,26 +33,98 @@ pub async fn handle_write_command(cli: Cli, context: Context) -> Result<()> {
         ref message,
         ref remote,
         ref spool_name,
+        ref resume,
+        ref compression,
+        level,
+        ref encrypt,
+        ref key_file,
+        ref recipients,
+        ref redundancy,
+        ref ignore,
     } = cli.command
     {
+        if filename.as_ref().is_some_and(|path| Path::new(path).is_dir()) {
+            return directory::handle_directory_write(cli, context).await;
+        }
+        if !ignore.is_empty() {
+            bail!("--ignore only applies when writing a directory");
+        }
+
         let (data, source, mime) =
             process_input(filename.clone(), message.clone(), remote.clone()).await?;
         let mime_type = mime_to_type(&mime);
 
-        let compression_algo = CompressionAlgo::Gzip;
-        let encryption_algo = EncryptionAlgo::None; // No encryption for now
-        let flags = SpoolFlags::Prefixed;
+        let mut recipients = recipients
+            .iter()
+            .map(|r| Pubkey::from_str(r).map_err(|_| anyhow!("Invalid recipient: {}", r)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let journal_dir = journal_dir();
+
+        // When resuming a given spool, the encoding comes from its journal
+        let journal = match resume {
+            Some(Some(spool)) => {
+                let spool_address = Pubkey::from_str(spool)
+                    .map_err(|_| anyhow!("Invalid spool address: {}", spool))?;
+                Some(UploadJournal::load(&journal_dir, &spool_address)?)
+            }
+            _ => None,
+        };
+
+        let mut header = match &journal {
+            Some(journal) => journal.header()?,
+            None => new_header(mime_type, compression, level, encrypt, redundancy.as_deref())?,
+        };
+
+        // Encrypted payloads use a fresh nonce, so they can't be matched against a journal
+        if journal.is_none() && resume.is_some() && header.is_encrypted() {
+            bail!("Resuming an encrypted upload requires its spool address: --resume <SPOOL>");
+        }
 
-        let mut header = SpoolHeader::new(mime_type, compression_algo, encryption_algo, flags);
+        let encoded = if !recipients.is_empty() || header.has_flag(SpoolFlags::Envelope) {
+            if recipients.is_empty() {
+                bail!("This upload is encrypted for recipients, pass the same --recipient list to resume it");
+            }
+
+            // The payer is always a recipient, so it can share the spool later
+            recipients.insert(0, context.payer.pubkey());
+            encode_payload(&data, &mut header, &context.payer, &recipients, None)?
+        } else {
+            let key = encryption_key(&mut header, key_file.as_deref())?;
+            encode_payload(&data, &mut header, &context.payer, &[], key.as_ref())?
+        };
+
+        // Otherwise, look for an unfinished upload of the same payload
+        let journal = match (journal, resume) {
+            (None, Some(None)) => Some(
+                UploadJournal::find_by_payload(&journal_dir, &encoded)?
+                    .ok_or_else(|| anyhow!("No unfinished upload found for {}", source))?,
+            ),
+            (journal, _) => journal,
+        };
+
+        let compression_algo = CompressionAlgo::try_from(header.compression)
+            .map_err(|_| anyhow!("Invalid compression algorithm"))?;
+        let encryption_algo = EncryptionAlgo::try_from(header.encryption_algo)
+            .map_err(|_| anyhow!("Invalid encryption algorithm"))?;
+        let flags = header.flag_list();
+
+        // Payloads too large for a single spool are split across part spools and a manifest
+        let parts = needs_manifest(header.data_len as usize, header.erasure())
+            .then(|| (header.data_len as usize).div_ceil(max_payload_len(header.erasure())));
+        if parts.is_some() && resume.is_some() {
+            bail!("Uploads split across spools are resumed by writing them again with the same --spool-name");
+        }
 
-        let encoded = encode_spool(&data, &mut header)?;
         let num_segments = encoded.len().div_ceil(SEGMENT_SIZE);
-        let chunks: Vec<_> = encoded.chunks(SAFE_SIZE).map(|c| c.to_vec()).collect();
-        let chunks_len = chunks.len();
+        let chunks_len = encoded.len().div_ceil(SAFE_SIZE);
 
-        let spool_name = spool_name
-            .clone()
-            .unwrap_or_else(|| Utc::now().timestamp().to_string());
+        let spool_name = match &journal {
+            Some(journal) => journal.name.clone(),
+            None => spool_name
+                .clone()
+                .unwrap_or_else(|| Utc::now().timestamp().to_string()),
+        };
 
         print_write_summary(
             &cli,
@@ -67,8 +133,9 @@ pub async fn handle_write_command(cli: Cli, context: Context) -> Result<()> {
             &mime,
             compression_algo,
             encryption_algo,
-            flags,
+            &flags,
             chunks_len,
+            parts,
         );
 
         if !confirm_proceed()? {
@@ -76,45 +143,170 @@ pub async fn handle_write_command(cli: Cli, context: Context) -> Result<()> {
             return Ok(());
         }
 
-
         let Context{
             rpc,
             payer,
             ..
         } = context;
 
-        let payer = Arc::new(payer);
-        let payer_pk = payer.try_pubkey()
-            .expect("Failed to get payer pubkey");
+        let client = SpoolClient::new(rpc, payer);
 
-        let payer_ata = get_ata_address(&payer_pk);
-        let required_rent = min_finalization_rent(num_segments as u64);
+        let payer_ata = get_ata_address(&client.pubkey());
+        let required_rent = match parts {
+            Some(parts) => split_rent(header.data_len as usize, parts, header.erasure()),
+            None => min_finalization_rent(num_segments as u64),
+        };
 
-        if get_token_balance(&rpc, &payer_ata).await? < required_rent {
+        if get_token_balance(client.rpc(), &payer_ata).await? < required_rent {
             log::print_error("Insufficient SPOOL tokens in payer's ATA to pay for rent.");
             return Ok(());
         }
 
         let pb = setup_progress_bar(chunks_len as u64);
+        let progress = progress_reporter(&pb);
+
+        let res = match &journal {
+            _ if parts.is_some() => {
+                pb.set_message("Creating part spools (please wait)...");
+                client
+                    .upload_manifest(&journal_dir, &spool_name, &header, &encoded, progress)
+                    .await
+            }
+            Some(journal) => {
+                pb.set_message("Resuming upload (please wait)...");
+                let spool_address = journal.spool_address()?;
+                client
+                    .resume_upload(&journal_dir, &spool_address, &encoded, progress)
+                    .await
+            }
+            None => {
+                pb.set_message("Creating new spool (please wait)...");
+                client
+                    .upload(&journal_dir, &spool_name, &header, &encoded, progress)
+                    .await
+            }
+        };
+
+        let spool_address = match res {
+            Ok(spool_address) => spool_address,
+            Err(e) => {
+                pb.abandon();
+                match parts {
+                    Some(_) => print_split_resume_hint(&spool_name),
+                    None => print_resume_hint(&client, &spool_name),
+                }
+                return Err(e);
+            }
+        };
 
-        pb.set_message("Creating new spool (please wait)...");
-        let (spool_address, writer_address, _sig) =
-            create_spool(&rpc, &payer, &spool_name).await?;
+        pb.finish_with_message("");
 
-        write_chunks(&rpc, &payer, spool_address, writer_address, chunks, &pb).await?;
+        print_write_completion(&cli, spool_address, chunks_len);
+    }
+    Ok(())
+}
 
-        pb.set_message("finalizing spool...");
-        tokio::time::sleep(Duration::from_secs(32)).await;
+/// Header of a new upload, from the write options.
+pub(crate) fn new_header(
+    mime_type: MimeType,
+    compression: &str,
+    level: Option<u8>,
+    encrypt: &Option<Option<String>>,
+    redundancy: Option<&str>,
+) -> Result<SpoolHeader> {
+    let encryption_algo = match encrypt {
+        Some(algo) => EncryptionAlgo::from_str(algo.as_deref().unwrap_or("aes-256-gcm"))?,
+        None => EncryptionAlgo::None,
+    };
+    let mut header = SpoolHeader::new(
+        mime_type,
+        CompressionAlgo::from_str(compression)?,
+        encryption_algo,
+        SpoolFlags::Prefixed,
+    );
+    header.compression_level = level.unwrap_or(0);
+    if let Some(redundancy) = redundancy {
+        header.set_erasure(Some(ErasureParams::from_str(redundancy)?));
+    }
+    Ok(header)
+}
 
-        set_header(&rpc, &payer, spool_address, header).await?;
-        subsidize_spool(&rpc, &payer, spool_address, payer_ata, required_rent).await?;
-        finalize_spool(&rpc, &payer, spool_address, writer_address).await?;
+/// Encodes `data` with `header`: for `recipients` if there are any (sent by `payer`), otherwise
+/// with `key` if the header sets an encryption algorithm.
+pub(crate) fn encode_payload(
+    data: &[u8],
+    header: &mut SpoolHeader,
+    payer: &Keypair,
+    recipients: &[Pubkey],
+    key: Option<&SpoolKey>,
+) -> Result<Vec<u8>> {
+    if recipients.is_empty() {
+        encode_spool_with_key(data, header, key)
+    } else {
+        encode_spool_for_recipients(data, header, payer, recipients)
+    }
+}
 
-        pb.finish_with_message("");
+/// Maps upload progress onto the progress bar.
+fn progress_reporter(pb: &ProgressBar) -> impl Fn(UploadProgress) + Send + Sync + 'static {
+    let pb = pb.clone();
+    move |progress| match progress {
+        UploadProgress::Part { index, total } if index == total => {
+            pb.println("Uploading manifest spool");
+        }
+        UploadProgress::Part { index, total } => {
+            pb.println(format!("Uploading part {} of {}", index + 1, total));
+        }
+        UploadProgress::Created => pb.set_message("Spool created"),
+        UploadProgress::Reconciled { confirmed, total } => {
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
+                    .expect("Failed to set progress style"),
+            );
+            pb.set_length(total as u64);
+            pb.set_position(confirmed as u64);
+            pb.set_message("");
+        }
+        UploadProgress::ChunkSent => pb.inc(1),
+        UploadProgress::Waiting => pb.set_message("waiting for writes to land..."),
+        UploadProgress::Verifying => pb.set_message("verifying uploaded data..."),
+        UploadProgress::SettingHeader
+        | UploadProgress::Subsidizing
+        | UploadProgress::Finalizing => pb.set_message("finalizing spool..."),
+    }
+}
 
-        print_write_completion(&cli, spool_address, chunks_len);
+fn print_resume_hint(client: &SpoolClient, spool_name: &str) {
+    let (spool_address, _) = spool_pda(client.pubkey(), &to_name(spool_name));
+    if UploadJournal::load(&journal_dir(), &spool_address).is_ok() {
+        log::print_divider();
+        log::print_info("The upload was interrupted, to resume it run the same command with:");
+        log::print_title(&format!("--resume {spool_address}"));
+        log::print_divider();
     }
-    Ok(())
+}
+
+pub(crate) fn print_split_resume_hint(spool_name: &str) {
+    log::print_divider();
+    log::print_info("The upload was interrupted, to resume it run the same command with:");
+    log::print_title(&format!("--spool-name {spool_name}"));
+    log::print_divider();
+}
+
+/// Rent for the part spools and the manifest spool of a payload of `payload_len` bytes split
+/// into `parts`, with `erasure` coding.
+pub(crate) fn split_rent(payload_len: usize, parts: usize, erasure: Option<ErasureParams>) -> u64 {
+    let prefixed_segments = |len: usize| prefixed_segment_count(len as u64, erasure);
+
+    let part_len = max_payload_len(erasure);
+    let full_parts = (payload_len / part_len) as u64;
+    let last_part = payload_len % part_len;
+    let manifest_len = Manifest::new(vec![ManifestPart::new(Pubkey::default(), &[]); parts]).len();
+
+    full_parts * min_finalization_rent(prefixed_segments(part_len))
+        + if last_part > 0 { min_finalization_rent(prefixed_segments(last_part)) } else { 0 }
+        + min_finalization_rent(prefixed_segments(manifest_len))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -125,8 +317,9 @@ fn print_write_summary(
     mime: &Mime,
     compression_algo: CompressionAlgo,
     encryption_algo: EncryptionAlgo,
-    flags: SpoolFlags,
+    flags: &[SpoolFlags],
     chunk_count: usize,
+    parts: Option<usize>,
 ) {
     if cli.verbose {
         log::print_section_header("Spool Write");
@@ -138,10 +331,13 @@ fn print_write_summary(
         log::print_message(&format!("Flags: {flags:?}"));
     }
     log::print_count(&format!("Total Chunks: {chunk_count}"));
+    if let Some(parts) = parts {
+        log::print_count(&format!("Part Spools: {parts} (plus a manifest spool)"));
+    }
     log::print_divider();
 }
 
-fn confirm_proceed() -> Result<bool> {
+pub(crate) fn confirm_proceed() -> Result<bool> {
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("→ Begin writing to spool?")
         .default(false)
@@ -149,7 +345,7 @@ fn confirm_proceed() -> Result<bool> {
         .map_err(|e| anyhow::anyhow!("Failed to get user input: {}", e))
 }
 
-fn setup_progress_bar(total: u64) -> ProgressBar {
+pub(crate) fn setup_progress_bar(total: u64) -> ProgressBar {
     let pb = ProgressBar::new(total);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -166,52 +362,6 @@ fn setup_progress_bar(total: u64) -> ProgressBar {
     pb
 }
 
-async fn write_chunks(
-    client: &Arc<RpcClient>,
-    payer: &Arc<Keypair>,
-    spool_address: Pubkey,
-    writer_address: Pubkey,
-    chunks: Vec<Vec<u8>>,
-    pb: &ProgressBar,
-) -> Result<()> {
-    pb.set_message("");
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
-            .expect("Failed to set progress style"),
-    );
-
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT));
-
-    let mut handles = Vec::with_capacity(chunks.len());
-    for chunk in chunks {
-        let client_clone = client.clone();
-        let payer_clone = payer.clone();
-        let pb_clone = pb.clone();
-        let semaphore_clone = semaphore.clone();
-        let handle: task::JoinHandle<Result<Signature>> = task::spawn(async move {
-            let _permit = semaphore_clone.acquire().await?;
-            let sig = write_to_spool(
-                &client_clone,
-                &payer_clone,
-                spool_address,
-                writer_address,
-                &chunk,
-            )
-            .await?;
-            pb_clone.inc(1);
-            Ok(sig)
-        });
-        handles.push(handle);
-    }
-
-    for handle in handles {
-        handle.await??;
-    }
-
-    Ok(())
-}
-
 fn print_write_completion(cli: &Cli, spool_address: Pubkey, chunk_count: usize) {
     log::print_divider();
 
@@ -230,7 +380,7 @@ fn print_write_completion(cli: &Cli, spool_address: Pubkey, chunk_count: usize)
 
 /// Processes input from file, message, or remote URL.
 /// Returns the data, source description, and MIME type.
-async fn process_input(
+pub(crate) async fn process_input(
     filename: Option<String>,
     message: Option<String>,
     remote: Option<String>,
@@ -8,7 +8,7 @@ mod utils;
 use anyhow::{Ok, Result};
 use clap::Parser;
 use cli::{Cli, Commands};
-use commands::{admin, read, write, info, snapshot, network, claim};
+use commands::{admin, read, write, share, offline, info, snapshot, network, claim};
 use env_logger::{self, Env};
 use spool_network::store::SpoolStore;
 
@@ -43,7 +43,10 @@ async fn run_spoolnet_cli() -> Result<()> {
 
     match cli.command {
         Commands::Init {} |
+        Commands::ConfigureFaucet { .. } |
         Commands::Write { .. } | 
+        Commands::Share { .. } |
+        Commands::Offline(_) |
         Commands::Register { .. } |
         Commands::Mine { .. }
         => {
@@ -62,7 +65,8 @@ async fn run_spoolnet_cli() -> Result<()> {
         // Admin Commands
 
         Commands::Init { .. } |
-        Commands::Airdrop { .. } 
+        Commands::Airdrop { .. } |
+        Commands::ConfigureFaucet { .. } 
         => {
             admin::handle_admin_commands(cli, context).await?;
         }
@@ -72,9 +76,21 @@ async fn run_spoolnet_cli() -> Result<()> {
         Commands::Read { .. } => {
             read::handle_read_command(cli, context).await?;
         }
+        Commands::History { .. } => {
+            read::handle_history_command(cli, context).await?;
+        }
         Commands::Write { .. } => {
             write::handle_write_command(cli, context).await?;
         }
+        Commands::Share { .. } => {
+            share::handle_share_command(cli, context).await?;
+        }
+        Commands::Offline(_) => {
+            offline::handle_offline_commands(cli, context).await?;
+        }
+        Commands::Submit { .. } => {
+            offline::handle_submit_command(cli, context).await?;
+        }
 
         // Miner Commands
 
@@ -1,10 +1,17 @@
 use anyhow::Result;
-use std::{ fs, io::{self, Write}, path::Path };
+use std::{ fs, io::{self, Write}, path::{Path, PathBuf} };
 use spool_client::MimeType;
 
 use crate::log;
 use super::get_extension;
 
+/// Directory where the journals of unfinished uploads are kept.
+pub fn journal_dir() -> PathBuf {
+    dirs::home_dir()
+        .expect("Could not find home directory")
+        .join(".config/spoolnet/uploads")
+}
+
 pub fn write_output(output: Option<String>, data: &[u8], mime_type: MimeType) -> Result<()> {
     match output {
         Some(mut filename) => {
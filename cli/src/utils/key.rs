@@ -0,0 +1,65 @@
+use anyhow::{anyhow, bail, Result};
+use dialoguer::{theme::ColorfulTheme, Password};
+use std::path::Path;
+use spool_client::{new_salt, SpoolHeader, SpoolKey};
+
+/// Key to encrypt a payload with `header`: read from `key_file`, or derived from a passphrase.
+/// A new passphrase gets a fresh salt, stored in the header so readers can derive the same key.
+pub fn encryption_key(header: &mut SpoolHeader, key_file: Option<&str>) -> Result<Option<SpoolKey>> {
+    if !header.is_encrypted() {
+        if key_file.is_some() {
+            bail!("--key-file requires --encrypt");
+        }
+        return Ok(None);
+    }
+
+    if let Some(path) = key_file {
+        if header.kdf_salt().is_some() {
+            bail!("This upload was encrypted with a passphrase, not a key file");
+        }
+        return SpoolKey::from_file(Path::new(path)).map(Some);
+    }
+
+    // When resuming, the salt (and so the key) comes from the journal header
+    let (salt, confirm) = match header.kdf_salt() {
+        Some(salt) => (salt, false),
+        None => {
+            header.kdf_salt = new_salt();
+            (header.kdf_salt, true)
+        }
+    };
+
+    let passphrase = prompt_passphrase(confirm)?;
+    Ok(Some(SpoolKey::from_passphrase(&passphrase, &salt)?))
+}
+
+/// Key to decrypt a spool with `header`, from the `--key` argument: a key file path, or `None`
+/// to prompt for a passphrase.
+pub fn decryption_key(header: &SpoolHeader, key: Option<Option<String>>) -> Result<Option<SpoolKey>> {
+    match key {
+        None if header.is_encrypted() => {
+            bail!("Spool is encrypted, pass --key <KEY_FILE> (or --key alone for a passphrase)")
+        }
+        None => Ok(None),
+        Some(Some(path)) => SpoolKey::from_file(Path::new(&path)).map(Some),
+        Some(None) => {
+            let salt = header
+                .kdf_salt()
+                .ok_or_else(|| anyhow!("Spool was not encrypted with a passphrase, pass --key <KEY_FILE>"))?;
+            let passphrase = prompt_passphrase(false)?;
+            Ok(Some(SpoolKey::from_passphrase(&passphrase, &salt)?))
+        }
+    }
+}
+
+fn prompt_passphrase(confirm: bool) -> Result<String> {
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme).with_prompt("Passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases don't match");
+    }
+
+    prompt
+        .interact()
+        .map_err(|e| anyhow!("Failed to get user input: {}", e))
+}
@@ -1,5 +1,7 @@
 pub mod fs;
+pub mod key;
 pub mod mime;
 
 pub use fs::*;
+pub use key::*;
 pub use mime::*;
@@ -0,0 +1,296 @@
+#[cfg(feature = "litesvm")]
+mod svm;
+
+#[cfg(feature = "litesvm")]
+pub use svm::*;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{
+        RpcBlockConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig, RpcTransactionConfig,
+    },
+    rpc_response::{
+        RpcConfirmedTransactionStatusWithSignature,
+        RpcPrioritizationFee,
+        RpcSimulateTransactionResult,
+    },
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionStatus, UiConfirmedBlock,
+};
+
+/// The cluster calls the spool flows (uploads, reads, the archive and the miner) are built on,
+/// so they run against a validator through `RpcClient` or in process (see `LiteSvmBackend`,
+/// behind the `litesvm` feature).
+///
+/// Errors are `ClientError`s in every backend, so they decode the same way into
+/// `SpoolClientError`.
+#[async_trait]
+pub trait RpcBackend: Send + Sync {
+    /// Commitment reads and confirmations are made at.
+    fn commitment(&self) -> CommitmentConfig;
+
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Account>;
+
+    /// Accounts at `addresses`, `None` for the ones that don't exist.
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<Vec<Option<Account>>>;
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64>;
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64>;
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<Vec<(Pubkey, Account)>>;
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    /// Latest blockhash and the last block height at which a transaction using it can land.
+    async fn get_latest_blockhash_with_expiry(&self) -> ClientResult<(Hash, u64)>;
+
+    /// Simulates `tx` without verifying its signatures, against the latest blockhash.
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult>;
+
+    async fn get_recent_prioritization_fees(&self, addresses: &[Pubkey]) -> ClientResult<Vec<RpcPrioritizationFee>>;
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature>;
+
+    async fn send_and_confirm_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature>;
+
+    async fn get_block(&self, slot: u64, config: RpcBlockConfig) -> ClientResult<UiConfirmedBlock>;
+
+    async fn get_slot(&self) -> ClientResult<u64>;
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64>;
+
+    async fn get_block_height(&self) -> ClientResult<u64>;
+
+    async fn get_blocks_with_limit(&self, start_slot: u64, limit: usize) -> ClientResult<Vec<u64>>;
+
+    /// Statuses of `signatures`, searched in the full transaction history.
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> ClientResult<Vec<Option<TransactionStatus>>>;
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+
+    /// Signatures of the transactions that loaded `address`, newest first.
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        config: GetConfirmedSignaturesForAddress2Config,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>>;
+}
+
+#[async_trait]
+impl RpcBackend for RpcClient {
+    fn commitment(&self) -> CommitmentConfig {
+        RpcClient::commitment(self)
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Account> {
+        RpcClient::get_account(self, address).await
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts(self, addresses).await
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        RpcClient::get_minimum_balance_for_rent_exemption(self, data_len).await
+    }
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64> {
+        RpcClient::get_balance(self, address).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        self.get_program_accounts_with_config(program_id, config).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        RpcClient::get_latest_blockhash(self).await
+    }
+
+    async fn get_latest_blockhash_with_expiry(&self) -> ClientResult<(Hash, u64)> {
+        RpcClient::get_latest_blockhash_with_commitment(self, RpcClient::commitment(self)).await
+    }
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        Ok(self.simulate_transaction_with_config(tx, config).await?.value)
+    }
+
+    async fn get_recent_prioritization_fees(&self, addresses: &[Pubkey]) -> ClientResult<Vec<RpcPrioritizationFee>> {
+        RpcClient::get_recent_prioritization_fees(self, addresses).await
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        RpcClient::send_transaction(self, tx).await
+    }
+
+    async fn send_and_confirm_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        RpcClient::send_and_confirm_transaction(self, tx).await
+    }
+
+    async fn get_block(&self, slot: u64, config: RpcBlockConfig) -> ClientResult<UiConfirmedBlock> {
+        self.get_block_with_config(slot, config).await
+    }
+
+    async fn get_slot(&self) -> ClientResult<u64> {
+        RpcClient::get_slot(self).await
+    }
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        RpcClient::get_slot_with_commitment(self, commitment).await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        RpcClient::get_block_height(self).await
+    }
+
+    async fn get_blocks_with_limit(&self, start_slot: u64, limit: usize) -> ClientResult<Vec<u64>> {
+        RpcClient::get_blocks_with_limit(self, start_slot, limit).await
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> ClientResult<Vec<Option<TransactionStatus>>> {
+        Ok(self.get_signature_statuses_with_history(signatures).await?.value)
+    }
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        self.get_transaction_with_config(signature, config).await
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        config: GetConfirmedSignaturesForAddress2Config,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        self.get_signatures_for_address_with_config(address, config).await
+    }
+}
+
+#[async_trait]
+impl<T: RpcBackend + ?Sized> RpcBackend for Arc<T> {
+    fn commitment(&self) -> CommitmentConfig {
+        (**self).commitment()
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Account> {
+        (**self).get_account(address).await
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+        (**self).get_multiple_accounts(addresses).await
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        (**self).get_minimum_balance_for_rent_exemption(data_len).await
+    }
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64> {
+        (**self).get_balance(address).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        (**self).get_program_accounts(program_id, config).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        (**self).get_latest_blockhash().await
+    }
+
+    async fn get_latest_blockhash_with_expiry(&self) -> ClientResult<(Hash, u64)> {
+        (**self).get_latest_blockhash_with_expiry().await
+    }
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult> {
+        (**self).simulate_transaction(tx).await
+    }
+
+    async fn get_recent_prioritization_fees(&self, addresses: &[Pubkey]) -> ClientResult<Vec<RpcPrioritizationFee>> {
+        (**self).get_recent_prioritization_fees(addresses).await
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        (**self).send_transaction(tx).await
+    }
+
+    async fn send_and_confirm_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        (**self).send_and_confirm_transaction(tx).await
+    }
+
+    async fn get_block(&self, slot: u64, config: RpcBlockConfig) -> ClientResult<UiConfirmedBlock> {
+        (**self).get_block(slot, config).await
+    }
+
+    async fn get_slot(&self) -> ClientResult<u64> {
+        (**self).get_slot().await
+    }
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        (**self).get_slot_with_commitment(commitment).await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        (**self).get_block_height().await
+    }
+
+    async fn get_blocks_with_limit(&self, start_slot: u64, limit: usize) -> ClientResult<Vec<u64>> {
+        (**self).get_blocks_with_limit(start_slot, limit).await
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> ClientResult<Vec<Option<TransactionStatus>>> {
+        (**self).get_signature_statuses(signatures).await
+    }
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        (**self).get_transaction(signature, config).await
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        config: GetConfirmedSignaturesForAddress2Config,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        (**self).get_signatures_for_address(address, config).await
+    }
+}
@@ -0,0 +1,603 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use litesvm::{types::FailedTransactionMetadata, LiteSVM};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_filter::RpcFilterType,
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+        JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+        JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
+    },
+    rpc_request::{RpcError, RpcResponseErrorData},
+    rpc_response::{
+        RpcConfirmedTransactionStatusWithSignature,
+        RpcPrioritizationFee,
+        RpcSimulateTransactionResult,
+    },
+};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::state::AddressLookupTable,
+    clock::{Clock, MAX_PROCESSING_AGE},
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    message::{v0::LoadedAddresses, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    map_inner_instructions,
+    BlockEncodingOptions,
+    ConfirmedBlock,
+    ConfirmedTransactionWithStatusMeta,
+    EncodedConfirmedTransactionWithStatusMeta,
+    TransactionConfirmationStatus,
+    TransactionDetails,
+    TransactionStatus,
+    TransactionStatusMeta,
+    TransactionWithStatusMeta,
+    UiConfirmedBlock,
+    UiTransactionEncoding,
+    VersionedTransactionWithStatusMeta,
+};
+
+use super::RpcBackend;
+
+/// Most signatures `getSignaturesForAddress` returns without a limit.
+const MAX_SIGNATURES: usize = 1000;
+
+/// `RpcBackend` running transactions in an in-process LiteSVM, for end-to-end tests of the
+/// spool flows without a validator.
+///
+/// Every transaction sent lands in a block of its own, in the slot after the previous one, so
+/// blocks, slots and signatures can be queried as on a cluster. Failed transactions don't land.
+/// `getProgramAccounts` only sees the accounts transactions loaded and the ones set through
+/// the backend, since LiteSVM can't list its accounts.
+///
+/// Simulations verify signatures if the SVM does (`LiteSVM::new` does), so fee strategies
+/// that simulate fall back to their presets unless the SVM is built with `with_sigverify(false)`.
+pub struct LiteSvmBackend {
+    state: Mutex<SvmState>,
+}
+
+struct SvmState {
+    svm: LiteSVM,
+    accounts: BTreeSet<Pubkey>,
+    blocks: BTreeMap<u64, ConfirmedBlock>,
+    signatures: Vec<SignatureEntry>,
+}
+
+struct SignatureEntry {
+    signature: Signature,
+    slot: u64,
+    block_time: i64,
+    accounts: Vec<Pubkey>,
+}
+
+impl LiteSvmBackend {
+    pub fn new(svm: LiteSVM) -> Self {
+        Self {
+            state: Mutex::new(SvmState {
+                svm,
+                accounts: BTreeSet::new(),
+                blocks: BTreeMap::new(),
+                signatures: Vec::new(),
+            }),
+        }
+    }
+
+    /// Runs `f` on the SVM, to set it up or inspect it. Accounts created through it are not
+    /// tracked, use `set_account` or `airdrop` for the ones `getProgramAccounts` should see.
+    pub fn with_svm<T>(&self, f: impl FnOnce(&mut LiteSVM) -> T) -> T {
+        f(&mut self.state().svm)
+    }
+
+    pub fn set_account(&self, address: Pubkey, account: Account) -> Result<()> {
+        let mut state = self.state();
+        state
+            .svm
+            .set_account(address, account)
+            .map_err(|e| anyhow!("Failed to set account {}: {}", address, e))?;
+        state.accounts.insert(address);
+        Ok(())
+    }
+
+    pub fn airdrop(&self, address: &Pubkey, lamports: u64) -> Result<()> {
+        let mut state = self.state();
+        state
+            .svm
+            .airdrop(address, lamports)
+            .map_err(|e| anyhow!("Failed to airdrop to {}: {}", address, e.err))?;
+        state.accounts.insert(*address);
+        Ok(())
+    }
+
+    fn state(&self) -> MutexGuard<'_, SvmState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SvmState {
+    /// Runs `tx` in the next slot and, if it succeeds, records it in that slot's block.
+    #[allow(clippy::result_large_err)] // The error is returned as is by the backend calls
+    fn execute(&mut self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        let parent_slot = self.svm.get_sysvar::<Clock>().slot;
+        let slot = parent_slot + 1;
+        self.svm.warp_to_slot(slot);
+
+        let loaded_addresses = self.loaded_addresses(&tx.message);
+        let accounts: Vec<Pubkey> = tx
+            .message
+            .static_account_keys()
+            .iter()
+            .chain(&loaded_addresses.writable)
+            .chain(&loaded_addresses.readonly)
+            .copied()
+            .collect();
+        let balances = |svm: &LiteSVM| -> Vec<u64> {
+            accounts.iter().map(|key| svm.get_balance(key).unwrap_or(0)).collect()
+        };
+
+        let pre_balances = balances(&self.svm);
+        let meta = self.svm.send_transaction(tx.clone()).map_err(preflight_failure)?;
+        let post_balances = balances(&self.svm);
+
+        let block_time = self.svm.get_sysvar::<Clock>().unix_timestamp;
+        let status_meta = TransactionStatusMeta {
+            status: Ok(()),
+            pre_balances,
+            post_balances,
+            inner_instructions: Some(map_inner_instructions(meta.inner_instructions).collect()),
+            log_messages: Some(meta.logs),
+            loaded_addresses,
+            compute_units_consumed: Some(meta.compute_units_consumed),
+            ..TransactionStatusMeta::default()
+        };
+
+        let previous_blockhash = self
+            .blocks
+            .values()
+            .next_back()
+            .map(|block| block.blockhash.clone())
+            .unwrap_or_else(|| Hash::default().to_string());
+        self.blocks.insert(
+            slot,
+            ConfirmedBlock {
+                previous_blockhash,
+                blockhash: Hash::new_from_array(slot_hash(slot)).to_string(),
+                parent_slot,
+                transactions: vec![TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+                    transaction: tx.clone(),
+                    meta: status_meta,
+                })],
+                rewards: Vec::new(),
+                num_partitions: None,
+                block_time: Some(block_time),
+                block_height: Some(self.blocks.len() as u64 + 1),
+            },
+        );
+
+        self.accounts.extend(accounts.iter().copied());
+        self.signatures.push(SignatureEntry {
+            signature: meta.signature,
+            slot,
+            block_time,
+            accounts,
+        });
+
+        Ok(meta.signature)
+    }
+
+    /// Accounts `message` loads through its address lookup tables.
+    fn loaded_addresses(&self, message: &VersionedMessage) -> LoadedAddresses {
+        let mut loaded = LoadedAddresses::default();
+
+        for lookup in message.address_table_lookups().unwrap_or_default() {
+            let Some(account) = self.svm.get_account(&lookup.account_key) else {
+                continue;
+            };
+            let Ok(table) = AddressLookupTable::deserialize(&account.data) else {
+                continue;
+            };
+
+            let select = |indexes: &[u8]| -> Vec<Pubkey> {
+                indexes
+                    .iter()
+                    .filter_map(|index| table.addresses.get(*index as usize).copied())
+                    .collect()
+            };
+            loaded.writable.extend(select(&lookup.writable_indexes));
+            loaded.readonly.extend(select(&lookup.readonly_indexes));
+        }
+
+        loaded
+    }
+}
+
+#[async_trait]
+impl RpcBackend for LiteSvmBackend {
+    /// Transactions land final, every commitment sees the same state.
+    fn commitment(&self) -> CommitmentConfig {
+        CommitmentConfig::confirmed()
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Account> {
+        self.state()
+            .svm
+            .get_account(address)
+            .filter(|account| account.lamports > 0)
+            .ok_or_else(|| for_user(format!("AccountNotFound: pubkey={address}")))
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+        let state = self.state();
+        Ok(addresses
+            .iter()
+            .map(|address| state.svm.get_account(address).filter(|account| account.lamports > 0))
+            .collect())
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        Ok(self.state().svm.minimum_balance_for_rent_exemption(data_len))
+    }
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64> {
+        Ok(self.state().svm.get_balance(address).unwrap_or(0))
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let state = self.state();
+        let filters = config.filters.unwrap_or_default();
+        let data_slice = config.account_config.data_slice;
+
+        let accounts = state
+            .accounts
+            .iter()
+            .filter_map(|address| Some((*address, state.svm.get_account(address)?)))
+            .filter(|(_, account)| account.owner == *program_id && account.lamports > 0)
+            .filter(|(_, account)| filters.iter().all(|filter| filter_allows(filter, &account.data)))
+            .map(|(address, mut account)| {
+                if let Some(slice) = data_slice {
+                    let start = slice.offset.min(account.data.len());
+                    let end = slice.offset.saturating_add(slice.length).min(account.data.len());
+                    account.data = account.data[start..end].to_vec();
+                }
+                (address, account)
+            })
+            .collect();
+
+        Ok(accounts)
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(self.state().svm.latest_blockhash())
+    }
+
+    async fn get_latest_blockhash_with_expiry(&self) -> ClientResult<(Hash, u64)> {
+        let state = self.state();
+        let last_valid_block_height = state.blocks.len() as u64 + MAX_PROCESSING_AGE as u64;
+        Ok((state.svm.latest_blockhash(), last_valid_block_height))
+    }
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult> {
+        let state = self.state();
+        let mut tx = tx.clone();
+        tx.message.set_recent_blockhash(state.svm.latest_blockhash());
+
+        let (err, meta) = match state.svm.simulate_transaction(tx) {
+            Ok(info) => (None, info.meta),
+            Err(failed) => (Some(failed.err), failed.meta),
+        };
+
+        Ok(RpcSimulateTransactionResult {
+            err,
+            logs: Some(meta.logs),
+            accounts: None,
+            units_consumed: Some(meta.compute_units_consumed),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        })
+    }
+
+    async fn get_recent_prioritization_fees(&self, _addresses: &[Pubkey]) -> ClientResult<Vec<RpcPrioritizationFee>> {
+        Ok(Vec::new())
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        self.state().execute(tx)
+    }
+
+    async fn send_and_confirm_transaction(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        self.state().execute(tx)
+    }
+
+    async fn get_block(&self, slot: u64, config: RpcBlockConfig) -> ClientResult<UiConfirmedBlock> {
+        let state = self.state();
+
+        let Some(block) = state.blocks.get(&slot) else {
+            return Err(if slot > state.svm.get_sysvar::<Clock>().slot {
+                response_error(JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, format!("Block not available for slot {slot}"))
+            } else {
+                response_error(JSON_RPC_SERVER_ERROR_SLOT_SKIPPED, format!("Slot {slot} was skipped"))
+            });
+        };
+
+        let options = BlockEncodingOptions {
+            transaction_details: config.transaction_details.unwrap_or(TransactionDetails::Full),
+            show_rewards: config.rewards.unwrap_or(true),
+            max_supported_transaction_version: config.max_supported_transaction_version,
+        };
+        block
+            .clone()
+            .encode_with_options(config.encoding.unwrap_or(UiTransactionEncoding::Json), options)
+            .map_err(|e| response_error(JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION, e.to_string()))
+    }
+
+    async fn get_slot(&self) -> ClientResult<u64> {
+        Ok(self.state().svm.get_sysvar::<Clock>().slot)
+    }
+
+    async fn get_slot_with_commitment(&self, _commitment: CommitmentConfig) -> ClientResult<u64> {
+        self.get_slot().await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        Ok(self.state().blocks.len() as u64)
+    }
+
+    async fn get_blocks_with_limit(&self, start_slot: u64, limit: usize) -> ClientResult<Vec<u64>> {
+        Ok(self.state().blocks.range(start_slot..).map(|(slot, _)| *slot).take(limit).collect())
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> ClientResult<Vec<Option<TransactionStatus>>> {
+        let state = self.state();
+        Ok(signatures
+            .iter()
+            .map(|signature| {
+                let entry = state.signatures.iter().find(|entry| entry.signature == *signature)?;
+                Some(TransactionStatus {
+                    slot: entry.slot,
+                    confirmations: None,
+                    status: Ok(()),
+                    err: None,
+                    confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+                })
+            })
+            .collect())
+    }
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        let state = self.state();
+
+        let transaction = state
+            .signatures
+            .iter()
+            .find(|entry| entry.signature == *signature)
+            .and_then(|entry| {
+                let block = state.blocks.get(&entry.slot)?;
+                Some(ConfirmedTransactionWithStatusMeta {
+                    slot: entry.slot,
+                    tx_with_meta: block.transactions.first()?.clone(),
+                    block_time: block.block_time,
+                })
+            })
+            .ok_or_else(|| for_user(format!("Transaction {signature} not found")))?;
+
+        transaction
+            .encode(
+                config.encoding.unwrap_or(UiTransactionEncoding::Json),
+                config.max_supported_transaction_version,
+            )
+            .map_err(|e| response_error(JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION, e.to_string()))
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        config: GetConfirmedSignaturesForAddress2Config,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let state = self.state();
+        let newest_first = state.signatures.iter().rev();
+
+        let entries: Vec<&SignatureEntry> = match config.before {
+            Some(before) => newest_first.skip_while(|entry| entry.signature != before).skip(1).collect(),
+            None => newest_first.collect(),
+        };
+
+        Ok(entries
+            .into_iter()
+            .take_while(|entry| Some(entry.signature) != config.until)
+            .filter(|entry| entry.accounts.contains(address))
+            .take(config.limit.unwrap_or(MAX_SIGNATURES))
+            .map(|entry| RpcConfirmedTransactionStatusWithSignature {
+                signature: entry.signature.to_string(),
+                slot: entry.slot,
+                err: None,
+                memo: None,
+                block_time: Some(entry.block_time),
+                confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+            })
+            .collect())
+    }
+}
+
+/// Blockhash of the block of `slot`.
+fn slot_hash(slot: u64) -> [u8; 32] {
+    *blake3::hash(&slot.to_le_bytes()).as_bytes()
+}
+
+/// Whether an account with `data` passes `filter`. Token account filters match nothing, the
+/// backend only serves the spool program.
+fn filter_allows(filter: &RpcFilterType, data: &[u8]) -> bool {
+    match filter {
+        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        RpcFilterType::TokenAccountState => false,
+    }
+}
+
+fn for_user(message: String) -> ClientError {
+    ClientErrorKind::RpcError(RpcError::ForUser(message)).into()
+}
+
+fn response_error(code: i64, message: String) -> ClientError {
+    ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        code,
+        message,
+        data: RpcResponseErrorData::Empty,
+    })
+    .into()
+}
+
+/// The error a cluster returns for a transaction that fails its preflight simulation.
+fn preflight_failure(failed: FailedTransactionMetadata) -> ClientError {
+    ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        code: JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+        message: format!("Transaction simulation failed: {}", failed.err),
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(RpcSimulateTransactionResult {
+            err: Some(failed.err),
+            logs: Some(failed.meta.logs),
+            accounts: None,
+            units_consumed: Some(failed.meta.compute_units_consumed),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        }),
+    })
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use solana_sdk::{
+        message::Message,
+        signature::{Keypair, Signer},
+        system_instruction,
+        system_program,
+        transaction::Transaction,
+    };
+
+    use crate::utils::{get_transaction_with_meta, process_block};
+    use crate::SpoolClient;
+
+    fn transfer(backend: &LiteSvmBackend, from: &Keypair, to: &Pubkey, lamports: u64) -> VersionedTransaction {
+        let blockhash = backend.with_svm(|svm| svm.latest_blockhash());
+        let ix = system_instruction::transfer(&from.pubkey(), to, lamports);
+        Transaction::new(&[from], Message::new(&[ix], Some(&from.pubkey())), blockhash).into()
+    }
+
+    #[tokio::test]
+    async fn test_blocks_and_signatures() {
+        let backend = LiteSvmBackend::new(LiteSVM::new());
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let start = backend.get_slot().await.unwrap();
+        let first = backend.send_transaction(&transfer(&backend, &payer, &recipient, 1_000_000)).await.unwrap();
+        let second = backend
+            .send_and_confirm_transaction(&transfer(&backend, &payer, &recipient, 2_000_000))
+            .await
+            .unwrap();
+
+        assert_eq!(backend.get_slot().await.unwrap(), start + 2);
+        assert_eq!(backend.get_blocks_with_limit(start, 10).await.unwrap(), vec![start + 1, start + 2]);
+        assert_eq!(backend.get_account(&recipient).await.unwrap().lamports, 3_000_000);
+
+        let config = RpcBlockConfig {
+            max_supported_transaction_version: Some(0),
+            ..RpcBlockConfig::default()
+        };
+        let block = backend.get_block(start + 2, config).await.unwrap();
+        assert_eq!(block.parent_slot, start + 1);
+        assert_eq!(block.transactions.as_ref().unwrap().len(), 1);
+        assert!(process_block(block, start + 2).unwrap().segment_writes.is_empty());
+
+        let skipped = backend.get_block(start, config).await.unwrap_err();
+        assert!(!crate::SpoolClientError::from(skipped).is_retryable());
+
+        let signatures = backend
+            .get_signatures_for_address(&recipient, GetConfirmedSignaturesForAddress2Config::default())
+            .await
+            .unwrap();
+        let signatures: Vec<String> = signatures.into_iter().map(|s| s.signature).collect();
+        assert_eq!(signatures, vec![second.to_string(), first.to_string()]);
+
+        let before = GetConfirmedSignaturesForAddress2Config {
+            before: Some(second),
+            ..GetConfirmedSignaturesForAddress2Config::default()
+        };
+        let older = backend.get_signatures_for_address(&recipient, before).await.unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].signature, first.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_spool_client_on_backend() {
+        let backend = Arc::new(LiteSvmBackend::new(LiteSVM::new()));
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let client = SpoolClient::new(backend.clone(), payer);
+        let ix = system_instruction::transfer(&client.pubkey(), &recipient, 1_000_000);
+        let signature = client.send_and_confirm(&[ix], None, &[]).await.unwrap();
+
+        assert_eq!(client.rpc().get_balance(&recipient).await.unwrap(), 1_000_000);
+        let statuses = client.rpc().get_signature_statuses(&[signature, Signature::default()]).await.unwrap();
+        assert!(statuses[0].as_ref().is_some_and(|status| status.err.is_none()));
+        assert!(statuses[1].is_none());
+
+        let tx = get_transaction_with_meta(client.rpc(), &signature).await.unwrap();
+        assert_eq!(tx.slot, backend.get_slot().await.unwrap());
+        assert!(tx.transaction.meta.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_transactions_and_program_accounts() {
+        let backend = LiteSvmBackend::new(LiteSVM::new());
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let err = backend
+            .send_transaction(&transfer(&backend, &payer, &recipient, 10_000_000_000))
+            .await
+            .unwrap_err();
+        assert!(err.get_transaction_error().is_some());
+        assert!(backend.get_account(&recipient).await.is_err());
+        assert!(backend.get_blocks_with_limit(0, 10).await.unwrap().is_empty());
+
+        backend.send_transaction(&transfer(&backend, &payer, &recipient, 1_000_000)).await.unwrap();
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(0)]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = backend.get_program_accounts(&system_program::id(), config).await.unwrap();
+        let addresses: Vec<Pubkey> = accounts.into_iter().map(|(address, _)| address).collect();
+        assert!(addresses.contains(&payer.pubkey()));
+        assert!(addresses.contains(&recipient));
+    }
+}
@@ -0,0 +1,232 @@
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{bail, Result};
+use reqwest::Client as HttpClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Keypair,
+};
+
+use crate::backend::RpcBackend;
+use crate::consts::*;
+use crate::spool::ReadStrategy;
+use crate::utils::ArchiveNode;
+use super::{
+    SpoolClient, PriorityFeePolicy, ComputeUnitPolicy, RetryPolicy, VerifyPolicy, LookupTablePolicy,
+};
+
+/// Builder for [`SpoolClient`]. An RPC endpoint (URL or client) and a signer are required,
+/// everything else has a default.
+#[derive(Default)]
+pub struct SpoolClientBuilder {
+    rpc_url: Option<String>,
+    rpc_client: Option<Arc<dyn RpcBackend>>,
+    signer: Option<Arc<Keypair>>,
+    commitment: Option<CommitmentConfig>,
+    priority_fee: PriorityFeePolicy,
+    compute_units: ComputeUnitPolicy,
+    retry: RetryPolicy,
+    verify: VerifyPolicy,
+    lookup_tables: LookupTablePolicy,
+    archive_nodes: Vec<ArchiveNode>,
+    read_strategy: ReadStrategy,
+    concurrency: Option<usize>,
+}
+
+impl SpoolClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to the RPC node at `url`.
+    pub fn rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.rpc_url = Some(url.into());
+        self
+    }
+
+    /// Use an existing RPC client, or any other [`RpcBackend`] such as `LiteSvmBackend`. Its
+    /// commitment is used as-is.
+    pub fn rpc_client(mut self, client: Arc<dyn RpcBackend>) -> Self {
+        self.rpc_client = Some(client);
+        self
+    }
+
+    /// Keypair that pays for and signs every transaction.
+    pub fn signer(mut self, signer: impl Into<Arc<Keypair>>) -> Self {
+        self.signer = Some(signer.into());
+        self
+    }
+
+    /// Commitment for reads and confirmations (only with `rpc_url`, defaults to confirmed).
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    pub fn priority_fee(mut self, policy: PriorityFeePolicy) -> Self {
+        self.priority_fee = policy;
+        self
+    }
+
+    pub fn compute_units(mut self, policy: ComputeUnitPolicy) -> Self {
+        self.compute_units = policy;
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn verify(mut self, policy: VerifyPolicy) -> Self {
+        self.verify = policy;
+        self
+    }
+
+    /// When uploads write through an address lookup table.
+    pub fn lookup_tables(mut self, policy: LookupTablePolicy) -> Self {
+        self.lookup_tables = policy;
+        self
+    }
+
+    /// Archive node to read segments from before walking blocks. Nodes are tried in the order
+    /// they are added.
+    pub fn archive_node(mut self, node: ArchiveNode) -> Self {
+        self.archive_nodes.push(node);
+        self
+    }
+
+    pub fn archive_nodes(mut self, nodes: impl IntoIterator<Item = ArchiveNode>) -> Self {
+        self.archive_nodes.extend(nodes);
+        self
+    }
+
+    /// How reads find the segments the archive nodes don't serve.
+    pub fn read_strategy(mut self, strategy: ReadStrategy) -> Self {
+        self.read_strategy = strategy;
+        self
+    }
+
+    /// Maximum number of transactions in flight for bulk operations such as
+    /// [`SpoolClient::write_chunks`].
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    pub fn build(self) -> Result<SpoolClient> {
+        let rpc: Arc<dyn RpcBackend> = match (self.rpc_url, self.rpc_client) {
+            (Some(url), None) => Arc::new(RpcClient::new_with_commitment(
+                url,
+                self.commitment.unwrap_or_else(CommitmentConfig::confirmed),
+            )),
+            (None, Some(client)) => {
+                if self.commitment.is_some() {
+                    bail!("Commitment can't be set for an existing RPC client, configure the client instead");
+                }
+                client
+            }
+            (Some(_), Some(_)) => bail!("Set either an RPC URL or an RPC client, not both"),
+            (None, None) => bail!("An RPC URL or an RPC client is required"),
+        };
+
+        let Some(signer) = self.signer else {
+            bail!("A signer is required");
+        };
+
+        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        if concurrency == 0 {
+            bail!("Concurrency must be at least 1");
+        }
+
+        Ok(SpoolClient {
+            rpc,
+            signer,
+            priority_fee: self.priority_fee,
+            compute_units: self.compute_units,
+            retry: self.retry,
+            verify: self.verify,
+            lookup_tables: self.lookup_tables,
+            http: shared_http_client(),
+            archive_nodes: self.archive_nodes.into(),
+            read_strategy: self.read_strategy,
+            concurrency,
+        })
+    }
+}
+
+/// HTTP client for archive node reads. It is shared so that short-lived clients, such as
+/// the ones behind the free-function wrappers, reuse one connection pool.
+fn shared_http_client() -> HttpClient {
+    static HTTP: OnceLock<HttpClient> = OnceLock::new();
+    HTTP.get_or_init(HttpClient::new).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    const URL: &str = "http://127.0.0.1:8899";
+
+    #[test]
+    fn test_build_from_url() {
+        let signer = Keypair::new();
+        let pubkey = signer.pubkey();
+        let client = SpoolClientBuilder::new()
+            .rpc_url(URL)
+            .signer(signer)
+            .commitment(CommitmentConfig::finalized())
+            .priority_fee(PriorityFeePolicy::Fixed(10))
+            .verify(VerifyPolicy::Strict)
+            .lookup_tables(LookupTablePolicy::Never)
+            .archive_node("http://127.0.0.1:3000/api".parse().unwrap())
+            .read_strategy(ReadStrategy::Signatures)
+            .concurrency(4)
+            .build()
+            .unwrap();
+
+        assert_eq!(client.pubkey(), pubkey);
+        assert_eq!(client.commitment(), CommitmentConfig::finalized());
+        assert_eq!(client.priority_fee_policy(), PriorityFeePolicy::Fixed(10));
+        assert_eq!(client.compute_unit_policy(), ComputeUnitPolicy::default());
+        assert_eq!(client.verify_policy(), VerifyPolicy::Strict);
+        assert_eq!(client.lookup_table_policy(), LookupTablePolicy::Never);
+        assert_eq!(client.archive_nodes().len(), 1);
+        assert_eq!(client.read_strategy(), ReadStrategy::Signatures);
+        assert_eq!(client.concurrency(), 4);
+    }
+
+    #[test]
+    fn test_build_requires_rpc_and_signer() {
+        assert!(SpoolClientBuilder::new().signer(Keypair::new()).build().is_err());
+        assert!(SpoolClientBuilder::new().rpc_url(URL).build().is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_conflicting_options() {
+        let rpc = Arc::new(RpcClient::new(URL.to_string()));
+
+        let res = SpoolClientBuilder::new()
+            .rpc_url(URL)
+            .rpc_client(rpc.clone())
+            .signer(Keypair::new())
+            .build();
+        assert!(res.is_err());
+
+        let res = SpoolClientBuilder::new()
+            .rpc_client(rpc)
+            .commitment(CommitmentConfig::processed())
+            .signer(Keypair::new())
+            .build();
+        assert!(res.is_err());
+
+        let res = SpoolClientBuilder::new()
+            .rpc_url(URL)
+            .signer(Keypair::new())
+            .concurrency(0)
+            .build();
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{close_lookup_table, deactivate_lookup_table},
+        AddressLookupTableAccount,
+    },
+    pubkey::Pubkey,
+    signature::Signature,
+};
+
+use crate::utils::*;
+use super::SpoolClient;
+
+impl SpoolClient {
+    /// Creates an address lookup table owned by the client signer holding `addresses`, and
+    /// waits until transactions can load them through it.
+    pub async fn create_lookup_table(
+        &self,
+        addresses: Vec<Pubkey>,
+    ) -> Result<(AddressLookupTableAccount, Signature)> {
+        let recent_slot = get_lookup_table_slot(&self.rpc).await?;
+        let (ixs, table_address) =
+            build_create_lookup_table_ixs(self.pubkey(), recent_slot, addresses.clone());
+
+        let signature = self.send_and_confirm(&ixs, None, &[]).await?;
+        wait_for_lookup_table(&self.rpc, get_slot(&self.rpc).await?).await?;
+
+        let table = AddressLookupTableAccount {
+            key: table_address,
+            addresses,
+        };
+        Ok((table, signature))
+    }
+
+    pub async fn get_lookup_table(&self, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+        get_lookup_table(&self.rpc, address).await
+    }
+
+    /// Deactivates a lookup table of the client signer. It can be closed once the slot it was
+    /// deactivated in leaves the slot hashes, about 513 slots later.
+    pub async fn deactivate_lookup_table(&self, address: Pubkey) -> Result<Signature> {
+        let ix = deactivate_lookup_table(address, self.pubkey());
+        self.send_and_confirm(&[ix], None, &[]).await
+    }
+
+    /// Closes a deactivated lookup table of the client signer, returning its rent to the signer.
+    pub async fn close_lookup_table(&self, address: Pubkey) -> Result<Signature> {
+        let ix = close_lookup_table(address, self.pubkey(), self.pubkey());
+        self.send_and_confirm(&[ix], None, &[]).await
+    }
+
+    /// Closes every lookup table of the client signer that was deactivated long enough ago, such
+    /// as the ones uploads leave behind, and returns their addresses. Tables still cooling
+    /// down are left for a later call.
+    pub async fn close_lookup_tables(&self) -> Result<Vec<Pubkey>> {
+        let tables = find_closable_lookup_tables(&self.rpc, &self.pubkey()).await?;
+        for address in &tables {
+            self.close_lookup_table(*address).await?;
+        }
+        Ok(tables)
+    }
+}
+
+#[cfg(all(test, feature = "litesvm"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use litesvm::LiteSVM;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    use crate::backend::{LiteSvmBackend, RpcBackend};
+
+    #[tokio::test]
+    async fn test_close_lookup_tables_after_cooldown() {
+        let backend = Arc::new(LiteSvmBackend::new(LiteSVM::new()));
+        let payer = Keypair::new();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+        let client = SpoolClient::new(backend.clone(), payer);
+
+        let (ixs, table_address) =
+            build_create_lookup_table_ixs(client.pubkey(), 0, vec![Pubkey::new_unique()]);
+        client.send_and_confirm(&ixs, None, &[]).await.unwrap();
+        backend.with_svm(|svm| svm.warp_to_slot(1));
+
+        // Active tables are never closed
+        assert!(client.close_lookup_tables().await.unwrap().is_empty());
+
+        client.deactivate_lookup_table(table_address).await.unwrap();
+        assert!(client.close_lookup_tables().await.unwrap().is_empty());
+
+        backend.with_svm(|svm| svm.warp_to_slot(600));
+        assert_eq!(client.close_lookup_tables().await.unwrap(), vec![table_address]);
+        assert!(backend.get_multiple_accounts(&[table_address]).await.unwrap()[0].is_none());
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+};
+
+use spool_api::prelude::*;
+use spool_api::instruction::miner::{build_register_ix, build_mine_ix, build_claim_ix};
+
+use crate::consts::*;
+use crate::utils::*;
+use super::SpoolClient;
+
+impl SpoolClient {
+    /// Registers a miner owned by the client signer.
+    pub async fn register_miner(&self, name: &str) -> Result<Signature> {
+        let register_ix = build_register_ix(self.pubkey(), name);
+
+        self.send_and_confirm(&[register_ix], Some(CU_REGISTER), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to register miner: {}", e))
+    }
+
+    /// Submits a proof of work and access for `spool_address`.
+    pub async fn perform_mining(
+        &self,
+        miner_address: Pubkey,
+        spool_address: Pubkey,
+        pow: PoW,
+        poa: PoA,
+    ) -> Result<Signature> {
+        let mine_ix = build_mine_ix(
+            self.pubkey(),
+            miner_address,
+            spool_address,
+            pow,
+            poa,
+        );
+
+        self.send_and_confirm(&[mine_ix], Some(CU_MINE), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to mine: {}", e))
+    }
+
+    /// Claims `amount` of the miner's rewards into `beneficiary`.
+    pub async fn claim_rewards(
+        &self,
+        miner_address: Pubkey,
+        beneficiary: Pubkey,
+        amount: u64,
+    ) -> Result<Signature> {
+        let claim_ix = build_claim_ix(self.pubkey(), miner_address, beneficiary, amount);
+
+        self.send_and_confirm(&[claim_ix], Some(CU_CLAIM), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to claim rewards: {}", e))
+    }
+
+    pub async fn get_miner_account(&self, miner_address: &Pubkey) -> Result<(Miner, Pubkey)> {
+        get_miner_account(&self.rpc, miner_address).await
+    }
+
+    pub async fn get_epoch_account(&self) -> Result<(Epoch, Pubkey)> {
+        get_epoch_account(&self.rpc).await
+    }
+
+    pub async fn get_block_account(&self) -> Result<(Block, Pubkey)> {
+        get_block_account(&self.rpc).await
+    }
+
+    pub async fn get_archive_account(&self) -> Result<(Archive, Pubkey)> {
+        get_archive_account(&self.rpc).await
+    }
+}
@@ -0,0 +1,254 @@
+mod builder;
+mod policy;
+mod program;
+mod spool;
+mod mine;
+mod reel;
+mod lookup_table;
+mod upload;
+mod offline;
+
+pub use builder::*;
+pub use policy::*;
+pub use upload::*;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::debug;
+use reqwest::Client as HttpClient;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use tokio::time::sleep;
+
+use crate::backend::RpcBackend;
+use crate::error::SpoolClientError;
+use crate::spool::ReadStrategy;
+use crate::utils::*;
+
+/// High-level client for the spool program. Holds the RPC connection (any [`RpcBackend`]), the
+/// signer, and the policies applied to every transaction it sends.
+///
+/// ```no_run
+/// # use solana_sdk::signature::Keypair;
+/// # use spool_client::{SpoolClient, PriorityFeePolicy};
+/// # async fn run() -> anyhow::Result<()> {
+/// let client = SpoolClient::builder()
+///     .rpc_url("https://api.devnet.solana.com")
+///     .signer(Keypair::new())
+///     .priority_fee(PriorityFeePolicy::Fixed(1_000))
+///     .build()?;
+///
+/// let (spool_address, writer_address, _) = client.create_spool("my-spool").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SpoolClient {
+    rpc: Arc<dyn RpcBackend>,
+    signer: Arc<Keypair>,
+    priority_fee: PriorityFeePolicy,
+    compute_units: ComputeUnitPolicy,
+    retry: RetryPolicy,
+    verify: VerifyPolicy,
+    lookup_tables: LookupTablePolicy,
+    http: HttpClient,
+    archive_nodes: Arc<[ArchiveNode]>,
+    read_strategy: ReadStrategy,
+    concurrency: usize,
+}
+
+impl SpoolClient {
+    pub fn builder() -> SpoolClientBuilder {
+        SpoolClientBuilder::new()
+    }
+
+    /// Creates a client with the default policies.
+    pub fn new(rpc: Arc<dyn RpcBackend>, signer: Keypair) -> Self {
+        SpoolClientBuilder::new()
+            .rpc_client(rpc)
+            .signer(signer)
+            .build()
+            .expect("rpc client and signer are set")
+    }
+
+    /// Client behind the free-function wrappers: preset compute unit limits and no priority
+    /// fee, so they send the same transactions as before `SpoolClient` existed.
+    pub(crate) fn preset(rpc: Arc<dyn RpcBackend>, signer: Keypair) -> Self {
+        SpoolClientBuilder::new()
+            .rpc_client(rpc)
+            .signer(signer)
+            .compute_units(ComputeUnitPolicy::Preset)
+            .priority_fee(PriorityFeePolicy::None)
+            .build()
+            .expect("rpc client and signer are set")
+    }
+
+    pub fn rpc(&self) -> &Arc<dyn RpcBackend> {
+        &self.rpc
+    }
+
+    pub fn signer(&self) -> &Keypair {
+        &self.signer
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.rpc.commitment()
+    }
+
+    pub fn priority_fee_policy(&self) -> PriorityFeePolicy {
+        self.priority_fee
+    }
+
+    pub fn compute_unit_policy(&self) -> ComputeUnitPolicy {
+        self.compute_units
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    pub fn verify_policy(&self) -> VerifyPolicy {
+        self.verify
+    }
+
+    pub fn lookup_table_policy(&self) -> LookupTablePolicy {
+        self.lookup_tables
+    }
+
+    pub fn archive_nodes(&self) -> &[ArchiveNode] {
+        &self.archive_nodes
+    }
+
+    pub fn read_strategy(&self) -> ReadStrategy {
+        self.read_strategy
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Fee strategy built from the compute unit and priority fee policies.
+    pub fn fee_strategy(&self) -> FeeStrategy {
+        FeeStrategy::new(self.compute_units, self.priority_fee)
+    }
+
+    /// Prepends the compute budget instructions required by the client policies to `ixs`,
+    /// simulating them or querying recent fees when the policies ask for it.
+    pub async fn with_compute_budget(&self, ixs: &[Instruction], default_limit: Option<u32>) -> Vec<Instruction> {
+        self.fee_strategy()
+            .apply(&self.rpc, ixs, &self.pubkey(), default_limit)
+            .await
+    }
+
+    /// Sends `ixs` in a single transaction paid for by the client signer and waits for
+    /// confirmation. `signers` are added to the client signer.
+    pub async fn send_and_confirm(
+        &self,
+        ixs: &[Instruction],
+        default_limit: Option<u32>,
+        signers: &[&Keypair],
+    ) -> Result<Signature> {
+        let mut all_signers: Vec<&Keypair> = vec![&self.signer];
+        all_signers.extend(signers.iter().filter(|s| s.pubkey() != self.pubkey()));
+
+        build_send_and_confirm_versioned_tx(
+            ixs,
+            &self.rpc,
+            self.pubkey(),
+            &all_signers,
+            &self.fee_strategy(),
+            default_limit,
+            &[],
+        )
+        .await
+    }
+
+    /// Sends `ixs` without waiting for confirmation, retrying with a fresh blockhash according
+    /// to the retry policy. Only use this for instructions that are safe to apply twice. Errors
+    /// that are not retryable, like the spool program rejecting the instructions, are returned
+    /// right away.
+    pub async fn send_with_retry(
+        &self,
+        ixs: &[Instruction],
+        default_limit: Option<u32>,
+    ) -> Result<Signature> {
+        self.send_with_retry_through(ixs, default_limit, &[]).await
+    }
+
+    /// Like `send_with_retry`, loading the accounts found in `tables` through them.
+    pub async fn send_with_retry_through(
+        &self,
+        ixs: &[Instruction],
+        default_limit: Option<u32>,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Signature> {
+        let (signature, _) = self.send_with_expiry_through(ixs, default_limit, tables).await?;
+        Ok(signature)
+    }
+
+    /// Like `send_with_retry_through`, also returning the last block height at which the sent
+    /// transaction can land. A transaction with no status past that height was dropped.
+    pub(crate) async fn send_with_expiry_through(
+        &self,
+        ixs: &[Instruction],
+        default_limit: Option<u32>,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<(Signature, u64)> {
+        let ixs = self.with_compute_budget(ixs, default_limit).await;
+        let max_retries = self.retry.max_retries;
+
+        let mut attempts = 0;
+        loop {
+            let (recent_blockhash, last_valid_block_height) = self
+                .rpc
+                .get_latest_blockhash_with_expiry()
+                .await
+                .map_err(|e| SpoolClientError::rpc(e, "Failed to fetch the latest blockhash"))?;
+
+            let tx = build_versioned_tx(&ixs, &self.pubkey(), &[&*self.signer], tables, recent_blockhash)?;
+
+            match send(&self.rpc, &tx).await {
+                Ok(signature) => return Ok((signature, last_valid_block_height)),
+                Err(e) if attempts < max_retries && e.is_retryable() => {
+                    attempts += 1;
+                    let delay = self.retry.backoff(attempts);
+
+                    debug!(
+                        "send_with_retry attempt {attempts}/{max_retries}, waiting {delay:?}: {e}"
+                    );
+
+                    sleep(delay).await;
+                }
+                Err(e) if !e.is_retryable() => return Err(e.into()),
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("Failed to send transaction after {max_retries} attempts")))
+                }
+            }
+        }
+    }
+
+    /// Creates the SPOOL token account of the client signer if needed.
+    pub async fn create_ata(&self) -> Result<(Pubkey, Signature)> {
+        create_ata(&self.rpc, &self.signer).await
+    }
+
+    /// SPOOL token account of the client signer.
+    pub fn ata_address(&self) -> Pubkey {
+        get_ata_address(&self.pubkey())
+    }
+
+    pub async fn get_token_balance(&self, ata: &Pubkey) -> Result<u64> {
+        get_token_balance(&self.rpc, ata).await
+    }
+}
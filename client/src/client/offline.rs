@@ -0,0 +1,365 @@
+use anyhow::{anyhow, bail, Result};
+use solana_client::nonce_utils::nonblocking::data_from_account;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    nonce,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::create_nonce_account_with_seed,
+    transaction::VersionedTransaction,
+};
+
+use spool_api::prelude::*;
+use spool_api::instruction::spool::{
+    build_create_ix,
+    build_write_ix,
+    build_set_header_ix,
+    build_subsidize_ix,
+    build_finalize_ix,
+};
+
+use crate::consts::*;
+use crate::spool::*;
+use crate::utils::{get_ata_address, get_token_balance, send_and_confirm, with_compute_budget};
+use super::{max_write_segments, SpoolClient, UploadProgress};
+
+/// Most accounts `getMultipleAccounts` returns in one request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+impl SpoolClient {
+    /// Makes sure `authority` has `count` durable nonce accounts to sign bundles with, creating
+    /// the missing ones (paid for by the client signer), and returns their addresses.
+    pub async fn create_nonce_accounts(&self, authority: Pubkey, count: usize) -> Result<Vec<Pubkey>> {
+        let addresses = (0..count)
+            .map(|index| nonce_account_address(&self.pubkey(), &authority, index))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut missing = Vec::new();
+        for (batch_index, batch) in addresses.chunks(MAX_MULTIPLE_ACCOUNTS).enumerate() {
+            let accounts = self.rpc.get_multiple_accounts(batch).await?;
+            for (offset, account) in accounts.iter().enumerate() {
+                if account.is_none() {
+                    missing.push(batch_index * MAX_MULTIPLE_ACCOUNTS + offset);
+                }
+            }
+        }
+
+        let rent = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(nonce::State::size())
+            .await?;
+
+        for batch in missing.chunks(NONCE_ACCOUNTS_PER_TX) {
+            let ixs: Vec<Instruction> = batch
+                .iter()
+                .flat_map(|&index| {
+                    create_nonce_account_with_seed(
+                        &self.pubkey(),
+                        &addresses[index],
+                        &self.pubkey(),
+                        &nonce_seed(&authority, index),
+                        &authority,
+                        rent,
+                    )
+                })
+                .collect();
+
+            self.send_and_confirm(&ixs, None, &[]).await?;
+        }
+
+        Ok(addresses)
+    }
+
+    /// Number of transactions (and so of nonce accounts) `plan_offline_upload` needs to upload
+    /// an encoded payload of `payload_len` bytes to the spool of `authority` named `name`.
+    pub fn offline_transaction_count(&self, authority: Pubkey, name: &str, payload_len: usize) -> usize {
+        let (spool_address, _spool_bump) = spool_pda(authority, &to_name(name));
+        let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+        let segments = offline_write_segments(authority, spool_address, writer_address);
+        payload_len.div_ceil(segments * SEGMENT_SIZE) + 2
+    }
+
+    /// Plans the upload of an encoded `payload` to a new spool of `authority` named `name` as a
+    /// bundle of unsigned transactions, one per nonce account. `header` must be the header
+    /// returned by `encode_spool` for the payload. Fails if there are fewer nonce accounts than
+    /// transactions, or if the authority can't pay the rent.
+    ///
+    /// Compute budgets come from the policies without querying the cluster, since the bundle is
+    /// submitted later.
+    pub async fn plan_offline_upload(
+        &self,
+        authority: Pubkey,
+        name: &str,
+        header: &SpoolHeader,
+        payload: &[u8],
+        nonce_accounts: &[Pubkey],
+    ) -> Result<SpoolBundle> {
+        let (spool_address, _spool_bump) = spool_pda(authority, &to_name(name));
+        let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+        if self.get_spool_account(&spool_address).await.is_ok() {
+            bail!("Spool {} already exists, plan the upload under another name", spool_address);
+        }
+
+        let unit_limit = self.compute_units.unit_limit(None);
+        let unit_price = self.priority_fee.unit_price();
+
+        let segments = offline_write_segments(authority, spool_address, writer_address);
+        let chunks: Vec<&[u8]> = payload.chunks(segments * SEGMENT_SIZE).collect();
+        let needed = chunks.len() + 2;
+        if nonce_accounts.len() < needed {
+            bail!(
+                "The upload needs {} nonce accounts (one per transaction), {} given",
+                needed, nonce_accounts.len()
+            );
+        }
+
+        let blockhashes = self.nonce_blockhashes(&authority, &nonce_accounts[..needed]).await?;
+
+        let segments = payload_segments(payload);
+        let total_segments = segments.len() as u64;
+        let merkle_root = segment_root(&spool_address, &segments)?;
+
+        let ata = get_ata_address(&authority);
+        let rent = min_finalization_rent(total_segments);
+        let balance = get_token_balance(&self.rpc, &ata).await.unwrap_or(0);
+        if balance < rent {
+            bail!("{} has {} SPOOL, the spool rent is {}", authority, balance, rent);
+        }
+
+        let header_data = header
+            .to_bytes()
+            .try_into()
+            .map_err(|_| anyhow!("Failed to convert header to bytes"))?;
+
+        let mut plan: Vec<(BundleStage, Vec<Instruction>)> = Vec::with_capacity(needed);
+        plan.push((BundleStage::Create, vec![build_create_ix(authority, authority, name)]));
+        for chunk in chunks {
+            let write_ix = build_write_ix(authority, spool_address, writer_address, chunk);
+            plan.push((BundleStage::Write, vec![write_ix]));
+        }
+        plan.push((
+            BundleStage::Finalize,
+            vec![
+                build_set_header_ix(authority, spool_address, &header_data),
+                build_subsidize_ix(authority, ata, spool_address, rent),
+                build_finalize_ix(authority, authority, spool_address, writer_address),
+            ],
+        ));
+
+        let mut bundle = SpoolBundle::new(
+            spool_address,
+            writer_address,
+            name,
+            authority,
+            total_segments,
+            merkle_root,
+        );
+        for ((stage, ixs), (nonce_account, blockhash)) in plan.into_iter().zip(blockhashes) {
+            let ixs = with_compute_budget(&ixs, unit_limit, unit_price);
+            let tx = build_nonce_tx(&ixs, &authority, &nonce_account, blockhash)?;
+            bundle.push(stage, nonce_account, &tx)?;
+        }
+
+        Ok(bundle)
+    }
+
+    /// Submits a signed bundle a stage at a time, skipping the transactions that already landed,
+    /// so a bundle can be submitted again after an interruption. The spool is only finalized if
+    /// it holds exactly the planned segments.
+    ///
+    /// Writes are sent one at a time in plan order: the program numbers segments in the order
+    /// writes land, and the planned merkle root only holds for that order.
+    pub async fn submit_bundle<F>(&self, bundle: &SpoolBundle, progress: F) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        bundle.verify_signatures()?;
+
+        let spool_address = bundle.spool_address()?;
+        let transactions = bundle.transactions()?;
+        let stage = |stage: BundleStage| -> Vec<VersionedTransaction> {
+            transactions
+                .iter()
+                .filter(|(s, _)| *s == stage)
+                .map(|(_, tx)| tx.clone())
+                .collect()
+        };
+
+        for tx in stage(BundleStage::Create) {
+            self.submit_signed(&tx).await?;
+        }
+        progress(UploadProgress::Created);
+
+        for tx in stage(BundleStage::Write) {
+            self.submit_signed(&tx).await?;
+            progress(UploadProgress::ChunkSent);
+        }
+
+        progress(UploadProgress::Waiting);
+        self.wait_for_segments(&spool_address, bundle.total_segments).await?;
+
+        progress(UploadProgress::Verifying);
+        let (spool, _) = self.get_spool_account(&spool_address).await?;
+        if spool.state != u64::from(SpoolState::Finalized)
+            && (spool.total_segments != bundle.total_segments || spool.merkle_root != bundle.merkle_root)
+        {
+            bail!(
+                "Spool {} has {} segments, expected {}, or doesn't match the planned merkle root, not finalizing",
+                spool_address, spool.total_segments, bundle.total_segments
+            );
+        }
+
+        progress(UploadProgress::Finalizing);
+        for tx in stage(BundleStage::Finalize) {
+            self.submit_signed(&tx).await?;
+        }
+
+        Ok(spool_address)
+    }
+
+    /// Sends and confirms a signed transaction, unless it already landed. Its nonce was
+    /// advanced if it did, so it couldn't land again anyway.
+    async fn submit_signed(&self, tx: &VersionedTransaction) -> Result<Signature> {
+        let signature = tx.signatures[0];
+        let status = self.rpc.get_signature_statuses(&[signature]).await?.remove(0);
+
+        match status {
+            Some(status) if status.err.is_none() => Ok(signature),
+            _ => Ok(send_and_confirm(&self.rpc, tx).await?),
+        }
+    }
+
+    /// The blockhash each nonce account holds, checking that `authority` can advance it.
+    async fn nonce_blockhashes(
+        &self,
+        authority: &Pubkey,
+        nonce_accounts: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Hash)>> {
+        let mut blockhashes = Vec::with_capacity(nonce_accounts.len());
+
+        for batch in nonce_accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = self.rpc.get_multiple_accounts(batch).await?;
+            for (address, account) in batch.iter().zip(accounts) {
+                let account = account
+                    .ok_or_else(|| anyhow!("Nonce account {} doesn't exist", address))?;
+                let data = data_from_account(&account)
+                    .map_err(|e| anyhow!("Invalid nonce account {}: {}", address, e))?;
+
+                if data.authority != *authority {
+                    bail!("Nonce account {} is not advanced by {}", address, authority);
+                }
+                blockhashes.push((*address, data.blockhash()));
+            }
+        }
+
+        Ok(blockhashes)
+    }
+}
+
+/// Most segments a write of a bundle can carry, with the nonce advanced first.
+fn offline_write_segments(authority: Pubkey, spool_address: Pubkey, writer_address: Pubkey) -> usize {
+    // Nonce accounts don't change the transaction size, so any of them sizes the writes
+    max_write_segments(authority, spool_address, writer_address, |write_ix| {
+        let ixs = with_compute_budget(&[write_ix], Some(u32::MAX), Some(u64::MAX));
+        build_nonce_tx(&ixs, &authority, &Pubkey::default(), Hash::default())
+            .ok()
+            .map(|tx| tx.message)
+    })
+}
+
+#[cfg(all(test, feature = "litesvm"))]
+mod tests {
+    use super::*;
+    use std::{path::PathBuf, str::FromStr, sync::Arc};
+
+    use litesvm::LiteSVM;
+    use solana_program::program_pack::Pack;
+    use solana_sdk::{
+        account::Account,
+        native_token::LAMPORTS_PER_SOL,
+        signature::{Keypair, Signer},
+    };
+    use spl_token::state::{Account as TokenAccount, AccountState};
+
+    use crate::backend::LiteSvmBackend;
+
+    const METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+    fn program_bytes(path: &str) -> Vec<u8> {
+        let mut so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        so_path.push(path);
+        std::fs::read(so_path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_submit_bundle_with_many_writes() {
+        let mut svm = LiteSVM::new();
+        svm.add_program(
+            Pubkey::from_str(METADATA_PROGRAM).unwrap(),
+            &program_bytes("../program/tests/elfs/metadata.so"),
+        );
+        svm.add_program(spool_api::ID, &program_bytes("../target/deploy/spool.so"));
+
+        let backend = Arc::new(LiteSvmBackend::new(svm));
+        let authority = Keypair::new();
+        backend.airdrop(&authority.pubkey(), 100 * LAMPORTS_PER_SOL).unwrap();
+
+        let client = SpoolClient::new(backend.clone(), authority.insecure_clone());
+        client.initialize().await.unwrap();
+
+        let name = "bundle";
+        let data: Vec<u8> = (0..4_000u32).map(|i| (i * 7) as u8).collect();
+        let mut header = SpoolHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            SpoolFlags::None,
+        );
+        let encoded = encode_spool(&data, &mut header).unwrap();
+
+        let count = client.offline_transaction_count(authority.pubkey(), name, encoded.len());
+        assert!(count > 4, "the payload should take several writes");
+
+        // Pay the spool rent without going through the faucet
+        let total_segments = payload_segments(&encoded).len() as u64;
+        let mut token_data = vec![0; TokenAccount::LEN];
+        TokenAccount {
+            mint: spool_api::MINT_ADDRESS,
+            owner: authority.pubkey(),
+            amount: min_finalization_rent(total_segments),
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        }
+        .pack_into_slice(&mut token_data);
+        let token_account = Account {
+            lamports: LAMPORTS_PER_SOL,
+            data: token_data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        backend.set_account(get_ata_address(&authority.pubkey()), token_account).unwrap();
+
+        let nonce_accounts = client.create_nonce_accounts(authority.pubkey(), count).await.unwrap();
+        // A nonce can only be advanced once the blockhash it was created with is gone
+        backend.with_svm(|svm| svm.expire_blockhash());
+
+        let mut bundle = client
+            .plan_offline_upload(authority.pubkey(), name, &header, &encoded, &nonce_accounts)
+            .await
+            .unwrap();
+        bundle.sign(&authority).unwrap();
+
+        let spool_address = client.submit_bundle(&bundle, |_| {}).await.unwrap();
+        let (spool, _) = client.get_spool_account(&spool_address).await.unwrap();
+        assert_eq!(spool.state, u64::from(SpoolState::Finalized));
+        assert_eq!(spool.total_segments, bundle.total_segments);
+        assert_eq!(spool.merkle_root, bundle.merkle_root);
+
+        // Every transaction landed, submitting again sends nothing
+        assert_eq!(client.submit_bundle(&bundle, |_| {}).await.unwrap(), spool_address);
+    }
+}
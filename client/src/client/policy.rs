@@ -0,0 +1,174 @@
+use tokio::time::Duration;
+
+use crate::consts::*;
+
+/// How `SpoolClient` prices its transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityFeePolicy {
+    /// Don't pay a priority fee.
+    None,
+    /// Pay a fixed price per compute unit, in micro-lamports.
+    Fixed(u64),
+    /// Pay the given percentile of the fees recently paid for the accounts the transaction
+    /// writes to, capped at `max_unit_price` micro-lamports.
+    Recent { percentile: u8, max_unit_price: u64 },
+}
+
+impl PriorityFeePolicy {
+    /// Compute unit price to set for a transaction without querying the cluster, if any.
+    /// `Recent` has no static price and returns `None`.
+    pub fn unit_price(&self) -> Option<u64> {
+        match self {
+            PriorityFeePolicy::None | PriorityFeePolicy::Recent { .. } => None,
+            PriorityFeePolicy::Fixed(price) => Some(*price),
+        }
+    }
+}
+
+impl Default for PriorityFeePolicy {
+    fn default() -> Self {
+        PriorityFeePolicy::Recent {
+            percentile: FEE_PERCENTILE,
+            max_unit_price: MAX_UNIT_PRICE,
+        }
+    }
+}
+
+/// How `SpoolClient` limits the compute units of its transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeUnitPolicy {
+    /// Use the per-instruction presets from `consts` (or the runtime default if there is none).
+    Preset,
+    /// Use the same limit for every transaction.
+    Fixed(u32),
+    /// Simulate the transaction and use the consumed units plus `margin_percent`. Falls back to
+    /// the preset if the simulation fails.
+    Simulate { margin_percent: u32 },
+}
+
+impl ComputeUnitPolicy {
+    /// Compute unit limit to set for a transaction without simulating it, given the preset.
+    pub fn unit_limit(&self, default_limit: Option<u32>) -> Option<u32> {
+        match self {
+            ComputeUnitPolicy::Preset | ComputeUnitPolicy::Simulate { .. } => default_limit,
+            ComputeUnitPolicy::Fixed(limit) => Some(*limit),
+        }
+    }
+}
+
+impl Default for ComputeUnitPolicy {
+    fn default() -> Self {
+        ComputeUnitPolicy::Simulate {
+            margin_percent: CU_MARGIN_PERCENT,
+        }
+    }
+}
+
+/// How `SpoolClient` checks the segments it reads against the spool's merkle root.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerifyPolicy {
+    /// Return the data as read.
+    Skip,
+    /// Log a warning with the verification report if the data doesn't match the root.
+    #[default]
+    Warn,
+    /// Fail the read if the data doesn't match the root.
+    Strict,
+}
+
+/// Whether `SpoolClient::upload` writes through an address lookup table holding the spool and
+/// writer accounts. The table is created for the upload, reused when it is resumed and
+/// deactivated once the spool is finalized.
+///
+/// Tables don't speed uploads up at the current `SEGMENT_SIZE`: a write loads only the signer,
+/// spool and writer accounts, and the two keys a table saves are less than a segment, so each
+/// write carries as many segments either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LookupTablePolicy {
+    /// Write with static account keys.
+    #[default]
+    Never,
+    /// Write through a table created for the upload. Its rent is only recovered once it is
+    /// closed, see `SpoolClient::close_lookup_tables`.
+    Always,
+}
+
+/// How `SpoolClient` retries sends that are safe to repeat (segment writes).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on every retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_RETRIES,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(WAIT_TIME),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policies_are_dynamic() {
+        assert_eq!(
+            ComputeUnitPolicy::default(),
+            ComputeUnitPolicy::Simulate { margin_percent: CU_MARGIN_PERCENT }
+        );
+        assert_eq!(
+            PriorityFeePolicy::default(),
+            PriorityFeePolicy::Recent {
+                percentile: FEE_PERCENTILE,
+                max_unit_price: MAX_UNIT_PRICE,
+            }
+        );
+    }
+
+    #[test]
+    fn test_static_fallbacks() {
+        let simulate = ComputeUnitPolicy::default();
+        assert_eq!(simulate.unit_limit(None), None);
+        assert_eq!(simulate.unit_limit(Some(CU_MINE)), Some(CU_MINE));
+        assert_eq!(ComputeUnitPolicy::Preset.unit_limit(Some(CU_MINE)), Some(CU_MINE));
+        assert_eq!(ComputeUnitPolicy::Fixed(42_000).unit_limit(Some(CU_MINE)), Some(42_000));
+
+        assert_eq!(PriorityFeePolicy::default().unit_price(), None);
+        assert_eq!(PriorityFeePolicy::None.unit_price(), None);
+        assert_eq!(PriorityFeePolicy::Fixed(1_000).unit_price(), Some(1_000));
+    }
+
+    #[test]
+    fn test_retry_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), policy.initial_backoff);
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(1000), policy.max_backoff);
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+};
+
+use spool_api::instruction::program::{build_initialize_ix, build_airdrop_ix, build_configure_faucet_ix};
+
+use crate::consts::*;
+use crate::program::*;
+use super::SpoolClient;
+
+impl SpoolClient {
+    /// Initializes the program, with the client signer as the genesis spool authority.
+    pub async fn initialize(&self) -> Result<Signature> {
+        let initialize_ix = build_initialize_ix(self.pubkey());
+
+        self.send_and_confirm(&[initialize_ix], Some(CU_INITIALIZE), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to initialize program: {}", e))
+    }
+
+    /// Claims `amount` SPOOL from the faucet into `beneficiary`.
+    pub async fn airdrop_tokens(&self, beneficiary: Pubkey, amount: u64) -> Result<Signature> {
+        let airdrop_ix = build_airdrop_ix(self.pubkey(), beneficiary, amount);
+
+        self.send_and_confirm(&[airdrop_ix], Some(CU_AIRDROP), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to airdrop tokens: {}", e))
+    }
+
+    /// Sets the faucet limits. The client signer must be the genesis spool authority.
+    pub async fn configure_faucet(
+        &self,
+        cap: u64,
+        window_seconds: i64,
+        cooldown_seconds: i64,
+    ) -> Result<Signature> {
+        let configure_ix = build_configure_faucet_ix(self.pubkey(), cap, window_seconds, cooldown_seconds);
+
+        self.send_and_confirm(&[configure_ix], Some(CU_CONFIGURE_FAUCET), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to configure the faucet: {}", e))
+    }
+
+    /// Returns the faucet allowance of the client signer.
+    pub async fn get_faucet_allowance(&self) -> Result<FaucetAllowance> {
+        get_faucet_allowance(&self.rpc, self.pubkey()).await
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+};
+
+use spool_api::consts::SPOOL_PROOF_LEN;
+use spool_api::instruction::reel::{
+    build_create_ix,
+    build_destroy_ix,
+    build_pack_ix,
+    build_unpack_ix,
+    build_commit_ix,
+};
+use spool_api::types::*;
+
+use crate::consts::*;
+use super::SpoolClient;
+
+impl SpoolClient {
+    /// Creates reel `number` for the miner.
+    pub async fn create_reel(&self, miner_address: Pubkey, number: u64) -> Result<Signature> {
+        let create_ix = build_create_ix(self.pubkey(), miner_address, number);
+
+        self.send_and_confirm(&[create_ix], Some(CU_REEL_CREATE), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to create reel: {}", e))
+    }
+
+    /// Destroys reel `number` of the miner.
+    pub async fn destroy_reel(&self, miner_address: Pubkey, number: u64) -> Result<Signature> {
+        let destroy_ix = build_destroy_ix(self.pubkey(), miner_address, number);
+
+        self.send_and_confirm(&[destroy_ix], Some(CU_REEL_DESTROY), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to destroy reel: {}", e))
+    }
+
+    /// Packs a spool into the reel.
+    pub async fn pack_spool(
+        &self,
+        reel_address: Pubkey,
+        spool_address: Pubkey,
+        value: [u8; 32],
+    ) -> Result<Signature> {
+        let pack_ix = build_pack_ix(self.pubkey(), reel_address, spool_address, value);
+
+        self.send_and_confirm(&[pack_ix], Some(CU_PACK), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to pack spool: {}", e))
+    }
+
+    /// Unpacks the spool at `index` from the reel.
+    pub async fn unpack_spool(
+        &self,
+        reel_address: Pubkey,
+        index: u64,
+        proof: [[u8; 32]; SPOOL_PROOF_LEN],
+        value: [u8; 32],
+    ) -> Result<Signature> {
+        let unpack_ix = build_unpack_ix(self.pubkey(), reel_address, index, proof, value);
+
+        self.send_and_confirm(&[unpack_ix], Some(CU_UNPACK), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to unpack spool: {}", e))
+    }
+
+    /// Commits a solution for the reel.
+    pub async fn commit_solution(
+        &self,
+        miner_address: Pubkey,
+        reel_address: Pubkey,
+        index: u64,
+        proof: ProofPath,
+        value: [u8; 32],
+    ) -> Result<Signature> {
+        let commit_ix = build_commit_ix(self.pubkey(), miner_address, reel_address, index, proof, value);
+
+        self.send_and_confirm(&[commit_ix], Some(CU_COMMIT), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to commit solution: {}", e))
+    }
+}
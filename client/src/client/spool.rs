@@ -0,0 +1,461 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use tokio::{io::AsyncWrite, sync::Semaphore, task};
+use std::{io, sync::Arc};
+
+use spool_api::prelude::*;
+use spool_api::instruction::spool::{
+    build_create_ix,
+    build_write_ix,
+    build_set_header_ix,
+    build_subsidize_ix,
+    build_finalize_ix,
+    build_mint_ownership_token_ix,
+};
+use solana_sdk::account::Account;
+
+use crate::consts::*;
+use crate::spool::*;
+use crate::utils::*;
+use super::{SpoolClient, VerifyPolicy};
+
+impl SpoolClient {
+    /// Creates a new spool owned by the client signer and returns the spool address, writer
+    /// address, and signature.
+    pub async fn create_spool(&self, name: &str) -> Result<(Pubkey, Pubkey, Signature)> {
+        self.create_spool_with_authority(&self.signer, name).await
+    }
+
+    /// Creates a new spool owned by `authority`. The client signer funds the accounts and the
+    /// transaction fee.
+    pub async fn create_spool_with_authority(
+        &self,
+        authority: &Keypair,
+        name: &str,
+    ) -> Result<(Pubkey, Pubkey, Signature)> {
+        let (spool_address, _spool_bump) = spool_pda(authority.pubkey(), &to_name(name));
+        let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+        let create_ix = build_create_ix(
+            self.pubkey(),
+            authority.pubkey(),
+            name,
+        );
+
+        let signature = self.send_and_confirm(&[create_ix], None, &[authority]).await?;
+
+        Ok((spool_address, writer_address, signature))
+    }
+
+    /// Appends `data` to the spool. Writes are sent with the retry policy and are not
+    /// confirmed.
+    pub async fn write_to_spool(
+        &self,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        data: &[u8],
+    ) -> Result<Signature> {
+        self.write_to_spool_through(spool_address, writer_address, data, None).await
+    }
+
+    /// Like `write_to_spool`, loading the spool and writer accounts through `table` if given.
+    pub async fn write_to_spool_through(
+        &self,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        data: &[u8],
+        table: Option<&AddressLookupTableAccount>,
+    ) -> Result<Signature> {
+        let (signature, _) = self
+            .write_to_spool_with_expiry(spool_address, writer_address, data, table)
+            .await?;
+        Ok(signature)
+    }
+
+    /// Like `write_to_spool_through`, also returning the last block height at which the write
+    /// can land.
+    pub(crate) async fn write_to_spool_with_expiry(
+        &self,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        data: &[u8],
+        table: Option<&AddressLookupTableAccount>,
+    ) -> Result<(Signature, u64)> {
+        let write_ix = build_write_ix(
+            self.pubkey(),
+            spool_address,
+            writer_address,
+            data,
+        );
+
+        let tables = table.map(std::slice::from_ref).unwrap_or_default();
+        self.send_with_expiry_through(&[write_ix], None, tables).await
+    }
+
+    /// Writes every chunk to the spool, keeping up to `concurrency` writes in flight, and
+    /// returns the signatures in chunk order. `on_write` is called after each write.
+    pub async fn write_chunks<F>(
+        &self,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        chunks: Vec<Vec<u8>>,
+        on_write: F,
+    ) -> Result<Vec<Signature>>
+    where
+        F: Fn(&Signature) + Send + Sync + 'static,
+    {
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let on_write = Arc::new(on_write);
+
+        let mut handles = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let client = self.clone();
+            let semaphore = semaphore.clone();
+            let on_write = on_write.clone();
+
+            let handle: task::JoinHandle<Result<Signature>> = task::spawn(async move {
+                let _permit = semaphore.acquire().await?;
+                let sig = client
+                    .write_to_spool(spool_address, writer_address, &chunk)
+                    .await?;
+                on_write(&sig);
+                Ok(sig)
+            });
+            handles.push(handle);
+        }
+
+        let mut signatures = Vec::with_capacity(handles.len());
+        for handle in handles {
+            signatures.push(handle.await??);
+        }
+
+        Ok(signatures)
+    }
+
+    /// Sets the spool header.
+    pub async fn set_header(
+        &self,
+        spool_address: Pubkey,
+        header: SpoolHeader,
+    ) -> Result<(Pubkey, Signature)> {
+        let header_data = &header.to_bytes().try_into()
+            .map_err(|_| anyhow!("Failed to convert header to bytes"))?;
+
+        let set_header_ix = build_set_header_ix(
+            self.pubkey(),
+            spool_address,
+            header_data,
+        );
+
+        let signature = self.send_and_confirm(&[set_header_ix], None, &[]).await?;
+
+        Ok((spool_address, signature))
+    }
+
+    /// Pays `amount` SPOOL from `ata` as rent for the spool.
+    pub async fn subsidize_spool(
+        &self,
+        spool_address: Pubkey,
+        ata: Pubkey,
+        amount: u64,
+    ) -> Result<Signature> {
+        let subsidize_ix = build_subsidize_ix(
+            self.pubkey(),
+            ata,
+            spool_address,
+            amount,
+        );
+
+        self.send_and_confirm(&[subsidize_ix], Some(CU_SUBSIDIZE), &[])
+            .await
+            .map_err(|e| anyhow!("Failed to subsidize spool: {}", e))
+    }
+
+    /// Finalizes a spool owned by the client signer.
+    pub async fn finalize_spool(
+        &self,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+    ) -> Result<Signature> {
+        self.finalize_spool_with_authority(&self.signer, spool_address, writer_address).await
+    }
+
+    /// Finalizes a spool owned by `authority`. The client signer pays the fee and receives the
+    /// writer's rent back.
+    pub async fn finalize_spool_with_authority(
+        &self,
+        authority: &Keypair,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+    ) -> Result<Signature> {
+        let finalize_ix = build_finalize_ix(
+            self.pubkey(),
+            authority.pubkey(),
+            spool_address,
+            writer_address,
+        );
+
+        self.send_and_confirm(&[finalize_ix], None, &[authority]).await
+    }
+
+    /// Mints the ownership token of a spool owned by the client signer.
+    pub async fn mint_ownership_token(&self, spool_address: Pubkey) -> Result<(Pubkey, Signature)> {
+        self.mint_ownership_token_with_authority(&self.signer, spool_address).await
+    }
+
+    /// Mints the ownership token of a spool owned by `authority` to the authority and returns
+    /// the mint address and signature.
+    pub async fn mint_ownership_token_with_authority(
+        &self,
+        authority: &Keypair,
+        spool_address: Pubkey,
+    ) -> Result<(Pubkey, Signature)> {
+        let (mint_address, _mint_bump) = ownership_pda(spool_address);
+
+        let mint_ix = build_mint_ownership_token_ix(
+            self.pubkey(),
+            authority.pubkey(),
+            spool_address,
+        );
+
+        let signature = self.send_and_confirm(&[mint_ix], None, &[authority]).await?;
+
+        Ok((mint_address, signature))
+    }
+
+    pub async fn get_spool_account(&self, spool_address: &Pubkey) -> Result<(Spool, Pubkey)> {
+        get_spool_account(&self.rpc, spool_address).await
+    }
+
+    pub async fn get_spool_account_if_exists(&self, spool_address: &Pubkey) -> Result<Option<Spool>> {
+        get_spool_account_if_exists(&self.rpc, spool_address).await
+    }
+
+    pub async fn find_spool_account(&self, number: u64) -> Result<Option<(Pubkey, Account)>> {
+        find_spool_account(&self.rpc, number).await
+    }
+
+    pub async fn get_writer_account(&self, writer_address: &Pubkey) -> Result<(Writer, Pubkey)> {
+        get_writer_account(&self.rpc, writer_address).await
+    }
+
+    /// Reads the raw segments of a spool, walking back from `slot`.
+    pub async fn get_spool_segments_starting_at(
+        &self,
+        spool_address: &Pubkey,
+        slot: u64,
+    ) -> Result<Vec<u8>> {
+        get_spool_segments_starting_at(&self.rpc, spool_address, slot).await
+    }
+
+    /// Streams the events of the spool program, see `subscribe_events`. `ws_url` is the
+    /// websocket endpoint of the client's RPC node.
+    pub fn subscribe_events(&self, ws_url: &str, kind: SubscriptionKind, from_slot: Option<u64>) -> EventStream {
+        subscribe_events(self.rpc.clone(), ws_url, kind, from_slot)
+    }
+
+    /// Reads and decodes a spool, returning its header and data.
+    pub async fn read_spool(&self, spool_address: &Pubkey) -> Result<(SpoolHeader, Vec<u8>)> {
+        self.read_spool_with_key(spool_address, None).await
+    }
+
+    /// Reads and decodes a spool, decrypting it with `key` if it is encrypted.
+    pub async fn read_spool_with_key(
+        &self,
+        spool_address: &Pubkey,
+        key: Option<&SpoolKey>,
+    ) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (header, data) = self.read_raw_spool(spool_address).await?;
+        Ok((header, decode_spool_with_key(data, &header, key)?))
+    }
+
+    /// Reads and decodes a spool shared with `keypair` through a key envelope. If the spool is
+    /// an envelope that references another spool, that spool is read instead.
+    pub async fn read_spool_with_keypair(
+        &self,
+        spool_address: &Pubkey,
+        keypair: &Keypair,
+    ) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (header, data) = self.read_raw_spool(spool_address).await?;
+        let (key, reference) = open_envelope(data.clone(), &header, keypair)?;
+
+        match reference {
+            Some(reference) => self.read_spool_with_key(&reference, Some(&key)).await,
+            None => Ok((header, decode_spool_with_key(data, &header, Some(&key))?)),
+        }
+    }
+
+    /// Reads a directory spool (see `Directory`), decrypting it with `key` if it is encrypted.
+    pub async fn read_directory(
+        &self,
+        spool_address: &Pubkey,
+        key: Option<&SpoolKey>,
+    ) -> Result<Directory> {
+        let (header, data) = self.read_spool_with_key(spool_address, key).await?;
+        if !header.has_flag(SpoolFlags::Directory) {
+            return Err(anyhow!("Spool {} is not a directory", spool_address));
+        }
+
+        Directory::parse(&data)
+    }
+
+    /// Reads a spool and writes its decoded data to `writer`, decrypting it with `key` if it is
+    /// encrypted. Segments are decoded one at a time instead of being concatenated first.
+    /// Returns the header.
+    pub async fn read_spool_into<W: AsyncWrite + Unpin>(
+        &self,
+        spool_address: &Pubkey,
+        key: Option<&SpoolKey>,
+        writer: W,
+    ) -> Result<SpoolHeader> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+        let header = *SpoolHeader::try_from_bytes(&spool.header)?;
+
+        // The parts of a manifest are stitched in memory before decoding
+        if header.has_flag(SpoolFlags::Manifest) {
+            let (header, data) = self.read_raw_spool(spool_address).await?;
+            decode_spool_async(io::Cursor::new(data), &header, key, writer).await?;
+            return Ok(header);
+        }
+
+        let state = self.collect_segments(spool_address, &spool).await?;
+
+        decode_spool_async(state.into_reader(), &header, key, writer).await?;
+        Ok(header)
+    }
+
+    /// Reads the header and the raw (still encoded) data of a spool. The segments are checked
+    /// against the merkle root of the spool as the verify policy says. Manifest spools are
+    /// resolved: the payload is stitched from their parts and returned with the header to
+    /// decode it with.
+    pub async fn read_raw_spool(&self, spool_address: &Pubkey) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (header, data) = self.read_spool_data(spool_address).await?;
+        if !header.has_flag(SpoolFlags::Manifest) {
+            return Ok((header, data));
+        }
+
+        let manifest = read_manifest(data, &header)?;
+        let payload = self.read_manifest_parts(&manifest).await?;
+
+        Ok((manifest.payload_header(&header), payload))
+    }
+
+    /// Reads the parts of a manifest, up to `concurrency` at a time, and stitches them back into
+    /// the payload.
+    pub async fn read_manifest_parts(&self, manifest: &Manifest) -> Result<Vec<u8>> {
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+
+        let mut handles = Vec::with_capacity(manifest.parts.len());
+        for part in &manifest.parts {
+            let client = self.clone();
+            let semaphore = semaphore.clone();
+            let part_address = part.address;
+
+            let handle: task::JoinHandle<Result<Vec<u8>>> = task::spawn(async move {
+                let _permit = semaphore.acquire().await?;
+                let (header, data) = client.read_spool_data(&part_address).await?;
+                if header.has_flag(SpoolFlags::Manifest) {
+                    return Err(anyhow!("Part spool {} is a manifest itself", part_address));
+                }
+                decode_spool(data, &header)
+            });
+            handles.push(handle);
+        }
+
+        let mut parts = Vec::with_capacity(handles.len());
+        for handle in handles {
+            parts.push(handle.await??);
+        }
+
+        manifest.stitch(parts)
+    }
+
+    /// Reads the header and the raw data of a single spool, without resolving manifests.
+    async fn read_spool_data(&self, spool_address: &Pubkey) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+        let header = *SpoolHeader::try_from_bytes(&spool.header)?;
+
+        let state = self.collect_segments(spool_address, &spool).await?;
+
+        Ok((header, finalize_read(state)?))
+    }
+
+    /// Reads the header and the raw data of a spool as it was at the end of `slot`. The data
+    /// isn't checked against the merkle root, which only matches the latest segments.
+    pub async fn read_raw_spool_at_slot(
+        &self,
+        spool_address: &Pubkey,
+        slot: u64,
+    ) -> Result<(SpoolHeader, Vec<u8>)> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+        let header = *SpoolHeader::try_from_bytes(&spool.header)?;
+
+        let state = collect_spool_segments_at(
+            &self.rpc,
+            self.read_strategy,
+            spool_address,
+            &spool,
+            slot,
+            |_| {},
+        )
+        .await?;
+
+        Ok((header, finalize_read(state)?))
+    }
+
+    /// Reads every version of the segments of a spool.
+    pub async fn spool_history(&self, spool_address: &Pubkey) -> Result<SpoolHistory> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+        read_spool_history(&self.rpc, self.read_strategy, spool_address, &spool, |_| {}).await
+    }
+
+    /// Reads the segments of a spool and checks them against its merkle root.
+    pub async fn verify_spool(&self, spool_address: &Pubkey) -> Result<ReadVerification> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+        let state = self.collect_unverified(spool_address, &spool).await?;
+
+        verify_read(spool_address, &spool, &state.segments)
+    }
+
+    /// Collects the segments of a spool, checking them as the verify policy says.
+    async fn collect_segments(&self, spool_address: &Pubkey, spool: &Spool) -> Result<ReadState> {
+        let state = self.collect_unverified(spool_address, spool).await?;
+
+        if self.verify == VerifyPolicy::Skip {
+            return Ok(state);
+        }
+
+        let report = verify_read(spool_address, spool, &state.segments)?;
+        if !report.is_ok() {
+            if self.verify == VerifyPolicy::Strict {
+                return Err(anyhow!(
+                    "Spool {} doesn't match its merkle root:\n{}",
+                    spool_address,
+                    report
+                ));
+            }
+            warn!("Spool {} doesn't match its merkle root:\n{}", spool_address, report);
+        }
+
+        Ok(state)
+    }
+
+    /// Collects the segments of a spool from the archive nodes, then from the chain.
+    async fn collect_unverified(&self, spool_address: &Pubkey, spool: &Spool) -> Result<ReadState> {
+        collect_spool_segments(
+            &self.rpc,
+            &self.http,
+            &self.archive_nodes,
+            self.read_strategy,
+            spool_address,
+            spool,
+            |_| {},
+        )
+        .await
+    }
+}
@@ -0,0 +1,851 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail, Result};
+use log::{debug, warn};
+use solana_client::rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::VersionedMessage,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use tokio::{sync::Semaphore, task::JoinSet, time::{sleep, Duration, Instant}};
+
+use spool_api::prelude::*;
+use spool_api::instruction::spool::build_write_ix;
+
+use crate::consts::*;
+use crate::spool::*;
+use crate::utils::{compile_message, fits_in_packet, with_compute_budget};
+use super::{LookupTablePolicy, SpoolClient};
+
+/// Progress of an upload, reported through the callback of [`SpoolClient::upload`] and
+/// [`SpoolClient::resume_upload`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadProgress {
+    /// Starting the upload of part `index` of `total` of a payload split across spools (see
+    /// [`SpoolClient::upload_manifest`]); `total` is the manifest spool, uploaded last.
+    Part { index: usize, total: usize },
+    /// The spool account was created.
+    Created,
+    /// The journal was checked against the chain; `confirmed` of `total` chunks are on-chain.
+    Reconciled { confirmed: usize, total: usize },
+    /// A chunk write was sent.
+    ChunkSent,
+    /// Waiting for sent writes to land.
+    Waiting,
+    /// Comparing the on-chain segments against the payload before finalizing.
+    Verifying,
+    SettingHeader,
+    Subsidizing,
+    Finalizing,
+}
+
+impl SpoolClient {
+    /// Uploads an encoded `payload` to a new spool named `name`, keeping a journal in
+    /// `journal_dir` so the upload can be finished with [`SpoolClient::resume_upload`] if it is
+    /// interrupted. `header` must be the header returned by `encode_spool` for the payload.
+    ///
+    /// Each write carries as many segments as fit in a transaction. With
+    /// `LookupTablePolicy::Always` the writes load the spool and writer accounts through a table
+    /// created for the upload, and the tables earlier uploads left deactivated are closed first.
+    ///
+    /// Fails if the spool or a journal for it already exists; unfinished uploads are finished
+    /// with [`SpoolClient::resume_upload`].
+    pub async fn upload<F>(
+        &self,
+        journal_dir: &Path,
+        name: &str,
+        header: &SpoolHeader,
+        payload: &[u8],
+        progress: F,
+    ) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        let (spool_address, _spool_bump) = spool_pda(self.pubkey(), &to_name(name));
+        let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+        // A new journal would replace the saved one, and the lookup table recorded in it
+        if UploadJournal::path(journal_dir, &spool_address).exists() {
+            bail!(
+                "An upload to spool {} is already in progress, finish it with resume_upload",
+                spool_address
+            );
+        }
+        if self.get_spool_account_if_exists(&spool_address).await?.is_some() {
+            bail!(
+                "Spool {} already exists, resume its upload with resume_upload or upload under another name",
+                spool_address
+            );
+        }
+
+        let (use_table, segments) = self.plan_writes(spool_address, writer_address);
+
+        // The journal is saved before anything is created on-chain, so a crash from here on
+        // leaves an upload that can be resumed
+        let mut journal = UploadJournal::new(
+            spool_address,
+            writer_address,
+            name,
+            header,
+            payload,
+            SEGMENT_SIZE * segments,
+        )?;
+        journal.save(journal_dir)?;
+
+        if use_table {
+            match self.close_lookup_tables().await {
+                Ok(closed) if !closed.is_empty() => debug!("Closed lookup tables {:?}", closed),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to close deactivated lookup tables: {}", e),
+            }
+
+            self.attach_lookup_table(journal_dir, &mut journal).await?;
+        }
+
+        self.resume_upload(journal_dir, &spool_address, payload, progress).await
+    }
+
+    /// Finishes the upload recorded in the journal of `spool_address`: creates the spool if
+    /// needed, writes the chunks that aren't on-chain yet and checks that the spool holds
+    /// exactly the payload, then sets the header, pays the rent and finalizes. A spool that is
+    /// already finalized is only checked against the payload. The journal is removed once the
+    /// spool is finalized.
+    pub async fn resume_upload<F>(
+        &self,
+        journal_dir: &Path,
+        spool_address: &Pubkey,
+        payload: &[u8],
+        progress: F,
+    ) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        let progress = Arc::new(progress);
+        let mut journal = UploadJournal::load(journal_dir, spool_address)?;
+        journal.verify_payload(payload)?;
+
+        let writer_address = journal.writer_address()?;
+
+        let spool = match self.get_spool_account_if_exists(spool_address).await? {
+            Some(spool) => spool,
+            None => {
+                self.create_spool(&journal.name).await?;
+                progress(UploadProgress::Created);
+                self.get_spool_account(spool_address).await?.0
+            }
+        };
+
+        if spool.authority != self.pubkey() {
+            bail!("Spool {} is not owned by {}", spool_address, self.pubkey());
+        }
+
+        if spool.state != u64::from(SpoolState::Finalized) {
+            // A crash between saving the journal and recording its table leaves chunks that only
+            // fit a write through a table
+            let without_table = segments_per_write(self.pubkey(), *spool_address, writer_address, None);
+            if journal.lookup_table.is_none() && journal.chunk_size > SEGMENT_SIZE * without_table {
+                self.attach_lookup_table(journal_dir, &mut journal).await?;
+            }
+
+            self.write_verified(journal_dir, &mut journal, payload, progress.clone()).await?;
+            self.finish_upload(&journal, spool_address, &writer_address, progress.as_ref()).await?;
+        } else {
+            progress(UploadProgress::Verifying);
+            match self.check_upload(&journal, payload, &spool).await? {
+                Some(report) if report.is_ok() => {}
+                Some(report) => bail!(
+                    "Spool {} is already finalized with other data:\n{}",
+                    spool_address, report
+                ),
+                None => bail!(
+                    "Segments read back from finalized spool {} don't match its merkle root",
+                    spool_address
+                ),
+            }
+        }
+
+        // The table is only needed for writes. Once it has cooled down `close_lookup_tables`
+        // recovers its rent
+        if let Some(table_address) = journal.lookup_table()? {
+            if let Err(e) = self.deactivate_lookup_table(table_address).await {
+                warn!("Failed to deactivate lookup table {}: {}", table_address, e);
+            }
+        }
+
+        journal.remove(journal_dir)?;
+
+        Ok(*spool_address)
+    }
+
+    /// Uploads an encoded `payload` too large for a single spool (see `needs_manifest`): the
+    /// payload is split into part spools named after `name`, then a manifest spool named `name`
+    /// lists them. Parts already finalized by an earlier attempt are checked against the payload
+    /// instead of being uploaded again, and unfinished ones are resumed. Returns the address of
+    /// the manifest spool.
+    pub async fn upload_manifest<F>(
+        &self,
+        journal_dir: &Path,
+        name: &str,
+        header: &SpoolHeader,
+        payload: &[u8],
+        progress: F,
+    ) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        let progress = Arc::new(progress);
+        let processed = if header.has_flag(SpoolFlags::Prefixed) {
+            unprefix_segments(payload.to_vec(), header.data_len as usize)?
+        } else {
+            payload.to_vec()
+        };
+
+        let chunks: Vec<&[u8]> = processed.chunks(max_payload_len(header.erasure())).collect();
+        let mut parts = Vec::with_capacity(chunks.len());
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            progress(UploadProgress::Part { index, total: chunks.len() });
+
+            let part_name = manifest_part_name(name, index);
+            let (part_address, _) = spool_pda(self.pubkey(), &to_name(&part_name));
+            let part = ManifestPart::new(part_address, chunk);
+
+            let finalized = self
+                .get_spool_account_if_exists(&part_address)
+                .await?
+                .is_some_and(|spool| spool.state == u64::from(SpoolState::Finalized));
+
+            if finalized {
+                let (part_header, data) = self.read_raw_spool(&part_address).await?;
+                part.check(&decode_spool(data, &part_header)?)
+                    .map_err(|e| anyhow!("{}, upload the payload under another name", e))?;
+            } else {
+                let mut part_header = SpoolHeader::new(
+                    MimeType::Unknown,
+                    CompressionAlgo::None,
+                    EncryptionAlgo::None,
+                    SpoolFlags::Prefixed,
+                );
+                part_header.set_erasure(header.erasure());
+                let encoded = encode_spool(chunk, &mut part_header)?;
+                let progress = progress.clone();
+                self.upload_or_resume(journal_dir, &part_name, &part_header, &encoded, move |p| progress(p))
+                    .await?;
+            }
+
+            parts.push(part);
+        }
+
+        progress(UploadProgress::Part { index: chunks.len(), total: chunks.len() });
+
+        let mut manifest_header = *header;
+        let encoded = encode_manifest_spool(&Manifest::new(parts), &mut manifest_header)?;
+        self.upload_or_resume(journal_dir, name, &manifest_header, &encoded, move |p| progress(p))
+            .await
+    }
+
+    /// Resumes the upload to the spool `name` if it has a journal in `journal_dir`, otherwise
+    /// starts a new one.
+    async fn upload_or_resume<F>(
+        &self,
+        journal_dir: &Path,
+        name: &str,
+        header: &SpoolHeader,
+        payload: &[u8],
+        progress: F,
+    ) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        let (spool_address, _) = spool_pda(self.pubkey(), &to_name(name));
+        if UploadJournal::path(journal_dir, &spool_address).exists() {
+            self.resume_upload(journal_dir, &spool_address, payload, progress).await
+        } else {
+            self.upload(journal_dir, name, header, payload, progress).await
+        }
+    }
+
+    /// Grants `recipients` access to an encrypted spool with a key envelope, by uploading a new
+    /// spool named `name` that references it. The client signer must be a recipient of the
+    /// spool (or of an envelope spool that references it).
+    pub async fn share_spool<F>(
+        &self,
+        journal_dir: &Path,
+        spool_address: &Pubkey,
+        name: &str,
+        recipients: &[Pubkey],
+        progress: F,
+    ) -> Result<Pubkey>
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        let (header, data) = self.read_raw_spool(spool_address).await?;
+        let (key, reference) = open_envelope(data, &header, &self.signer)?;
+
+        let envelope = Envelope::seal(&key, recipients, Some(reference.unwrap_or(*spool_address)))?;
+        let mut header = SpoolHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::try_from(header.encryption_algo)
+                .map_err(|_| anyhow!("Invalid encryption algorithm"))?,
+            SpoolFlags::Prefixed,
+        );
+        let payload = encode_envelope_spool(&envelope, &mut header)?;
+
+        self.upload(journal_dir, name, &header, &payload, progress).await
+    }
+
+    /// Decides whether the writes to a new spool go through a lookup table and how many
+    /// segments each write carries.
+    fn plan_writes(&self, spool_address: Pubkey, writer_address: Pubkey) -> (bool, usize) {
+        match self.lookup_tables {
+            LookupTablePolicy::Never => {
+                (false, segments_per_write(self.pubkey(), spool_address, writer_address, None))
+            }
+            LookupTablePolicy::Always => {
+                // Table addresses don't change the transaction size, so any key sizes the writes
+                let candidate = AddressLookupTableAccount {
+                    key: Pubkey::default(),
+                    addresses: vec![spool_address, writer_address],
+                };
+                (true, segments_per_write(self.pubkey(), spool_address, writer_address, Some(&candidate)))
+            }
+        }
+    }
+
+    /// Creates the lookup table the writes of the upload go through and records it in the
+    /// journal.
+    async fn attach_lookup_table(&self, journal_dir: &Path, journal: &mut UploadJournal) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+        let writer_address = journal.writer_address()?;
+
+        let (table, _) = self.create_lookup_table(vec![spool_address, writer_address]).await?;
+        debug!("Writing spool {} through lookup table {}", spool_address, table.key);
+
+        journal.lookup_table = Some(table.key.to_string());
+        journal.save(journal_dir)
+    }
+
+    /// Writes the payload and checks it against the spool, re-sending whatever is missing,
+    /// until the on-chain segment count and merkle root match the payload.
+    async fn write_verified(
+        &self,
+        journal_dir: &Path,
+        journal: &mut UploadJournal,
+        payload: &[u8],
+        progress: Arc<impl Fn(UploadProgress) + Send + Sync + 'static>,
+    ) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+
+        for _round in 0..MAX_UPLOAD_ROUNDS {
+            self.write_pending(journal_dir, journal, payload, progress.clone()).await?;
+
+            progress(UploadProgress::Verifying);
+            let report = self.verify_upload(journal, payload).await?;
+
+            if report.is_ok() {
+                return Ok(());
+            }
+
+            if report.is_corrupt() {
+                bail!("Spool {} doesn't match the upload, not finalizing:\n{}", spool_address, report);
+            }
+
+            debug!("Spool {} is missing {} segments, re-sending", spool_address, report.missing.len());
+            journal.mark_missing(&report.missing);
+            journal.save(journal_dir)?;
+        }
+
+        let report = self.verify_upload(journal, payload).await?;
+        if report.is_ok() {
+            return Ok(());
+        }
+
+        bail!(
+            "Spool {} still doesn't match the upload after {} rounds, not finalizing:\n{}",
+            spool_address, MAX_UPLOAD_ROUNDS, report
+        )
+    }
+
+    /// Compares the spool against the payload, polling for up to `WAIT_TIME` while it doesn't
+    /// match yet (writes still landing, or blocks not yet readable).
+    pub async fn verify_upload(
+        &self,
+        journal: &UploadJournal,
+        payload: &[u8],
+    ) -> Result<IntegrityReport> {
+        let spool_address = journal.spool_address()?;
+        let deadline = Instant::now() + Duration::from_secs(WAIT_TIME);
+
+        loop {
+            let (spool, _) = self.get_spool_account(&spool_address).await?;
+            let report = self.check_upload(journal, payload, &spool).await?;
+
+            let expired = Instant::now() >= deadline;
+            match report {
+                Some(report) if report.is_ok() || report.is_corrupt() || expired => return Ok(report),
+                None if expired => bail!(
+                    "Segments read back from spool {} never matched its merkle root",
+                    spool_address
+                ),
+                _ => sleep(Duration::from_secs(2)).await,
+            }
+        }
+    }
+
+    /// Compares `spool` against the payload once. Returns `None` if the segments read back
+    /// don't match the spool account (yet).
+    async fn check_upload(
+        &self,
+        journal: &UploadJournal,
+        payload: &[u8],
+        spool: &Spool,
+    ) -> Result<Option<IntegrityReport>> {
+        let spool_address = journal.spool_address()?;
+
+        if journal.header()?.flags & (SpoolFlags::Prefixed as u8) == 0 {
+            return Ok(Some(check_sequential(&spool_address, payload, spool)?));
+        }
+
+        match self.read_back(&spool_address, spool).await? {
+            Some(segments) => Ok(Some(check_prefixed(&spool_address, payload, spool, &segments)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the segments of a spool back from the chain, in on-chain order. Returns `None` if
+    /// they don't match the spool account (yet).
+    async fn read_back(
+        &self,
+        spool_address: &Pubkey,
+        spool: &Spool,
+    ) -> Result<Option<Vec<[u8; SEGMENT_SIZE]>>> {
+        let data = self
+            .get_spool_segments_starting_at(spool_address, spool.tail_slot)
+            .await?;
+        let segments = payload_segments(&data);
+
+        if segments.len() as u64 != spool.total_segments
+            || segment_root(spool_address, &segments)? != spool.merkle_root
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(segments))
+    }
+
+    /// Writes every chunk that isn't confirmed, in rounds, until the journal and the chain agree
+    /// that the whole payload is on-chain. Prefixed chunks are written concurrently; without
+    /// prefixes the on-chain order is the payload order, so each write waits for the previous
+    /// one to land.
+    async fn write_pending(
+        &self,
+        journal_dir: &Path,
+        journal: &mut UploadJournal,
+        payload: &[u8],
+        progress: Arc<impl Fn(UploadProgress) + Send + Sync + 'static>,
+    ) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+        let writer_address = journal.writer_address()?;
+        let table = match journal.lookup_table()? {
+            Some(table_address) => Some(self.get_lookup_table(&table_address).await?),
+            None => None,
+        };
+        let prefixed = journal.header()?.has_flag(SpoolFlags::Prefixed);
+
+        for _round in 0..MAX_UPLOAD_ROUNDS {
+            self.reconcile(journal, payload).await?;
+            journal.save(journal_dir)?;
+
+            let pending = journal.pending_chunks();
+            progress(UploadProgress::Reconciled {
+                confirmed: journal.chunks.len() - pending.len(),
+                total: journal.chunks.len(),
+            });
+
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let (spool, _) = self.get_spool_account(&spool_address).await?;
+            let expected = spool.total_segments
+                + pending.iter().map(|&i| journal.chunks[i].segments).sum::<u64>();
+
+            // Sending a chunk again while its last write can still land would write it twice
+            if let Some(last_valid_block_height) = journal.in_flight_until() {
+                progress(UploadProgress::Waiting);
+                self.wait_for_in_flight(&spool_address, expected, last_valid_block_height).await?;
+                continue;
+            }
+
+            if !prefixed {
+                self.write_in_order(journal_dir, journal, payload, table.as_ref(), progress.as_ref())
+                    .await?;
+                continue;
+            }
+
+            let semaphore = Arc::new(Semaphore::new(self.concurrency));
+            let shared = Arc::new(Mutex::new(journal.clone()));
+
+            let mut tasks: JoinSet<Result<()>> = JoinSet::new();
+            for index in journal.unsent_chunks() {
+                let client = self.clone();
+                let semaphore = semaphore.clone();
+                let shared = shared.clone();
+                let progress = progress.clone();
+                let chunk = journal.chunk(payload, index).to_vec();
+                let journal_dir = journal_dir.to_path_buf();
+                let table = table.clone();
+
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire().await?;
+                    let (sig, last_valid_block_height) = client
+                        .write_to_spool_with_expiry(spool_address, writer_address, &chunk, table.as_ref())
+                        .await?;
+
+                    let mut journal = shared.lock().map_err(|_| anyhow!("Journal lock poisoned"))?;
+                    journal.chunks[index].signature = Some(sig.to_string());
+                    journal.chunks[index].last_valid_block_height = Some(last_valid_block_height);
+
+                    let sent = journal.chunks.iter().filter(|c| c.signature.is_some()).count();
+                    if sent % JOURNAL_SAVE_EVERY == 0 {
+                        journal.save(&journal_dir)?;
+                    }
+
+                    progress(UploadProgress::ChunkSent);
+                    Ok(())
+                });
+            }
+
+            // Let every write finish, so the signatures of the ones that landed are saved even
+            // if another one failed
+            let mut first_error = None;
+            while let Some(result) = tasks.join_next().await {
+                if let Err(e) = result.map_err(anyhow::Error::from).and_then(|res| res) {
+                    first_error.get_or_insert(e);
+                }
+            }
+
+            *journal = shared
+                .lock()
+                .map_err(|_| anyhow!("Journal lock poisoned"))?
+                .clone();
+            journal.save(journal_dir)?;
+
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+
+            progress(UploadProgress::Waiting);
+            self.wait_for_segments(&spool_address, expected).await?;
+        }
+
+        bail!(
+            "Upload of spool {} is still incomplete after {} rounds, run it again to resume",
+            spool_address, MAX_UPLOAD_ROUNDS
+        )
+    }
+
+    /// Writes the unsent chunks one at a time in payload order, waiting for each write to land
+    /// before sending the next. Stops at the first write that fails or expires, leaving it to
+    /// the next round.
+    async fn write_in_order(
+        &self,
+        journal_dir: &Path,
+        journal: &mut UploadJournal,
+        payload: &[u8],
+        table: Option<&AddressLookupTableAccount>,
+        progress: &(impl Fn(UploadProgress) + Send + Sync),
+    ) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+        let writer_address = journal.writer_address()?;
+
+        for index in journal.unsent_chunks() {
+            let chunk = journal.chunk(payload, index).to_vec();
+            let (sig, last_valid_block_height) = self
+                .write_to_spool_with_expiry(spool_address, writer_address, &chunk, table)
+                .await?;
+
+            journal.chunks[index].signature = Some(sig.to_string());
+            journal.chunks[index].last_valid_block_height = Some(last_valid_block_height);
+            journal.save(journal_dir)?;
+            progress(UploadProgress::ChunkSent);
+
+            loop {
+                let status = self.rpc.get_signature_statuses(&[sig]).await?.into_iter().next().flatten();
+                match status {
+                    Some(status) if status.err.is_none() => break,
+                    None if self.rpc.get_block_height().await? <= last_valid_block_height => {
+                        sleep(Duration::from_secs(1)).await;
+                    }
+                    _ => return Ok(()),
+                }
+            }
+
+            journal.chunks[index].confirmed = true;
+        }
+
+        journal.save(journal_dir)
+    }
+
+    /// Updates which chunks are on-chain, from the recorded signatures and, if the journal
+    /// doesn't account for every on-chain segment, from the segments read back from the chain.
+    async fn reconcile(&self, journal: &mut UploadJournal, payload: &[u8]) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+
+        let unconfirmed = journal.unconfirmed_signatures();
+        let block_height = if unconfirmed.is_empty() {
+            0
+        } else {
+            self.rpc.get_block_height().await?
+        };
+
+        for batch in unconfirmed.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+            let signatures: Vec<Signature> = batch.iter().map(|(_, sig)| *sig).collect();
+            let statuses = self.rpc.get_signature_statuses(&signatures).await?;
+
+            for ((index, _), status) in batch.iter().zip(statuses) {
+                let chunk = &mut journal.chunks[*index];
+                let expired = chunk
+                    .last_valid_block_height
+                    .is_none_or(|last_valid| block_height > last_valid);
+
+                match status {
+                    Some(status) if status.err.is_none() => chunk.confirmed = true,
+                    // Not seen yet but its blockhash is still valid, the write may still land
+                    None if !expired => {}
+                    // Failed or dropped, the chunk is sent again
+                    _ => {
+                        chunk.signature = None;
+                        chunk.last_valid_block_height = None;
+                    }
+                }
+            }
+        }
+
+        let (spool, _) = self.get_spool_account(&spool_address).await?;
+        let confirmed = journal.confirmed_segments();
+
+        if confirmed == spool.total_segments {
+            return Ok(());
+        }
+
+        if confirmed > spool.total_segments {
+            bail!(
+                "Journal has {} segments on-chain but spool {} only has {}",
+                confirmed, spool_address, spool.total_segments
+            );
+        }
+
+        // The writes in flight may account for the rest once they land or expire
+        if journal.in_flight_until().is_some() {
+            return Ok(());
+        }
+
+        debug!(
+            "Journal accounts for {} of {} segments, reading spool {} back",
+            confirmed, spool.total_segments, spool_address
+        );
+
+        self.reconcile_from_chain(journal, payload, &spool).await
+    }
+
+    /// Marks the chunks whose segments are all on-chain, from the segments read back.
+    async fn reconcile_from_chain(
+        &self,
+        journal: &mut UploadJournal,
+        payload: &[u8],
+        spool: &Spool,
+    ) -> Result<()> {
+        let spool_address = journal.spool_address()?;
+        let header = journal.header()?;
+
+        // Without prefixes there is no way to tell which chunk a segment came from
+        if header.flags & (SpoolFlags::Prefixed as u8) == 0 {
+            bail!("Can't reconcile spool {} without prefixed segments", spool_address);
+        }
+
+        let Some(segments) = self.read_back(&spool_address, spool).await? else {
+            bail!(
+                "Segments read back from spool {} don't match its merkle root yet, try again later",
+                spool_address
+            );
+        };
+
+        let report = check_prefixed(&spool_address, payload, spool, &segments)?;
+        if report.is_corrupt() {
+            bail!("Spool {} holds data that isn't part of the upload:\n{}", spool_address, report);
+        }
+
+        for chunk in journal.chunks.iter_mut() {
+            chunk.confirmed = true;
+        }
+        journal.mark_missing(&report.missing);
+
+        Ok(())
+    }
+
+    /// Polls the spool until it has `expected` segments, or the block height passes
+    /// `last_valid_block_height` and no write still in flight can land.
+    async fn wait_for_in_flight(
+        &self,
+        spool_address: &Pubkey,
+        expected: u64,
+        last_valid_block_height: u64,
+    ) -> Result<()> {
+        loop {
+            let (spool, _) = self.get_spool_account(spool_address).await?;
+            if spool.total_segments >= expected
+                || self.rpc.get_block_height().await? > last_valid_block_height
+            {
+                return Ok(());
+            }
+
+            sleep(Duration::from_secs(2)).await;
+        }
+    }
+
+    /// Polls the spool until it has `expected` segments, or stops growing for `WAIT_TIME`.
+    pub(super) async fn wait_for_segments(&self, spool_address: &Pubkey, expected: u64) -> Result<()> {
+        let mut last = 0;
+        let mut last_change = Instant::now();
+
+        loop {
+            let (spool, _) = self.get_spool_account(spool_address).await?;
+            if spool.total_segments >= expected {
+                return Ok(());
+            }
+
+            if spool.total_segments != last {
+                last = spool.total_segments;
+                last_change = Instant::now();
+            } else if last_change.elapsed() >= Duration::from_secs(WAIT_TIME) {
+                return Ok(());
+            }
+
+            sleep(Duration::from_secs(2)).await;
+        }
+    }
+
+    /// Sets the header, tops up the rent and finalizes, skipping the steps already done.
+    async fn finish_upload(
+        &self,
+        journal: &UploadJournal,
+        spool_address: &Pubkey,
+        writer_address: &Pubkey,
+        progress: &(impl Fn(UploadProgress) + Send + Sync),
+    ) -> Result<()> {
+        let (spool, _) = self.get_spool_account(spool_address).await?;
+
+        if spool.header.as_slice() != journal.header.as_slice() {
+            progress(UploadProgress::SettingHeader);
+            self.set_header(*spool_address, journal.header()?).await?;
+        }
+
+        let required_rent = min_finalization_rent(spool.total_segments);
+        if spool.balance < required_rent {
+            progress(UploadProgress::Subsidizing);
+            self.subsidize_spool(*spool_address, self.ata_address(), required_rent - spool.balance)
+                .await?;
+        }
+
+        progress(UploadProgress::Finalizing);
+        self.finalize_spool(*spool_address, *writer_address).await?;
+
+        Ok(())
+    }
+}
+
+/// Most segments a write to `spool_address` paid by `payer` can carry while its transaction fits
+/// in a packet, with a compute unit limit and price set and the spool and writer accounts loaded
+/// through `table` if given. At least one.
+pub fn segments_per_write(
+    payer: Pubkey,
+    spool_address: Pubkey,
+    writer_address: Pubkey,
+    table: Option<&AddressLookupTableAccount>,
+) -> usize {
+    let tables = table.map(std::slice::from_ref).unwrap_or_default();
+
+    max_write_segments(payer, spool_address, writer_address, |write_ix| {
+        let ixs = with_compute_budget(&[write_ix], Some(u32::MAX), Some(u64::MAX));
+        compile_message(&ixs, &payer, tables, Hash::default()).ok()
+    })
+}
+
+/// Most segments a write can carry while the message `message` builds around its instruction
+/// fits in a packet. At least one.
+pub(crate) fn max_write_segments(
+    payer: Pubkey,
+    spool_address: Pubkey,
+    writer_address: Pubkey,
+    message: impl Fn(Instruction) -> Option<VersionedMessage>,
+) -> usize {
+    let fits = |segments: usize| {
+        let data = vec![0; segments * SEGMENT_SIZE];
+        let write_ix = build_write_ix(payer, spool_address, writer_address, &data);
+        message(write_ix).is_some_and(|message| fits_in_packet(&message))
+    };
+
+    (2..=PACKET_DATA_SIZE / SEGMENT_SIZE)
+        .take_while(|&segments| fits(segments))
+        .last()
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::transaction_size;
+
+    fn write_size(
+        payer: Pubkey,
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        tables: &[AddressLookupTableAccount],
+        segments: usize,
+    ) -> usize {
+        let data = vec![0; segments * SEGMENT_SIZE];
+        let write_ix = build_write_ix(payer, spool_address, writer_address, &data);
+        let ixs = with_compute_budget(&[write_ix], Some(u32::MAX), Some(u64::MAX));
+        transaction_size(&compile_message(&ixs, &payer, tables, Hash::default()).unwrap())
+    }
+
+    #[test]
+    fn test_segments_per_write() {
+        let payer = Pubkey::new_unique();
+        let spool_address = Pubkey::new_unique();
+        let writer_address = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![spool_address, writer_address],
+        };
+
+        let without_table = segments_per_write(payer, spool_address, writer_address, None);
+        let with_table = segments_per_write(payer, spool_address, writer_address, Some(&table));
+        assert_eq!(without_table, SEGMENTS_PER_TX);
+
+        // The bytes a table saves don't make room for another segment
+        assert_eq!(with_table, without_table);
+
+        let tables = [table];
+        for (segments, tables) in [(without_table, &[][..]), (with_table, &tables[..])] {
+            let size = |segments| write_size(payer, spool_address, writer_address, tables, segments);
+            assert!(size(segments) <= PACKET_DATA_SIZE);
+            assert!(size(segments + 1) > PACKET_DATA_SIZE);
+        }
+
+        // The table saves the two account keys, less the table reference
+        let saved = write_size(payer, spool_address, writer_address, &[], without_table)
+            - write_size(payer, spool_address, writer_address, &tables, without_table);
+        assert!(saved > 0 && saved < SEGMENT_SIZE);
+    }
+}
@@ -2,3 +2,50 @@ pub const MAX_RETRIES: u32 = 1000;
 pub const VERIFY_EVERY: usize = 500;
 pub const WAIT_TIME: u64 = 32; // seconds
 pub const LAMPORTS_PER_TX: u64 = 5000;
+
+/// Number of transactions `SpoolClient` keeps in flight by default.
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+/// Number of segment requests `collect_spool_segments` keeps in flight to archive nodes.
+pub const ARCHIVE_CONCURRENCY: usize = 32;
+
+/// Signatures requested per `getSignaturesForAddress` page.
+pub const SIGNATURE_PAGE_SIZE: usize = 1000;
+/// Number of transactions `read_spool_from_signatures` fetches at once.
+pub const TRANSACTION_FETCH_CONCURRENCY: usize = 16;
+
+/// Number of segments written per transaction with static account keys (7 x 128 = 896 bytes).
+/// Uploads size their writes with `segments_per_write`, which accounts for lookup tables.
+pub const SEGMENTS_PER_TX: usize = 7;
+/// Save the upload journal after this many writes.
+pub const JOURNAL_SAVE_EVERY: usize = 32;
+/// Maximum number of write rounds while finishing an upload.
+pub const MAX_UPLOAD_ROUNDS: usize = 8;
+/// Durable nonce accounts created per transaction by `create_nonce_accounts`.
+pub const NONCE_ACCOUNTS_PER_TX: usize = 4;
+
+/// Seconds an event subscription waits before reconnecting its websocket.
+pub const SUBSCRIBE_RECONNECT_DELAY: u64 = 2;
+/// Slots listed per `getBlocks` request while an event subscription backfills.
+pub const BACKFILL_BLOCKS_PER_REQUEST: usize = 100;
+
+/// Margin added to the simulated compute units, in percent.
+pub const CU_MARGIN_PERCENT: u32 = 10;
+/// Percentile of recent prioritization fees paid by default.
+pub const FEE_PERCENTILE: u8 = 50;
+/// Default cap on the compute unit price, in micro-lamports.
+pub const MAX_UNIT_PRICE: u64 = 100_000;
+
+// Default compute unit limits per instruction (`None` keeps the runtime default).
+pub const CU_INITIALIZE: u32 = 250_000;
+pub const CU_AIRDROP: u32 = 50_000;
+pub const CU_CONFIGURE_FAUCET: u32 = 50_000;
+pub const CU_SUBSIDIZE: u32 = 50_000;
+pub const CU_REGISTER: u32 = 50_000;
+pub const CU_CLAIM: u32 = 50_000;
+pub const CU_MINE: u32 = 700_000;
+pub const CU_REEL_CREATE: u32 = 50_000;
+pub const CU_REEL_DESTROY: u32 = 50_000;
+pub const CU_PACK: u32 = 100_000;
+pub const CU_UNPACK: u32 = 200_000;
+pub const CU_COMMIT: u32 = 700_000;
@@ -0,0 +1,261 @@
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP,
+        JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED,
+        JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+        JSON_RPC_SERVER_ERROR_TRANSACTION_PRECOMPILE_VERIFICATION_FAILURE,
+        JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_VERIFICATION_FAILURE,
+        JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
+    },
+    rpc_request::RpcError,
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    message::VersionedMessage,
+    transaction::TransactionError,
+};
+use thiserror::Error;
+use tokio::time::Duration;
+
+use spool_api::error::SpoolError;
+
+use crate::utils::BlockError;
+
+/// Errors of the client that callers may want to handle: transactions the spool program
+/// rejected, other failed transactions, and RPC, timeout and decoding failures.
+///
+/// Most of the client returns `anyhow::Result`, wrapping these; use `SpoolClientError::find`
+/// to get them back.
+#[derive(Error, Debug)]
+pub enum SpoolClientError {
+    #[error("Instruction {index} failed: {error}")]
+    Program { index: u8, error: SpoolError },
+    #[error("Transaction failed: {0}")]
+    Transaction(TransactionError),
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<ClientError>),
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Deserialization failed: {0}")]
+    Decode(String),
+    #[error(transparent)]
+    Block(#[from] BlockError),
+}
+
+impl SpoolClientError {
+    /// The first `SpoolClientError` in the chain of `err`.
+    pub fn find(err: &anyhow::Error) -> Option<&SpoolClientError> {
+        err.chain().find_map(|cause| cause.downcast_ref::<SpoolClientError>())
+    }
+
+    /// `err` as an `anyhow::Error` with `context`, still found by `find`.
+    pub(crate) fn rpc<C>(err: ClientError, context: C) -> anyhow::Error
+    where
+        C: std::fmt::Display + Send + Sync + 'static,
+    {
+        anyhow::Error::new(SpoolClientError::from(err)).context(context)
+    }
+
+    /// Decodes `err`, the error of a transaction with `message`. Custom errors of instructions
+    /// that call the spool program are decoded as `SpoolError`.
+    pub fn from_transaction(err: TransactionError, message: &VersionedMessage) -> Self {
+        if let TransactionError::InstructionError(index, InstructionError::Custom(code)) = err {
+            let program_id = message
+                .instructions()
+                .get(usize::from(index))
+                .and_then(|ix| message.static_account_keys().get(usize::from(ix.program_id_index)));
+
+            if program_id == Some(&spool_api::ID) {
+                if let Ok(error) = SpoolError::try_from(code) {
+                    return SpoolClientError::Program { index, error };
+                }
+            }
+        }
+        SpoolClientError::Transaction(err)
+    }
+
+    /// Decodes `err`, the error of sending a transaction with `message`, like
+    /// `from_transaction`.
+    pub fn from_send(err: ClientError, message: &VersionedMessage) -> Self {
+        match err.get_transaction_error() {
+            Some(tx_err) => SpoolClientError::from_transaction(tx_err, message),
+            None => SpoolClientError::Rpc(Box::new(err)),
+        }
+    }
+
+    /// The spool program error the transaction failed with, if any.
+    pub fn program_error(&self) -> Option<SpoolError> {
+        match self {
+            SpoolClientError::Program { error, .. } => Some(*error),
+            _ => None,
+        }
+    }
+
+    /// Whether sending the same request again may succeed. Failed instructions and malformed
+    /// data fail the same way every time.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SpoolClientError::Program { .. } | SpoolClientError::Decode(_) | SpoolClientError::Block(_) => false,
+            SpoolClientError::Transaction(err) => matches!(
+                err,
+                TransactionError::BlockhashNotFound
+                    | TransactionError::AccountInUse
+                    | TransactionError::ClusterMaintenance
+                    | TransactionError::WouldExceedMaxBlockCostLimit
+                    | TransactionError::WouldExceedMaxAccountCostLimit
+                    | TransactionError::WouldExceedMaxVoteCostLimit
+                    | TransactionError::WouldExceedAccountDataBlockLimit
+            ),
+            SpoolClientError::Rpc(err) => match err.kind() {
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => !matches!(
+                    *code,
+                    JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP
+                        | JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+                        | JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED
+                        | JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_VERIFICATION_FAILURE
+                        | JSON_RPC_SERVER_ERROR_TRANSACTION_PRECOMPILE_VERIFICATION_FAILURE
+                        | JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION
+                ),
+                // Client-side verdicts such as "AccountNotFound" don't change on retry, except
+                // confirmations that ran out of time or landed on an abandoned fork
+                ClientErrorKind::RpcError(RpcError::ForUser(message)) => {
+                    message.starts_with("unable to confirm transaction")
+                        || message.starts_with("transaction not finalized")
+                }
+                ClientErrorKind::SerdeJson(_) | ClientErrorKind::SigningError(_) => false,
+                _ => true,
+            },
+            SpoolClientError::Timeout(_) => true,
+        }
+    }
+}
+
+/// Without the transaction message the failing program is unknown, so custom instruction
+/// errors are kept as is; use `SpoolClientError::from_transaction` to decode them.
+impl From<TransactionError> for SpoolClientError {
+    fn from(err: TransactionError) -> Self {
+        SpoolClientError::Transaction(err)
+    }
+}
+
+/// Transactions that failed (in preflight or once sent) keep their transaction error.
+impl From<ClientError> for SpoolClientError {
+    fn from(err: ClientError) -> Self {
+        match err.get_transaction_error() {
+            Some(tx_err) => tx_err.into(),
+            None => SpoolClientError::Rpc(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use solana_client::rpc_request::RpcResponseErrorData;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::Instruction,
+        message::Message,
+        pubkey::Pubkey,
+    };
+
+    fn custom(code: u32) -> TransactionError {
+        TransactionError::InstructionError(2, InstructionError::Custom(code))
+    }
+
+    /// A message whose instruction 2 calls `program_id`, after the compute budget ones.
+    fn message_calling(program_id: Pubkey) -> VersionedMessage {
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+            Instruction::new_with_bytes(program_id, &[1], vec![]),
+        ];
+        VersionedMessage::Legacy(Message::new(&ixs, Some(&Pubkey::new_unique())))
+    }
+
+    #[test]
+    fn test_program_errors() {
+        let spool = message_calling(spool_api::ID);
+
+        let err = SpoolClientError::from_transaction(custom(SpoolError::InsufficientRent.into()), &spool);
+        assert!(matches!(
+            err,
+            SpoolClientError::Program { index: 2, error: SpoolError::InsufficientRent }
+        ));
+        assert!(!err.is_retryable());
+
+        let err = SpoolClientError::from_transaction(custom(0x1234), &spool);
+        assert!(matches!(err, SpoolClientError::Transaction(_)));
+        assert_eq!(err.program_error(), None);
+
+        let err = SpoolClientError::from_send(
+            ClientError::from(ClientErrorKind::TransactionError(custom(SpoolError::WriteFailed.into()))),
+            &spool,
+        );
+        assert_eq!(err.program_error(), Some(SpoolError::WriteFailed));
+    }
+
+    #[test]
+    fn test_other_program_errors() {
+        // The same code from another program isn't a spool error
+        let other = message_calling(Pubkey::new_unique());
+        let err = SpoolClientError::from_transaction(custom(SpoolError::InsufficientRent.into()), &other);
+        assert!(matches!(err, SpoolClientError::Transaction(_)));
+        assert_eq!(err.program_error(), None);
+
+        // Nor is it without the message
+        let err = SpoolClientError::from(custom(SpoolError::InsufficientRent.into()));
+        assert_eq!(err.program_error(), None);
+
+        // An index past the instructions isn't resolved either
+        let err = SpoolClientError::from_transaction(
+            TransactionError::InstructionError(7, InstructionError::Custom(SpoolError::WriteFailed.into())),
+            &message_calling(spool_api::ID),
+        );
+        assert_eq!(err.program_error(), None);
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(SpoolClientError::from(TransactionError::BlockhashNotFound).is_retryable());
+        assert!(!SpoolClientError::from(TransactionError::InsufficientFundsForFee).is_retryable());
+        assert!(SpoolClientError::Timeout(Duration::from_secs(1)).is_retryable());
+        assert!(!SpoolClientError::Decode("bad".to_string()).is_retryable());
+
+        let rpc = |code| {
+            SpoolClientError::from(ClientError::from(ClientErrorKind::RpcError(
+                RpcError::RpcResponseError {
+                    code,
+                    message: String::new(),
+                    data: RpcResponseErrorData::Empty,
+                },
+            )))
+        };
+        assert!(!rpc(JSON_RPC_SERVER_ERROR_SLOT_SKIPPED).is_retryable());
+        assert!(rpc(-32005).is_retryable());
+
+        let for_user = |message: &str| {
+            SpoolClientError::from(ClientError::from(ClientErrorKind::RpcError(
+                RpcError::ForUser(message.to_string()),
+            )))
+        };
+        assert!(!for_user(&format!("AccountNotFound: pubkey={}", Pubkey::new_unique())).is_retryable());
+        assert!(for_user("unable to confirm transaction. This can happen in situations such as transaction expiration").is_retryable());
+    }
+
+    #[test]
+    fn test_find() {
+        let err = SpoolClientError::from_transaction(
+            custom(SpoolError::SpoolTooLong.into()),
+            &message_calling(spool_api::ID),
+        );
+        let err = anyhow::Error::new(err);
+        let err = Err::<(), _>(err).context("Failed to write").unwrap_err();
+
+        let found = SpoolClientError::find(&err).unwrap();
+        assert_eq!(found.program_error(), Some(SpoolError::SpoolTooLong));
+        assert!(SpoolClientError::find(&anyhow::anyhow!("other")).is_none());
+    }
+}
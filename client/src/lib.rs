@@ -1,10 +1,16 @@
 pub mod consts;
+pub mod error;
+pub mod backend;
+pub mod client;
 pub mod program;
 pub mod utils;
 pub mod spool;
 pub mod mine;
 pub mod reel;
 
+pub use error::*;
+pub use backend::*;
+pub use client::*;
 pub use utils::*;
 pub use program::*;
 pub use spool::*;
@@ -1,33 +1,79 @@
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    account::Account,
+    clock::Clock,
+    signature::{Keypair, Signature},
     pubkey::Pubkey,
+    sysvar,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
 
-use spool_api::instruction::program::build_airdrop_ix;
 use crate::utils::*;
+use crate::{RpcBackend, SpoolClient};
+
+/// What a wallet can still claim from the faucet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FaucetAllowance {
+    /// Amount that can be claimed in the current window
+    pub remaining: u64,
+    /// Unix time at which the current window ends and the allowance resets
+    pub resets_at: Option<i64>,
+    /// Unix time before which the next claim will be rejected
+    pub next_claim_at: i64,
+}
 
 pub async fn airdrop_tokens(
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     signer: &Keypair,
     beneficiary: Pubkey,
     amount: u64,
 ) -> Result<Signature> {
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(50_000);
-    let airdrop_ix = build_airdrop_ix(signer.pubkey(), beneficiary, amount);
-
-    let signature = build_send_and_confirm_tx(
-        &[compute_budget_ix, airdrop_ix],
-        client,
-        signer.pubkey(),
-        &[signer],
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to airdrop tokens: {}", e))?;
-
-    Ok(signature)
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .airdrop_tokens(beneficiary, amount)
+        .await
+}
+
+/// Sets the faucet limits. `signer` must be the genesis spool authority.
+pub async fn configure_faucet(
+    client: &Arc<impl RpcBackend + 'static>,
+    signer: &Keypair,
+    cap: u64,
+    window_seconds: i64,
+    cooldown_seconds: i64,
+) -> Result<Signature> {
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .configure_faucet(cap, window_seconds, cooldown_seconds)
+        .await
+}
+
+/// Returns the faucet allowance of `authority`, according to the cluster clock.
+pub async fn get_faucet_allowance(
+    client: &impl RpcBackend,
+    authority: Pubkey,
+) -> Result<FaucetAllowance> {
+    let account_bytes = get_account(client, &sysvar::clock::ID).await?;
+    let account: Account = deserialize(&account_bytes)?;
+    let clock: Clock = deserialize(&account.data)?;
+    let now = clock.unix_timestamp;
+
+    let config = get_faucet_config_account(client).await?;
+
+    // A wallet that never claimed has no tracker account yet
+    let Some((claim, _)) = get_faucet_claim_account(client, &authority).await? else {
+        return Ok(FaucetAllowance {
+            remaining: config.cap,
+            resets_at: None,
+            next_claim_at: now,
+        });
+    };
+
+    let resets_at = (!claim.window_expired(&config, now))
+        .then(|| claim.window_start.saturating_add(config.window_seconds));
+
+    Ok(FaucetAllowance {
+        remaining: claim.remaining_allowance(&config, now),
+        resets_at,
+        next_claim_at: claim.next_claim_at(&config).max(now),
+    })
 }
@@ -1,19 +1,16 @@
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Keypair, Signature},
     pubkey::Pubkey,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
 
-use spool_api::instruction::reel::build_commit_ix;
 use spool_api::types::*;
-use crate::utils::*;
+use crate::{RpcBackend, SpoolClient};
 
 pub async fn commit_solution(
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     signer: &Keypair,
     miner_address: Pubkey,
     reel_address: Pubkey,
@@ -21,17 +18,7 @@ pub async fn commit_solution(
     proof: ProofPath,
     value: [u8; 32],
 ) -> Result<Signature> {
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(700_000);
-    let commit_ix = build_commit_ix(signer.pubkey(), miner_address, reel_address, index, proof, value);
-
-    let signature = build_send_and_confirm_tx(
-        &[compute_budget_ix, commit_ix],
-        client,
-        signer.pubkey(),
-        &[signer],
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to commit solution: {}", e))?;
-
-    Ok(signature)
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .commit_solution(miner_address, reel_address, index, proof, value)
+        .await
 }
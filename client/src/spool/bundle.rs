@@ -0,0 +1,297 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    system_instruction::advance_nonce_account,
+    system_program,
+    transaction::VersionedTransaction,
+};
+
+use crate::utils::{compile_message, deserialize, serialize};
+
+/// Version of the on-disk bundle format.
+pub const BUNDLE_VERSION: u8 = 1;
+
+/// When a transaction of a bundle can be submitted: each stage needs the previous one to have
+/// landed, transactions of the same stage can land in any order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStage {
+    /// Creates the spool.
+    Create,
+    /// Writes a chunk of the payload.
+    Write,
+    /// Sets the header, pays the rent and finalizes the spool.
+    Finalize,
+}
+
+/// A transaction of a bundle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BundleTransaction {
+    pub stage: BundleStage,
+    /// Durable nonce account the transaction takes its blockhash from.
+    pub nonce_account: String,
+    /// The bincode-serialized transaction, base64-encoded. Signatures are zeroed until signed.
+    pub transaction: String,
+}
+
+/// Portable file holding every transaction of a spool upload, so the spool authority can sign
+/// them on another (offline) machine and anyone can submit them later.
+///
+/// Transactions take their blockhash from a durable nonce account each, advanced as their first
+/// instruction, so they don't expire before they are submitted. The authority pays the fees and
+/// is the authority of the nonce accounts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpoolBundle {
+    pub version: u8,
+    pub spool_address: String,
+    pub writer_address: String,
+    pub name: String,
+    pub authority: String,
+    /// Segments the spool holds once every write landed.
+    pub total_segments: u64,
+    /// Root of the segment tree of the payload, checked before finalizing.
+    pub merkle_root: [u8; 32],
+    pub transactions: Vec<BundleTransaction>,
+}
+
+impl SpoolBundle {
+    pub fn new(
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        name: &str,
+        authority: Pubkey,
+        total_segments: u64,
+        merkle_root: [u8; 32],
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            spool_address: spool_address.to_string(),
+            writer_address: writer_address.to_string(),
+            name: name.to_string(),
+            authority: authority.to_string(),
+            total_segments,
+            merkle_root,
+            transactions: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path)
+            .map_err(|e| anyhow!("Failed to read bundle {}: {}", path.display(), e))?;
+        let bundle: Self = serde_json::from_slice(&data)
+            .map_err(|e| anyhow!("Failed to parse bundle {}: {}", path.display(), e))?;
+
+        if bundle.version != BUNDLE_VERSION {
+            bail!(
+                "Unsupported bundle version: found {}, expected {}",
+                bundle.version, BUNDLE_VERSION
+            );
+        }
+
+        Ok(bundle)
+    }
+
+    /// Writes the bundle to `path`, replacing the previous copy atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)
+            .map_err(|e| anyhow!("Failed to write bundle {}: {}", path.display(), e))
+    }
+
+    pub fn spool_address(&self) -> Result<Pubkey> {
+        parse_pubkey(&self.spool_address, "spool address")
+    }
+
+    pub fn writer_address(&self) -> Result<Pubkey> {
+        parse_pubkey(&self.writer_address, "writer address")
+    }
+
+    pub fn authority(&self) -> Result<Pubkey> {
+        parse_pubkey(&self.authority, "authority")
+    }
+
+    /// Adds a transaction built with `build_nonce_tx`.
+    pub fn push(
+        &mut self,
+        stage: BundleStage,
+        nonce_account: Pubkey,
+        tx: &VersionedTransaction,
+    ) -> Result<()> {
+        self.transactions.push(BundleTransaction {
+            stage,
+            nonce_account: nonce_account.to_string(),
+            transaction: base64::encode(serialize(tx)?),
+        });
+        Ok(())
+    }
+
+    /// The transactions with their stage, in bundle order.
+    pub fn transactions(&self) -> Result<Vec<(BundleStage, VersionedTransaction)>> {
+        self.transactions
+            .iter()
+            .map(|entry| {
+                let bytes = base64::decode(&entry.transaction)
+                    .map_err(|_| anyhow!("Invalid transaction encoding in bundle"))?;
+                Ok((entry.stage, deserialize(&bytes)?))
+            })
+            .collect()
+    }
+
+    /// Signs every transaction that requires `signer`, leaving the other signatures as they
+    /// are. Returns the number of transactions signed.
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<usize> {
+        let pubkey = signer.try_pubkey()?;
+        let mut signed = 0;
+
+        for entry in self.transactions.iter_mut() {
+            let bytes = base64::decode(&entry.transaction)
+                .map_err(|_| anyhow!("Invalid transaction encoding in bundle"))?;
+            let mut tx: VersionedTransaction = deserialize(&bytes)?;
+
+            let Some(position) = signer_position(&tx, &pubkey) else {
+                continue;
+            };
+
+            tx.signatures[position] = signer.try_sign_message(&tx.message.serialize())?;
+            entry.transaction = base64::encode(serialize(&tx)?);
+            signed += 1;
+        }
+
+        Ok(signed)
+    }
+
+    /// Signers some transaction still needs a signature from, each listed once.
+    pub fn missing_signers(&self) -> Result<Vec<Pubkey>> {
+        let mut missing = Vec::new();
+
+        for (_, tx) in self.transactions()? {
+            let signers = required_signers(&tx);
+            for (signer, signature) in signers.iter().zip(&tx.signatures) {
+                if *signature == Signature::default() && !missing.contains(signer) {
+                    missing.push(*signer);
+                }
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Checks every signature of every transaction against its message.
+    pub fn verify_signatures(&self) -> Result<()> {
+        for (index, (_, tx)) in self.transactions()?.iter().enumerate() {
+            if !tx.verify_with_results().into_iter().all(|ok| ok) {
+                bail!("Transaction {} of the bundle is not signed, or not validly", index);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the unsigned transaction of `ixs` paid by `authority`, taking its blockhash from
+/// `nonce_account` (holding `nonce_blockhash`) and advancing the nonce as its first instruction.
+pub fn build_nonce_tx(
+    ixs: &[Instruction],
+    authority: &Pubkey,
+    nonce_account: &Pubkey,
+    nonce_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let mut all_ixs = Vec::with_capacity(ixs.len() + 1);
+    all_ixs.push(advance_nonce_account(nonce_account, authority));
+    all_ixs.extend_from_slice(ixs);
+
+    let message = compile_message(&all_ixs, authority, &[], nonce_blockhash)?;
+    Ok(VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    })
+}
+
+/// Address of nonce account `index` of `authority`, created by `base` with a seed so the same
+/// accounts are found again without keeping their keypairs.
+pub fn nonce_account_address(base: &Pubkey, authority: &Pubkey, index: usize) -> Result<Pubkey> {
+    Pubkey::create_with_seed(base, &nonce_seed(authority, index), &system_program::id())
+        .map_err(|e| anyhow!("Failed to derive nonce account {}: {}", index, e))
+}
+
+/// Seed of nonce account `index` of `authority`: seeds are at most 32 characters, so the
+/// authority is shortened to a hash.
+pub fn nonce_seed(authority: &Pubkey, index: usize) -> String {
+    let hash = blake3::hash(authority.as_ref());
+    format!("{}-{}", &hash.to_hex()[..20], index)
+}
+
+fn required_signers(tx: &VersionedTransaction) -> &[Pubkey] {
+    let count = tx.message.header().num_required_signatures as usize;
+    &tx.message.static_account_keys()[..count]
+}
+
+fn signer_position(tx: &VersionedTransaction, pubkey: &Pubkey) -> Option<usize> {
+    required_signers(tx).iter().position(|signer| signer == pubkey)
+}
+
+fn parse_pubkey(value: &str, what: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|_| anyhow!("Invalid {} in bundle: {}", what, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn test_bundle_signing() {
+        let authority = Keypair::new();
+        let other = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+
+        let mut bundle = SpoolBundle::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            "offline",
+            authority.pubkey(),
+            1,
+            [7; 32],
+        );
+        let tx = build_nonce_tx(&[ix], &authority.pubkey(), &nonce_account, Hash::new_unique()).unwrap();
+        assert!(tx.uses_durable_nonce());
+        bundle.push(BundleStage::Write, nonce_account, &tx).unwrap();
+
+        assert_eq!(bundle.missing_signers().unwrap(), vec![authority.pubkey()]);
+        assert!(bundle.verify_signatures().is_err());
+
+        assert_eq!(bundle.sign(&other).unwrap(), 0);
+        let signer: &dyn Signer = &authority;
+        assert_eq!(bundle.sign(signer).unwrap(), 1);
+        assert!(bundle.missing_signers().unwrap().is_empty());
+        assert!(bundle.verify_signatures().is_ok());
+
+        let parsed: SpoolBundle = serde_json::from_slice(&serde_json::to_vec(&bundle).unwrap()).unwrap();
+        assert_eq!(parsed.merkle_root, [7; 32]);
+        assert_eq!(parsed.transactions().unwrap()[0].0, BundleStage::Write);
+        assert!(parsed.verify_signatures().is_ok());
+    }
+
+    #[test]
+    fn test_nonce_accounts() {
+        let base = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        assert!(nonce_seed(&authority, u32::MAX as usize).len() <= 32);
+        assert_eq!(
+            nonce_account_address(&base, &authority, 3).unwrap(),
+            nonce_account_address(&base, &authority, 3).unwrap()
+        );
+        assert_ne!(
+            nonce_account_address(&base, &authority, 3).unwrap(),
+            nonce_account_address(&base, &Pubkey::new_unique(), 3).unwrap()
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Result};
+use solana_sdk::pubkey::Pubkey;
+use spool_api::prelude::*;
+
+use super::MimeType;
+
+/// A 4-byte "magic" prefix to identify the directory format.
+pub const DIRECTORY_MAGIC: [u8; 4] = *b"SPDR";
+
+/// The version of the directory format.
+pub const DIRECTORY_VERSION: u8 = 1;
+
+/// Magic, version, 3 reserved bytes and entry count.
+const DIRECTORY_HEADER_LEN: usize = 12;
+
+/// Path length, then (after the path) spool, MIME type, file length and blake3 hash.
+const ENTRY_FIXED_LEN: usize = 2 + 32 + 1 + 8 + 32;
+
+/// A tree of files uploaded to spools of their own, listed by path relative to its root.
+///
+/// Stored as the (decoded) payload of a spool with the `Directory` flag. Identical files share a
+/// spool, so several entries can point to the same address.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Directory {
+    pub entries: Vec<DirectoryEntry>,
+}
+
+/// A file of a `Directory`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    /// Path relative to the root of the directory, with `/` separators.
+    pub path: String,
+    pub address: Pubkey,
+    pub mime_type: MimeType,
+    pub len: u64,
+    /// blake3 hash of the file contents.
+    pub hash: [u8; 32],
+}
+
+impl DirectoryEntry {
+    pub fn new(path: impl Into<String>, address: Pubkey, mime_type: MimeType, data: &[u8]) -> Self {
+        Self {
+            path: path.into(),
+            address,
+            mime_type,
+            len: data.len() as u64,
+            hash: *blake3::hash(data).as_bytes(),
+        }
+    }
+
+    /// Where to restore the file under `root`. Fails for paths that would leave it.
+    pub fn path_under(&self, root: &Path) -> Result<PathBuf> {
+        check_path(&self.path)?;
+        Ok(root.join(&self.path))
+    }
+
+    /// Checks the contents of the file against its length and hash.
+    pub fn check(&self, data: &[u8]) -> Result<()> {
+        if data.len() as u64 != self.len || *blake3::hash(data).as_bytes() != self.hash {
+            bail!("{} (spool {}) doesn't match its hash in the directory", self.path, self.address);
+        }
+        Ok(())
+    }
+}
+
+impl Directory {
+    pub fn new(mut entries: Vec<DirectoryEntry>) -> Result<Self> {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for entry in &entries {
+            check_path(&entry.path)?;
+        }
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].path == pair[1].path) {
+            bail!("Duplicate path in directory: {}", pair[0].path);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// The spools of the directory, each listed once.
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        let mut addresses: Vec<Pubkey> = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            if !addresses.contains(&entry.address) {
+                addresses.push(entry.address);
+            }
+        }
+        addresses
+    }
+
+    /// Total size of the files, in bytes.
+    pub fn total_len(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.len).sum()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        output.extend_from_slice(&DIRECTORY_MAGIC);
+        output.push(DIRECTORY_VERSION);
+        output.extend_from_slice(&[0; 3]);
+        output.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+        for entry in &self.entries {
+            output.extend_from_slice(&(entry.path.len() as u16).to_le_bytes());
+            output.extend_from_slice(entry.path.as_bytes());
+            output.extend_from_slice(&entry.address.to_bytes());
+            output.push(entry.mime_type.into());
+            output.extend_from_slice(&entry.len.to_le_bytes());
+            output.extend_from_slice(&entry.hash);
+        }
+
+        output
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < DIRECTORY_HEADER_LEN {
+            bail!("Invalid directory: data too short");
+        }
+        if data[0..4] != DIRECTORY_MAGIC {
+            bail!("Invalid directory: invalid magic number");
+        }
+        if data[4] != DIRECTORY_VERSION {
+            bail!("Invalid directory: unsupported version {}", data[4]);
+        }
+
+        let count = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+        let mut entries = Vec::with_capacity(count.min(data.len() / ENTRY_FIXED_LEN));
+        let mut rest = &data[DIRECTORY_HEADER_LEN..];
+
+        for _ in 0..count {
+            if rest.len() < 2 {
+                bail!("Invalid directory: data too short");
+            }
+            let path_len = u16::from_le_bytes(rest[0..2].try_into().unwrap()) as usize;
+            let end = path_len + ENTRY_FIXED_LEN;
+            if rest.len() < end {
+                bail!("Invalid directory: data too short");
+            }
+
+            let path = std::str::from_utf8(&rest[2..2 + path_len])
+                .map_err(|_| anyhow::anyhow!("Invalid directory: path is not UTF-8"))?;
+            let fixed = &rest[2 + path_len..end];
+
+            entries.push(DirectoryEntry {
+                path: path.to_string(),
+                address: Pubkey::new_from_array(fixed[0..32].try_into().unwrap()),
+                mime_type: MimeType::try_from(fixed[32]).unwrap_or(MimeType::Unknown),
+                len: u64::from_le_bytes(fixed[33..41].try_into().unwrap()),
+                hash: fixed[41..73].try_into().unwrap(),
+            });
+            rest = &rest[end..];
+        }
+
+        Self::new(entries)
+    }
+}
+
+/// Name of the spool of a file with contents hash `hash` in the directory uploaded as `name`:
+/// identical files share a spool, and uploading the same name again finds the same spools.
+pub fn directory_file_name(name: &str, hash: &[u8; 32]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(name.as_bytes());
+    hasher.update(hash);
+    hasher.finalize().to_hex()[..NAME_LEN].to_string()
+}
+
+/// Paths must be relative, `/`-separated and stay under the root of the directory.
+fn check_path(path: &str) -> Result<()> {
+    let valid = !path.is_empty()
+        && path.len() <= u16::MAX as usize
+        && !path.contains(['\\', '\0'])
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        && path.split('/').all(|part| !part.is_empty());
+
+    if !valid {
+        bail!("Invalid path in directory: {:?}", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_roundtrip() {
+        let shared = Pubkey::new_unique();
+        let directory = Directory::new(vec![
+            DirectoryEntry::new("index.html", Pubkey::new_unique(), MimeType::TextHtml, b"<html>"),
+            DirectoryEntry::new("assets/a.txt", shared, MimeType::TextPlain, b"same"),
+            DirectoryEntry::new("assets/b.txt", shared, MimeType::TextPlain, b"same"),
+        ])
+        .unwrap();
+
+        assert_eq!(directory.entries[0].path, "assets/a.txt");
+        assert_eq!(directory.addresses().len(), 2);
+        assert_eq!(directory.total_len(), 14);
+
+        let parsed = Directory::parse(&directory.to_bytes()).unwrap();
+        assert_eq!(parsed, directory);
+        assert!(parsed.entries[2].check(b"<html>").is_ok());
+        assert!(parsed.entries[2].check(b"<html!").is_err());
+
+        let bytes = directory.to_bytes();
+        assert!(Directory::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_paths() {
+        for path in ["", "/etc/passwd", "../up", "a/../../b", "a//b", "a\\b", "./a"] {
+            let entry = DirectoryEntry::new(path, Pubkey::new_unique(), MimeType::Unknown, b"");
+            assert!(Directory::new(vec![entry.clone()]).is_err(), "{path}");
+            assert!(entry.path_under(Path::new("out")).is_err(), "{path}");
+        }
+
+        let entry = DirectoryEntry::new("a/b.txt", Pubkey::new_unique(), MimeType::Unknown, b"");
+        assert_eq!(entry.path_under(Path::new("out")).unwrap(), Path::new("out/a/b.txt"));
+        assert!(Directory::new(vec![entry.clone(), entry]).is_err());
+    }
+}
@@ -1,123 +1,243 @@
 use anyhow::{Result, anyhow};
 use log::debug;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 use crate::utils::*;
-use super::{SpoolHeader, SpoolFlags, CompressionAlgo};
+use super::{
+    SpoolHeader, SpoolFlags, CompressionAlgo, EncryptionAlgo, EncryptionError, Envelope, Manifest,
+    MimeType, SpoolKey, SegmentWriter, SpoolDecoder, UnprefixReader, encrypt, ensure_nonce, seal_for_header,
+    prefixed_segment_count, spool_source,
+};
 use spool_api::prelude::*;
-use std::collections::HashSet;
+use std::io::{Read, Write};
 
 /// Encodes data into a spool format, applying compression if specified in the header.
 pub fn encode_spool(data: &[u8], header: &mut SpoolHeader) -> Result<Vec<u8>> {
+    encode_spool_with_key(data, header, None)
+}
+
+/// Encodes data into a spool format, applying compression and encryption if specified in the
+/// header. `key` is required when the header sets an encryption algorithm.
+pub fn encode_spool_with_key(data: &[u8], header: &mut SpoolHeader, key: Option<&SpoolKey>) -> Result<Vec<u8>> {
+    let processed = compress_and_encrypt(data, header, key)?;
+    finish_encoding(processed, header)
+}
+
+/// Encodes data into a spool format that only `recipients` can decrypt, with their wallet
+/// keypairs. The header must set an encryption algorithm; the content key is wrapped for each
+/// recipient in a key envelope at the start of the data (see `Envelope`). Include `sender` in
+/// `recipients` to be able to add recipients later.
+pub fn encode_spool_for_recipients(
+    data: &[u8],
+    header: &mut SpoolHeader,
+    sender: &Keypair,
+    recipients: &[Pubkey],
+) -> Result<Vec<u8>> {
+    if !header.is_encrypted() {
+        return Err(anyhow!("Spools with recipients must be encrypted"));
+    }
+
+    header.flags |= SpoolFlags::Envelope as u8;
+    ensure_nonce(header)?;
+
+    let (key, envelope) = seal_for_header(header, sender, recipients)?;
+    let mut processed = envelope.to_bytes();
+    processed.extend(compress_and_encrypt(data, header, Some(&key))?);
+
+    finish_encoding(processed, header)
+}
+
+/// Encodes a key envelope that grants access to another spool (`envelope.reference`) into a
+/// spool of its own.
+pub fn encode_envelope_spool(envelope: &Envelope, header: &mut SpoolHeader) -> Result<Vec<u8>> {
+    if envelope.reference.is_none() {
+        return Err(anyhow!("Envelope spools must reference the spool they grant access to"));
+    }
+
+    header.flags |= SpoolFlags::Envelope as u8;
+    finish_encoding(envelope.to_bytes(), header)
+}
+
+/// Encodes the manifest of a payload split across part spools into a spool of its own. `header`
+/// is the header the payload was encoded with; its flags and length are set for the manifest.
+pub fn encode_manifest_spool(manifest: &Manifest, header: &mut SpoolHeader) -> Result<Vec<u8>> {
+    if manifest.is_empty() {
+        return Err(anyhow!("Manifests must list at least one part"));
+    }
+
+    header.flags |= SpoolFlags::Manifest as u8 | SpoolFlags::Prefixed as u8;
+    finish_encoding(manifest.to_bytes(), header)
+}
+
+/// Codecs tried by `CompressionAlgo::Auto`.
+const AUTO_CANDIDATES: [CompressionAlgo; 4] = [
+    CompressionAlgo::Zstd,
+    CompressionAlgo::Brotli,
+    CompressionAlgo::Gzip,
+    CompressionAlgo::Lz4,
+];
 
+fn compress_and_encrypt(data: &[u8], header: &mut SpoolHeader, key: Option<&SpoolKey>) -> Result<Vec<u8>> {
     let compression_algo = CompressionAlgo::try_from(header.compression)
         .map_err(|_| anyhow!("Invalid compression algorithm"))?;
 
     let processed = match compression_algo {
-        CompressionAlgo::None => Ok(data.to_vec()),
-        CompressionAlgo::Gzip => compress(data),
-    }?;
-
-    // Add encryption, etc...
-    header.data_len = processed.len() as u64;
+        CompressionAlgo::Auto => {
+            let (algo, processed) = compress_auto(data, header)?;
+            header.compression = algo.into();
+            processed
+        }
+        algo => compress_with(data, algo, header.compression_level)?,
+    };
 
-    let output = if header.flags & (SpoolFlags::Prefixed as u8) != 0 {
-        prefix_segments(&processed)
+    let processed = if header.is_encrypted() {
+        let key = key.ok_or_else(|| missing_key(header))?;
+        encrypt(&processed, header, key)?
     } else {
         processed
     };
 
-    Ok(output)
+    Ok(processed)
+}
+
+/// Skips compression for already-compressed MIME types; otherwise picks the codec with the
+/// smallest output, or none if no codec makes the data smaller.
+fn compress_auto(data: &[u8], header: &SpoolHeader) -> Result<(CompressionAlgo, Vec<u8>)> {
+    let mime_type = MimeType::try_from(header.mime_type).unwrap_or(MimeType::Unknown);
+    if mime_type.is_compressed() {
+        return Ok((CompressionAlgo::None, data.to_vec()));
+    }
+
+    let mut best = (CompressionAlgo::None, data.to_vec());
+    for algo in AUTO_CANDIDATES {
+        let compressed = compress_with(data, algo, header.compression_level)?;
+        debug!("{:?} compresses {} bytes to {}", algo, data.len(), compressed.len());
+
+        if compressed.len() < best.1.len() {
+            best = (algo, compressed);
+        }
+    }
+
+    Ok(best)
+}
+
+fn finish_encoding(processed: Vec<u8>, header: &mut SpoolHeader) -> Result<Vec<u8>> {
+    header.data_len = processed.len() as u64;
+
+    if header.has_flag(SpoolFlags::ErasureCoded) {
+        let segments = prefixed_segment_count(header.data_len, header.erasure()) as usize;
+        let mut writer = SegmentWriter::for_header(Vec::with_capacity(segments * SEGMENT_SIZE), header)?;
+        writer.write_all(&processed)?;
+        Ok(writer.finish()?.0)
+    } else if header.flags & (SpoolFlags::Prefixed as u8) != 0 {
+        Ok(prefix_segments(&processed))
+    } else {
+        Ok(processed)
+    }
 }
 
 /// Decodes a spool format into raw data, decompressing if necessary based on the header.
 pub fn decode_spool(data: Vec<u8>, header: &SpoolHeader) -> Result<Vec<u8>> {
-    let processed = if header.flags & (SpoolFlags::Prefixed as u8) != 0 {
-        unprefix_segments(data, header.data_len as usize)?
-    } else {
-        data
-    };
+    decode_spool_with_key(data, header, None)
+}
 
-    // Add decryption, etc...
+/// Decodes a spool format into raw data, decrypting and decompressing if necessary based on the
+/// header. Fails with `EncryptionError` if the spool is encrypted and `key` is missing or wrong.
+pub fn decode_spool_with_key(data: Vec<u8>, header: &SpoolHeader, key: Option<&SpoolKey>) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    SpoolDecoder::with_key(data.as_slice(), header, key)?.read_to_end(&mut output)?;
+    Ok(output)
+}
 
-    let compression_algo = CompressionAlgo::try_from(header.compression)
-        .map_err(|_| anyhow!("Invalid compression algorithm"))?;
+/// Decodes a spool with a key envelope, unwrapping the content key with the keypair of one of
+/// its recipients.
+pub fn decode_spool_with_keypair(data: Vec<u8>, header: &SpoolHeader, keypair: &Keypair) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    SpoolDecoder::with_keypair(data.as_slice(), header, keypair)?.read_to_end(&mut output)?;
+    Ok(output)
+}
 
-    let decompressed = match compression_algo {
-        CompressionAlgo::None => Ok(processed),
-        CompressionAlgo::Gzip => decompress(&processed),
-    }?;
+/// Unwraps the content key from the key envelope of a spool with the keypair of one of its
+/// recipients. Also returns the spool the key is for, if the envelope references another one.
+pub fn open_envelope(data: Vec<u8>, header: &SpoolHeader, keypair: &Keypair) -> Result<(SpoolKey, Option<Pubkey>)> {
+    let (envelope, _) = spool_source(data.as_slice(), header)?;
+    let envelope = envelope.ok_or(EncryptionError::NoEnvelope)?;
+
+    Ok((envelope.open(keypair)?, envelope.reference))
+}
 
-    Ok(decompressed)
+pub(crate) fn missing_key(header: &SpoolHeader) -> EncryptionError {
+    match EncryptionAlgo::try_from(header.encryption_algo) {
+        Ok(algo) => EncryptionError::MissingKey(algo),
+        Err(_) => EncryptionError::InvalidAlgo(header.encryption_algo),
+    }
 }
 
 /// Splits data into segments of fixed size, prefixing each segment with its index.
 pub fn prefix_segments(data: &[u8]) -> Vec<u8> {
-    let chunks : Vec<_> = data
-        .chunks(SEGMENT_SIZE - 8)
-        .map(|c| c.to_vec())
-        .collect();
-
-    let mut output = Vec::with_capacity(data.len() + chunks.len() * 8);
-
-    for (i, chunk) in chunks.iter().enumerate() {
-        let seg_num = (i as u64).to_be_bytes();
-        output.extend_from_slice(&seg_num);
-        output.extend_from_slice(chunk);
-    }
+    let segments = data.len().div_ceil(SEGMENT_SIZE - 8);
+    let mut writer = SegmentWriter::new(Vec::with_capacity(data.len() + segments * 8), true);
 
-    output
+    writer.write_all(data).expect("writing to a Vec can't fail");
+    writer.finish().expect("writing to a Vec can't fail").0
 }
 
 /// Unprefixes segments from a prefixed data vector, ensuring segments are consecutive and starting
-/// from 0.
+/// from 0, and truncates the result to `data_length`. See `UnprefixReader`.
 pub fn unprefix_segments(data: Vec<u8>, data_length: usize) -> Result<Vec<u8>> {
-    let mut segments: Vec<(u64, Vec<u8>)> = Vec::new();
-    let mut seen = HashSet::new();
+    let mut output = Vec::with_capacity(data_length);
+    UnprefixReader::new(data.as_slice(), data_length as u64).read_to_end(&mut output)?;
+    Ok(output)
+}
 
-    for chunk in data.chunks(SEGMENT_SIZE) {
-        if chunk.len() < 8 {
-            return Err(anyhow!("Invalid segment size: too small"));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let seg_num_bytes: [u8; 8] = chunk[0..8].try_into().map_err(|_| anyhow!("Failed to read segment number"))?;
-        let seg_num = u64::from_be_bytes(seg_num_bytes);
+    fn header(mime_type: MimeType, compression: CompressionAlgo) -> SpoolHeader {
+        SpoolHeader::new(mime_type, compression, EncryptionAlgo::None, SpoolFlags::Prefixed)
+    }
+
+    #[test]
+    fn test_codecs_roundtrip() {
+        let data = b"the same few words, over and over. ".repeat(200);
 
-        let seg_data = chunk[8..].to_vec();
+        for algo in [CompressionAlgo::None, CompressionAlgo::Gzip, CompressionAlgo::Zstd, CompressionAlgo::Brotli, CompressionAlgo::Lz4] {
+            let mut header = header(MimeType::TextPlain, algo);
+            header.compression_level = 3;
 
-        if seen.insert(seg_num) {
-            segments.push((seg_num, seg_data));
+            let encoded = encode_spool(&data, &mut header).unwrap();
+            assert_eq!(header.compression, algo as u8);
+            assert_eq!(decode_spool(encoded, &header).unwrap(), data);
         }
-        // If duplicate, skip assuming they are identical; no check for differences here
     }
 
-    // sort by segment number
-    segments.sort_by_key(|(num, _)| *num);
+    #[test]
+    fn test_auto_picks_smallest() {
+        let data = b"the same few words, over and over. ".repeat(200);
+        let mut header = header(MimeType::TextPlain, CompressionAlgo::Auto);
 
-    // Check for consecutive segments starting from 0 with no gaps
-    if !segments.is_empty() {
-        if segments[0].0 != 0 {
-            return Err(anyhow!("Segments do not start from 0"));
-        }
-        for i in 0..segments.len() - 1 {
-            if segments[i + 1].0 != segments[i].0 + 1 {
-                debug!("Segment {} is not consecutive with segment {}", segments[i].0, segments[i + 1].0);
-                return Err(anyhow!("Non-consecutive segments detected"));
-            }
+        let encoded = encode_spool(&data, &mut header).unwrap();
+        let algo = CompressionAlgo::try_from(header.compression).unwrap();
+        assert_ne!(algo, CompressionAlgo::Auto);
+        assert_ne!(algo, CompressionAlgo::None);
+
+        for candidate in AUTO_CANDIDATES {
+            assert!(header.data_len as usize <= compress_with(&data, candidate, 0).unwrap().len());
         }
+        assert_eq!(decode_spool(encoded, &header).unwrap(), data);
     }
 
-    // merge segments without prefix
-    let mut output = Vec::with_capacity(data_length);
-    for (_, seg_data) in segments {
-        output.extend(seg_data);
-    }
+    #[test]
+    fn test_auto_skips_compressed_and_random_data() {
+        let data: Vec<u8> = (0..4096).map(|_| rand::random()).collect();
 
-    if output.len() > data_length {
-        // TODO: this seems wrong... we should probably find a different solution, the issue is
-        // outside the decode_spool function, where we merged the segments into single vec<u8>,
-        // perhaps pass the segments array instead of merging and then truncating.
+        let mut header = header(MimeType::ImagePng, CompressionAlgo::Auto);
+        encode_spool(b"not really a png, but compressible compressible compressible", &mut header).unwrap();
+        assert_eq!(header.compression, CompressionAlgo::None as u8);
 
-        output.truncate(data_length);
+        let mut header = self::header(MimeType::Unknown, CompressionAlgo::Auto);
+        let encoded = encode_spool(&data, &mut header).unwrap();
+        assert_eq!(header.compression, CompressionAlgo::None as u8);
+        assert_eq!(decode_spool(encoded, &header).unwrap(), data);
     }
-
-    Ok(output)
 }
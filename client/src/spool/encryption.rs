@@ -0,0 +1,306 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm,
+};
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use chacha20poly1305::XChaCha20Poly1305;
+use rand::RngCore;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+use super::{EncryptionAlgo, SpoolHeader};
+
+/// Length of a payload key, in bytes.
+pub const KEY_LEN: usize = 32;
+
+/// Length of the Argon2 salt stored in the header, in bytes.
+pub const SALT_LEN: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Spool is encrypted with {0:?}, a key is required")]
+    MissingKey(EncryptionAlgo),
+    #[error("Invalid encryption algorithm: {0}")]
+    InvalidAlgo(u8),
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("Encryption failed")]
+    EncryptionFailed,
+    #[error("Decryption failed: wrong key or corrupted data")]
+    DecryptionFailed,
+    #[error("Spool has no key envelope")]
+    NoEnvelope,
+    #[error("Invalid key envelope: {0}")]
+    InvalidEnvelope(&'static str),
+    #[error("{0} is not a recipient of the spool")]
+    NotARecipient(Pubkey),
+    #[error("{0} can't receive encrypted spools")]
+    InvalidRecipient(Pubkey),
+    #[error("Key envelope grants access to spool {0}, read that spool instead")]
+    Referenced(Pubkey),
+}
+
+/// A 256-bit key for encrypting spool payloads.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SpoolKey([u8; KEY_LEN]);
+
+impl SpoolKey {
+    pub fn new(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Generates a random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Derives a key from `passphrase` and `salt` with Argon2id (default parameters).
+    pub fn from_passphrase(passphrase: &str, salt: &[u8; SALT_LEN]) -> Result<Self, EncryptionError> {
+        let mut bytes = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut bytes)
+            .map_err(|e| EncryptionError::KeyDerivation(e.to_string()))?;
+        Ok(Self(bytes))
+    }
+
+    /// Reads a key file holding either 32 raw bytes or 64 hex characters.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data = fs::read(path)
+            .map_err(|e| anyhow!("Failed to read key file {}: {}", path.display(), e))?;
+
+        if let Ok(bytes) = <[u8; KEY_LEN]>::try_from(data.as_slice()) {
+            return Ok(Self(bytes));
+        }
+
+        std::str::from_utf8(&data)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid key file {}: expected {} raw bytes or {} hex characters",
+                    path.display(),
+                    KEY_LEN,
+                    KEY_LEN * 2
+                )
+            })
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+
+    /// Hex encoding of the key, as accepted by `from_file`.
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+impl FromStr for SpoolKey {
+    type Err = anyhow::Error;
+
+    /// Parses a hex-encoded key.
+    fn from_str(s: &str) -> Result<Self> {
+        if s.len() != KEY_LEN * 2 || !s.is_ascii() {
+            return Err(anyhow!("Expected {} hex characters", KEY_LEN * 2));
+        }
+
+        let mut bytes = [0u8; KEY_LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+                .map_err(|_| anyhow!("Invalid hex character in key"))?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Debug for SpoolKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SpoolKey(..)")
+    }
+}
+
+impl FromStr for EncryptionAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(EncryptionAlgo::None),
+            "aes-256-gcm" | "aes256gcm" | "aes" => Ok(EncryptionAlgo::Aes256Gcm),
+            "xchacha20-poly1305" | "xchacha20poly1305" | "xchacha" => {
+                Ok(EncryptionAlgo::XChaCha20Poly1305)
+            }
+            _ => Err(anyhow!(
+                "Unknown encryption algorithm: {} (expected aes-256-gcm or xchacha20-poly1305)",
+                s
+            )),
+        }
+    }
+}
+
+/// Generates a random salt for `SpoolKey::from_passphrase`.
+pub fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Encrypts `data` with the algorithm set in `header`. A random nonce is generated and stored in
+/// the header, unless it already has one (e.g. when re-encoding a payload to resume its upload).
+pub fn encrypt(data: &[u8], header: &mut SpoolHeader, key: &SpoolKey) -> Result<Vec<u8>, EncryptionError> {
+    let algo = ensure_nonce(header)?;
+
+    let nonce = nonce(header);
+    let output = match algo {
+        EncryptionAlgo::None => Ok(data.to_vec()),
+        EncryptionAlgo::Aes256Gcm => Aes256Gcm::new(key.as_bytes().into())
+            .encrypt(nonce[..12].into(), data),
+        EncryptionAlgo::XChaCha20Poly1305 => XChaCha20Poly1305::new(key.as_bytes().into())
+            .encrypt(nonce[..].into(), data),
+    };
+
+    output.map_err(|_| EncryptionError::EncryptionFailed)
+}
+
+/// Decrypts `data` with the algorithm and nonce set in `header`.
+pub fn decrypt(data: &[u8], header: &SpoolHeader, key: &SpoolKey) -> Result<Vec<u8>, EncryptionError> {
+    let algo = encryption_algo(header)?;
+
+    let nonce = nonce(header);
+    let output = match algo {
+        EncryptionAlgo::None => Ok(data.to_vec()),
+        EncryptionAlgo::Aes256Gcm => Aes256Gcm::new(key.as_bytes().into())
+            .decrypt(nonce[..12].into(), data),
+        EncryptionAlgo::XChaCha20Poly1305 => XChaCha20Poly1305::new(key.as_bytes().into())
+            .decrypt(nonce[..].into(), data),
+    };
+
+    output.map_err(|_| EncryptionError::DecryptionFailed)
+}
+
+/// Generates a random nonce for the algorithm of `header`, unless it already has one.
+pub(crate) fn ensure_nonce(header: &mut SpoolHeader) -> Result<EncryptionAlgo, EncryptionError> {
+    let algo = encryption_algo(header)?;
+
+    if header.iv == [0; 12] && header.iv_ext == [0; 12] {
+        rand::thread_rng().fill_bytes(&mut header.iv);
+        if algo == EncryptionAlgo::XChaCha20Poly1305 {
+            rand::thread_rng().fill_bytes(&mut header.iv_ext);
+        }
+    }
+
+    Ok(algo)
+}
+
+fn encryption_algo(header: &SpoolHeader) -> Result<EncryptionAlgo, EncryptionError> {
+    EncryptionAlgo::try_from(header.encryption_algo)
+        .map_err(|_| EncryptionError::InvalidAlgo(header.encryption_algo))
+}
+
+/// The 24-byte nonce of `header`; 12-byte nonce algorithms only use the first half.
+fn nonce(header: &SpoolHeader) -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    nonce[..12].copy_from_slice(&header.iv);
+    nonce[12..].copy_from_slice(&header.iv_ext);
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_spool, decode_spool_with_key, encode_spool_with_key, CompressionAlgo, MimeType, SpoolFlags};
+
+    fn header(algo: EncryptionAlgo) -> SpoolHeader {
+        SpoolHeader::new(MimeType::TextPlain, CompressionAlgo::None, algo, SpoolFlags::None)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let key = SpoolKey::generate();
+        let data = b"hello, spool".repeat(100);
+
+        for algo in [EncryptionAlgo::Aes256Gcm, EncryptionAlgo::XChaCha20Poly1305] {
+            let mut header = header(algo);
+            let encrypted = encrypt(&data, &mut header, &key).unwrap();
+
+            assert_ne!(header.iv, [0; 12]);
+            assert_eq!(header.iv_ext == [0; 12], algo == EncryptionAlgo::Aes256Gcm);
+            assert_eq!(encrypted.len(), data.len() + 16);
+            assert_eq!(decrypt(&encrypted, &header, &key).unwrap(), data);
+
+            // Re-encrypting with the stored nonce gives the same ciphertext
+            assert_eq!(encrypt(&data, &mut header, &key).unwrap(), encrypted);
+        }
+    }
+
+    #[test]
+    fn test_wrong_key_or_tampering_fails() {
+        let key = SpoolKey::generate();
+        let mut header = header(EncryptionAlgo::XChaCha20Poly1305);
+        let mut encrypted = encrypt(b"secret", &mut header, &key).unwrap();
+
+        assert!(matches!(
+            decrypt(&encrypted, &header, &SpoolKey::generate()),
+            Err(EncryptionError::DecryptionFailed)
+        ));
+
+        encrypted[0] ^= 1;
+        assert!(matches!(
+            decrypt(&encrypted, &header, &key),
+            Err(EncryptionError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_encode_decode_with_key() {
+        let key = SpoolKey::generate();
+        let data = b"compressed, encrypted and prefixed".repeat(50);
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::Aes256Gcm,
+            SpoolFlags::Prefixed,
+        );
+
+        assert!(encode_spool_with_key(&data, &mut header.clone(), None).is_err());
+
+        let encoded = encode_spool_with_key(&data, &mut header, Some(&key)).unwrap();
+        let decoded = decode_spool_with_key(encoded.clone(), &header, Some(&key)).unwrap();
+        assert_eq!(decoded, data);
+
+        let err = decode_spool(encoded.clone(), &header).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<EncryptionError>(),
+            Some(EncryptionError::MissingKey(EncryptionAlgo::Aes256Gcm))
+        ));
+
+        let err = decode_spool_with_key(encoded, &header, Some(&SpoolKey::generate())).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<EncryptionError>(),
+            Some(EncryptionError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_passphrase_key() {
+        let salt = new_salt();
+        let a = SpoolKey::from_passphrase("correct horse", &salt).unwrap();
+        let b = SpoolKey::from_passphrase("correct horse", &salt).unwrap();
+        let c = SpoolKey::from_passphrase("correct horse", &new_salt()).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_hex_key() {
+        let key = SpoolKey::generate();
+        assert_eq!(key.to_hex().parse::<SpoolKey>().unwrap(), key);
+        assert!("abcd".parse::<SpoolKey>().is_err());
+        assert!("zz".repeat(KEY_LEN).parse::<SpoolKey>().is_err());
+    }
+}
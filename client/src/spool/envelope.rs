@@ -0,0 +1,425 @@
+use std::io::{self, Read};
+
+use anyhow::Result;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305,
+};
+use curve25519_dalek::{edwards::CompressedEdwardsY, montgomery::MontgomeryPoint};
+use rand::RngCore;
+use sha2::Sha512;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use super::{EncryptionError, SpoolHeader, SpoolKey, KEY_LEN};
+
+/// A 4-byte "magic" prefix to identify the envelope format.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"SPEV";
+
+/// The version of the envelope format.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Maximum number of recipients of a single envelope.
+pub const MAX_RECIPIENTS: usize = u8::MAX as usize;
+
+/// Magic, version, recipient count, 2 reserved bytes and the referenced spool.
+const ENVELOPE_HEADER_LEN: usize = 40;
+
+/// Recipient, ephemeral X25519 key and wrapped content key (with its tag).
+const STANZA_LEN: usize = 32 + 32 + KEY_LEN + 16;
+
+const WRAPPING_KEY_CONTEXT: &str = "spoolnet envelope v1 wrapping key";
+const CONTENT_KEY_CONTEXT: &str = "spoolnet envelope v1 content key";
+const EPHEMERAL_KEY_CONTEXT: &str = "spoolnet envelope v1 ephemeral key";
+
+/// The content key of an encrypted spool, wrapped for each recipient's wallet.
+///
+/// Wallet keys are ed25519; they are converted to X25519 to agree on a wrapping key with a fresh
+/// ephemeral key per recipient, so only the holder of a recipient keypair can unwrap the content
+/// key. An envelope with a `reference` is stored in a spool of its own and grants access to the
+/// referenced spool, which is how recipients are added after the fact.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub reference: Option<Pubkey>,
+    pub stanzas: Vec<Stanza>,
+}
+
+/// The content key wrapped for a single recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stanza {
+    pub recipient: Pubkey,
+    pub ephemeral: [u8; 32],
+    pub wrapped_key: [u8; KEY_LEN + 16],
+}
+
+impl Envelope {
+    /// Wraps `content_key` for each of `recipients`, with random ephemeral keys.
+    pub fn seal(
+        content_key: &SpoolKey,
+        recipients: &[Pubkey],
+        reference: Option<Pubkey>,
+    ) -> Result<Self, EncryptionError> {
+        Self::seal_with(content_key, recipients, reference, |_| {
+            let mut secret = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut secret);
+            secret
+        })
+    }
+
+    /// Wraps `content_key` for each of `recipients`, taking the ephemeral secret for each
+    /// recipient from `ephemeral_secret`.
+    fn seal_with(
+        content_key: &SpoolKey,
+        recipients: &[Pubkey],
+        reference: Option<Pubkey>,
+        mut ephemeral_secret: impl FnMut(&Pubkey) -> [u8; 32],
+    ) -> Result<Self, EncryptionError> {
+        let mut unique: Vec<Pubkey> = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            if !unique.contains(recipient) {
+                unique.push(*recipient);
+            }
+        }
+
+        if unique.is_empty() || unique.len() > MAX_RECIPIENTS {
+            return Err(EncryptionError::InvalidEnvelope("expected 1 to 255 recipients"));
+        }
+
+        let stanzas = unique
+            .iter()
+            .map(|recipient| wrap(content_key, recipient, ephemeral_secret(recipient)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { reference, stanzas })
+    }
+
+    pub fn recipients(&self) -> impl Iterator<Item = &Pubkey> {
+        self.stanzas.iter().map(|stanza| &stanza.recipient)
+    }
+
+    /// Unwraps the content key with the keypair of one of the recipients.
+    pub fn open(&self, keypair: &Keypair) -> Result<SpoolKey, EncryptionError> {
+        let pubkey = keypair.pubkey();
+        let stanza = self
+            .stanzas
+            .iter()
+            .find(|stanza| stanza.recipient == pubkey)
+            .ok_or(EncryptionError::NotARecipient(pubkey))?;
+
+        let secret = x25519_secret(keypair);
+        let recipient = MontgomeryPoint::mul_base_clamped(secret);
+        let ephemeral = MontgomeryPoint(stanza.ephemeral);
+        let shared = ephemeral.mul_clamped(secret);
+
+        let wrapping_key = wrapping_key(&shared, &ephemeral, &recipient);
+        let key = ChaCha20Poly1305::new(&wrapping_key.into())
+            .decrypt(&[0u8; 12].into(), stanza.wrapped_key.as_slice())
+            .map_err(|_| EncryptionError::DecryptionFailed)?;
+
+        let key: [u8; KEY_LEN] = key
+            .try_into()
+            .map_err(|_| EncryptionError::InvalidEnvelope("invalid content key length"))?;
+        Ok(SpoolKey::new(key))
+    }
+
+    /// Serialized length, in bytes.
+    pub fn len(&self) -> usize {
+        ENVELOPE_HEADER_LEN + self.stanzas.len() * STANZA_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stanzas.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.len());
+        output.extend_from_slice(&ENVELOPE_MAGIC);
+        output.push(ENVELOPE_VERSION);
+        output.push(self.stanzas.len() as u8);
+        output.extend_from_slice(&[0; 2]);
+        output.extend_from_slice(&self.reference.unwrap_or_default().to_bytes());
+
+        for stanza in &self.stanzas {
+            output.extend_from_slice(&stanza.recipient.to_bytes());
+            output.extend_from_slice(&stanza.ephemeral);
+            output.extend_from_slice(&stanza.wrapped_key);
+        }
+
+        output
+    }
+
+    /// Parses the envelope at the start of `data`, returning it and the data that follows it.
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), EncryptionError> {
+        if data.len() < ENVELOPE_HEADER_LEN {
+            return Err(EncryptionError::InvalidEnvelope("data too short"));
+        }
+        if data[0..4] != ENVELOPE_MAGIC {
+            return Err(EncryptionError::InvalidEnvelope("invalid magic number"));
+        }
+        if data[4] != ENVELOPE_VERSION {
+            return Err(EncryptionError::InvalidEnvelope("unsupported version"));
+        }
+
+        let count = data[5] as usize;
+        let end = ENVELOPE_HEADER_LEN + count * STANZA_LEN;
+        if data.len() < end {
+            return Err(EncryptionError::InvalidEnvelope("data too short"));
+        }
+
+        let reference = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+        let stanzas = data[ENVELOPE_HEADER_LEN..end]
+            .chunks_exact(STANZA_LEN)
+            .map(|chunk| Stanza {
+                recipient: Pubkey::new_from_array(chunk[0..32].try_into().unwrap()),
+                ephemeral: chunk[32..64].try_into().unwrap(),
+                wrapped_key: chunk[64..].try_into().unwrap(),
+            })
+            .collect();
+
+        let envelope = Self {
+            reference: (reference != Pubkey::default()).then_some(reference),
+            stanzas,
+        };
+
+        Ok((envelope, &data[end..]))
+    }
+
+    /// Reads the envelope at the start of `reader`, leaving it at the data that follows.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut data = vec![0u8; ENVELOPE_HEADER_LEN];
+        read_envelope_bytes(reader, &mut data)?;
+
+        let count = data[5] as usize;
+        data.resize(ENVELOPE_HEADER_LEN + count * STANZA_LEN, 0);
+        read_envelope_bytes(reader, &mut data[ENVELOPE_HEADER_LEN..])?;
+
+        Ok(Self::parse(&data)?.0)
+    }
+}
+
+fn read_envelope_bytes<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => EncryptionError::InvalidEnvelope("data too short").into(),
+        _ => anyhow::Error::from(e),
+    })
+}
+
+/// Content key and envelope for a payload sent by `sender` to `recipients`. Both are derived
+/// from the sender's secret and the nonce in `header`, so re-encoding the same payload with the
+/// same header (e.g. to resume its upload) gives the same bytes.
+pub(crate) fn seal_for_header(
+    header: &SpoolHeader,
+    sender: &Keypair,
+    recipients: &[Pubkey],
+) -> Result<(SpoolKey, Envelope), EncryptionError> {
+    let seed = &sender.to_bytes()[..32];
+
+    let mut hasher = blake3::Hasher::new_derive_key(CONTENT_KEY_CONTEXT);
+    hasher.update(seed);
+    hasher.update(&header.iv);
+    hasher.update(&header.iv_ext);
+    let content_key = SpoolKey::new(*hasher.finalize().as_bytes());
+
+    let envelope = Envelope::seal_with(&content_key, recipients, None, |recipient| {
+        let mut hasher = blake3::Hasher::new_derive_key(EPHEMERAL_KEY_CONTEXT);
+        hasher.update(seed);
+        hasher.update(&header.iv);
+        hasher.update(&header.iv_ext);
+        hasher.update(recipient.as_ref());
+        *hasher.finalize().as_bytes()
+    })?;
+
+    Ok((content_key, envelope))
+}
+
+fn wrap(
+    content_key: &SpoolKey,
+    recipient: &Pubkey,
+    ephemeral_secret: [u8; 32],
+) -> Result<Stanza, EncryptionError> {
+    let recipient_point = x25519_public(recipient).ok_or(EncryptionError::InvalidRecipient(*recipient))?;
+    let ephemeral = MontgomeryPoint::mul_base_clamped(ephemeral_secret);
+    let shared = recipient_point.mul_clamped(ephemeral_secret);
+
+    // Low-order recipient keys would give a predictable shared secret
+    if shared.as_bytes() == &[0u8; 32] {
+        return Err(EncryptionError::InvalidRecipient(*recipient));
+    }
+
+    let wrapping_key = wrapping_key(&shared, &ephemeral, &recipient_point);
+    let wrapped_key = ChaCha20Poly1305::new(&wrapping_key.into())
+        .encrypt(&[0u8; 12].into(), content_key.as_bytes().as_slice())
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    Ok(Stanza {
+        recipient: *recipient,
+        ephemeral: ephemeral.to_bytes(),
+        wrapped_key: wrapped_key
+            .try_into()
+            .map_err(|_| EncryptionError::EncryptionFailed)?,
+    })
+}
+
+/// Each wrapping key is used once (fresh ephemeral key per stanza), so a zero nonce is safe.
+fn wrapping_key(shared: &MontgomeryPoint, ephemeral: &MontgomeryPoint, recipient: &MontgomeryPoint) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_derive_key(WRAPPING_KEY_CONTEXT);
+    hasher.update(shared.as_bytes());
+    hasher.update(ephemeral.as_bytes());
+    hasher.update(recipient.as_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// X25519 public key of an ed25519 wallet address.
+fn x25519_public(pubkey: &Pubkey) -> Option<MontgomeryPoint> {
+    CompressedEdwardsY(pubkey.to_bytes())
+        .decompress()
+        .map(|point| point.to_montgomery())
+}
+
+/// X25519 secret of an ed25519 keypair (the unclamped scalar half of its expanded secret).
+fn x25519_secret(keypair: &Keypair) -> [u8; 32] {
+    use sha2::Digest;
+
+    let hash = Sha512::digest(&keypair.to_bytes()[..32]);
+    hash[..32].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_spool_with_key, decode_spool_with_keypair, encode_envelope_spool,
+        encode_spool_for_recipients, open_envelope, CompressionAlgo, EncryptionAlgo, MimeType,
+        SpoolFlags,
+    };
+
+    #[test]
+    fn test_seal_and_open() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let key = SpoolKey::generate();
+
+        let envelope = Envelope::seal(&key, &[alice.pubkey(), bob.pubkey(), alice.pubkey()], None).unwrap();
+        assert_eq!(envelope.stanzas.len(), 2);
+        assert_eq!(envelope.open(&alice).unwrap(), key);
+        assert_eq!(envelope.open(&bob).unwrap(), key);
+
+        let eve = Keypair::new();
+        assert!(matches!(envelope.open(&eve), Err(EncryptionError::NotARecipient(_))));
+    }
+
+    #[test]
+    fn test_tampered_stanza_fails() {
+        let alice = Keypair::new();
+        let mut envelope = Envelope::seal(&SpoolKey::generate(), &[alice.pubkey()], None).unwrap();
+        envelope.stanzas[0].wrapped_key[0] ^= 1;
+
+        assert!(matches!(envelope.open(&alice), Err(EncryptionError::DecryptionFailed)));
+    }
+
+    #[test]
+    fn test_roundtrip_bytes() {
+        let reference = Pubkey::new_unique();
+        let recipients = [Keypair::new().pubkey(), Keypair::new().pubkey()];
+        let envelope = Envelope::seal(&SpoolKey::generate(), &recipients, Some(reference)).unwrap();
+
+        let mut bytes = envelope.to_bytes();
+        assert_eq!(bytes.len(), envelope.len());
+        bytes.extend_from_slice(b"payload");
+
+        let (parsed, rest) = Envelope::parse(&bytes).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.reference, Some(reference));
+        assert_eq!(rest, b"payload");
+
+        assert!(Envelope::parse(&bytes[..50]).is_err());
+        assert!(Envelope::parse(b"not an envelope, just some data here...").is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_recipients() {
+        assert!(Envelope::seal(&SpoolKey::generate(), &[], None).is_err());
+
+        // Identity point (low order)
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let result = Envelope::seal(&SpoolKey::generate(), &[Pubkey::new_from_array(identity)], None);
+        assert!(matches!(result, Err(EncryptionError::InvalidRecipient(_))));
+    }
+
+    #[test]
+    fn test_seal_for_header_is_deterministic() {
+        let sender = Keypair::new();
+        let recipients = [sender.pubkey(), Keypair::new().pubkey()];
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::None,
+            EncryptionAlgo::Aes256Gcm,
+            SpoolFlags::Prefixed,
+        );
+        header.iv = [7; 12];
+
+        let (key_a, envelope_a) = seal_for_header(&header, &sender, &recipients).unwrap();
+        let (key_b, envelope_b) = seal_for_header(&header, &sender, &recipients).unwrap();
+        assert_eq!(key_a, key_b);
+        assert_eq!(envelope_a, envelope_b);
+        assert_eq!(envelope_a.open(&sender).unwrap(), key_a);
+
+        header.iv = [8; 12];
+        let (key_c, _) = seal_for_header(&header, &sender, &recipients).unwrap();
+        assert_ne!(key_a, key_c);
+    }
+
+    #[test]
+    fn test_encode_decode_for_recipients() {
+        let sender = Keypair::new();
+        let reader = Keypair::new();
+        let data = b"for your eyes only".repeat(64);
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::XChaCha20Poly1305,
+            SpoolFlags::Prefixed,
+        );
+
+        let encoded = encode_spool_for_recipients(
+            &data,
+            &mut header,
+            &sender,
+            &[sender.pubkey(), reader.pubkey()],
+        )
+        .unwrap();
+        assert!(header.has_flag(SpoolFlags::Envelope));
+
+        let decoded = decode_spool_with_keypair(encoded.clone(), &header, &reader).unwrap();
+        assert_eq!(decoded, data);
+        assert!(decode_spool_with_keypair(encoded.clone(), &header, &Keypair::new()).is_err());
+
+        // Share with a new reader through an envelope spool referencing the original
+        let (key, reference) = open_envelope(encoded.clone(), &header, &sender).unwrap();
+        assert_eq!(reference, None);
+
+        let original = Pubkey::new_unique();
+        let late_reader = Keypair::new();
+        let envelope = Envelope::seal(&key, &[late_reader.pubkey()], Some(original)).unwrap();
+        let mut share_header = SpoolHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::XChaCha20Poly1305,
+            SpoolFlags::Prefixed,
+        );
+        let share = encode_envelope_spool(&envelope, &mut share_header).unwrap();
+
+        let err = decode_spool_with_keypair(share.clone(), &share_header, &late_reader).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<EncryptionError>(),
+            Some(EncryptionError::Referenced(address)) if *address == original
+        ));
+
+        let (key, reference) = open_envelope(share, &share_header, &late_reader).unwrap();
+        assert_eq!(reference, Some(original));
+        assert_eq!(decode_spool_with_key(encoded, &header, Some(&key)).unwrap(), data);
+    }
+}
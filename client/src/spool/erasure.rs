@@ -0,0 +1,315 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use spool_api::prelude::*;
+
+use super::{SpoolHeader, PREFIX_LEN};
+
+/// Most data and parity segments a stripe can have together.
+pub const MAX_STRIPE_SEGMENTS: usize = 256;
+
+/// Payload bytes of a prefixed segment, the size of a shard.
+const SHARD_SIZE: usize = SEGMENT_SIZE - PREFIX_LEN;
+
+/// Reed-Solomon parameters of an erasure-coded spool: every stripe of `data_segments`
+/// consecutive payload segments gets `parity_segments` parity segments, and any
+/// `data_segments` of them rebuild the stripe.
+///
+/// Parity segments are prefixed like payload segments, with the indexes that follow the last
+/// payload segment (the parity of stripe `s` starts at `payload segments + s * parity_segments`).
+/// The last stripe is padded with zeroed segments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErasureParams {
+    data_segments: u8,
+    parity_segments: u8,
+}
+
+impl ErasureParams {
+    pub fn new(data_segments: usize, parity_segments: usize) -> Result<Self> {
+        if data_segments == 0 || parity_segments == 0 {
+            bail!("Erasure coding needs at least one data and one parity segment per stripe");
+        }
+        if data_segments + parity_segments > MAX_STRIPE_SEGMENTS {
+            bail!("Stripes can't have more than {} segments", MAX_STRIPE_SEGMENTS);
+        }
+
+        Ok(Self {
+            data_segments: data_segments as u8,
+            parity_segments: parity_segments as u8,
+        })
+    }
+
+    pub fn data_segments(&self) -> usize {
+        self.data_segments as usize
+    }
+
+    pub fn parity_segments(&self) -> usize {
+        self.parity_segments as usize
+    }
+
+    /// Parity segments added to `payload_segments` payload segments.
+    pub fn parity_count(&self, payload_segments: u64) -> u64 {
+        payload_segments.div_ceil(self.data_segments as u64) * self.parity_segments as u64
+    }
+
+    /// Most payload segments that fit in `total` segments along with their parity.
+    pub fn max_payload_segments(&self, total: usize) -> usize {
+        let stripe = self.data_segments() + self.parity_segments();
+        let last = (total % stripe).saturating_sub(self.parity_segments());
+        total / stripe * self.data_segments() + last
+    }
+
+    fn codec(&self) -> Result<ReedSolomon> {
+        ReedSolomon::new(self.data_segments(), self.parity_segments())
+            .map_err(|e| anyhow!("Invalid erasure coding parameters: {}", e))
+    }
+}
+
+impl fmt::Display for ErasureParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.data_segments, self.parity_segments)
+    }
+}
+
+/// Parses `DATA:PARITY`, e.g. `16:4` for 4 parity segments every 16 payload segments.
+impl FromStr for ErasureParams {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (data, parity) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid redundancy {:?}, expected DATA:PARITY", s))?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid redundancy {:?}, expected DATA:PARITY", s))
+        };
+
+        Self::new(parse(data)?, parse(parity)?)
+    }
+}
+
+/// Computes the parity of the payload segments written by a `SegmentWriter`, a stripe at a
+/// time.
+pub(crate) struct StripeEncoder {
+    params: ErasureParams,
+    codec: ReedSolomon,
+    stripe: Vec<Vec<u8>>,
+    parity: Vec<Vec<u8>>,
+}
+
+impl StripeEncoder {
+    pub fn new(params: ErasureParams) -> Result<Self> {
+        Ok(Self {
+            params,
+            codec: params.codec()?,
+            stripe: Vec::with_capacity(params.data_segments() + params.parity_segments()),
+            parity: Vec::new(),
+        })
+    }
+
+    /// Adds the payload of the next segment.
+    pub fn push(&mut self, payload: &[u8]) {
+        self.stripe.push(padded_shard(payload));
+        if self.stripe.len() == self.params.data_segments() {
+            self.encode_stripe();
+        }
+    }
+
+    /// The payloads of the parity segments, in index order.
+    pub fn finish(mut self) -> Vec<Vec<u8>> {
+        if !self.stripe.is_empty() {
+            self.stripe.resize(self.params.data_segments(), vec![0; SHARD_SIZE]);
+            self.encode_stripe();
+        }
+        self.parity
+    }
+
+    fn encode_stripe(&mut self) {
+        let mut shards = std::mem::take(&mut self.stripe);
+        shards.resize(
+            self.params.data_segments() + self.params.parity_segments(),
+            vec![0; SHARD_SIZE],
+        );
+
+        self.codec
+            .encode(&mut shards)
+            .expect("shards have the codec's count and size");
+        self.parity.extend(shards.drain(self.params.data_segments()..));
+    }
+}
+
+/// Prefixed segments rebuilt by `recover_segments`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// The payload segments, prefixed and in index order, without parity.
+    pub data: Vec<u8>,
+    /// Indexes of the payload segments that were missing and rebuilt from parity.
+    pub recovered: Vec<u64>,
+}
+
+/// Rebuilds the payload segments missing from the raw data of an erasure-coded spool. Segments
+/// may come in any order and repeated indexes are skipped, as in `UnprefixReader`. Fails if a
+/// stripe has fewer segments left than its number of data segments.
+pub fn recover_segments(data: &[u8], header: &SpoolHeader) -> Result<Recovery> {
+    let params = header
+        .erasure()
+        .ok_or_else(|| anyhow!("The spool is not erasure coded"))?;
+
+    let mut segments: BTreeMap<u64, &[u8]> = BTreeMap::new();
+    for segment in data.chunks(SEGMENT_SIZE) {
+        if segment.len() < PREFIX_LEN {
+            bail!("Invalid segment size: too small");
+        }
+        let index = u64::from_be_bytes(segment[..PREFIX_LEN].try_into().unwrap());
+        segments.entry(index).or_insert(&segment[PREFIX_LEN..]);
+    }
+
+    let payload_segments = header.data_len.div_ceil(SHARD_SIZE as u64);
+    let data_count = params.data_segments() as u64;
+    let parity_count = params.parity_segments() as u64;
+    let codec = params.codec()?;
+
+    let mut rebuilt: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+    for stripe in 0..payload_segments.div_ceil(data_count) {
+        let first = stripe * data_count;
+        let last = (first + data_count).min(payload_segments);
+
+        let missing: Vec<u64> = (first..last).filter(|n| !segments.contains_key(n)).collect();
+        if missing.is_empty() {
+            continue;
+        }
+
+        // Indexes past the payload are the zeroed segments padding the last stripe
+        let parity_start = payload_segments + stripe * parity_count;
+        let mut shards: Vec<Option<Vec<u8>>> = (first..first + data_count)
+            .map(|n| match n < last {
+                true => segments.get(&n).map(|payload| padded_shard(payload)),
+                false => Some(vec![0; SHARD_SIZE]),
+            })
+            .chain((parity_start..parity_start + parity_count).map(|n| {
+                segments.get(&n).map(|payload| padded_shard(payload))
+            }))
+            .collect();
+
+        let present = shards.iter().filter(|shard| shard.is_some()).count();
+        if present < params.data_segments() {
+            bail!(
+                "Can't recover stripe {}: {} of its segments are missing, at most {} can be",
+                stripe,
+                shards.len() - present,
+                params.parity_segments()
+            );
+        }
+
+        codec
+            .reconstruct_data(&mut shards)
+            .map_err(|e| anyhow!("Failed to recover stripe {}: {}", stripe, e))?;
+
+        for n in missing {
+            let shard = shards[(n - first) as usize].take().expect("reconstructed");
+            rebuilt.insert(n, shard);
+        }
+    }
+
+    let mut output = Vec::with_capacity(payload_segments as usize * SEGMENT_SIZE);
+    for n in 0..payload_segments {
+        output.extend_from_slice(&n.to_be_bytes());
+        match rebuilt.get(&n) {
+            Some(shard) => output.extend_from_slice(shard),
+            None => output.extend_from_slice(&padded_shard(segments[&n])),
+        }
+    }
+
+    Ok(Recovery {
+        data: output,
+        recovered: rebuilt.into_keys().collect(),
+    })
+}
+
+/// On-chain segments of a prefixed payload of `data_len` bytes, parity segments included.
+pub fn prefixed_segment_count(data_len: u64, erasure: Option<ErasureParams>) -> u64 {
+    let payload_segments = data_len.div_ceil(SHARD_SIZE as u64);
+    match erasure {
+        Some(params) => payload_segments + params.parity_count(payload_segments),
+        None => payload_segments,
+    }
+}
+
+fn padded_shard(payload: &[u8]) -> Vec<u8> {
+    let mut shard = vec![0; SHARD_SIZE];
+    let len = payload.len().min(SHARD_SIZE);
+    shard[..len].copy_from_slice(&payload[..len]);
+    shard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_spool, encode_spool, CompressionAlgo, EncryptionAlgo, MimeType, SpoolFlags,
+    };
+
+    fn encoded(data: &[u8], params: &str) -> (Vec<u8>, SpoolHeader) {
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            SpoolFlags::Prefixed,
+        );
+        header.set_erasure(Some(params.parse().unwrap()));
+        let encoded = encode_spool(data, &mut header).unwrap();
+        (encoded, header)
+    }
+
+    fn without(encoded: &[u8], indexes: &[usize]) -> Vec<u8> {
+        encoded
+            .chunks(SEGMENT_SIZE)
+            .enumerate()
+            .filter(|(n, _)| !indexes.contains(n))
+            .flat_map(|(_, segment)| segment.to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_recovers_missing_segments() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let (encoded, header) = encoded(&data, "4:2");
+
+        // 9 payload segments in 3 stripes, the last one padded, with 2 parity segments each
+        assert_eq!(encoded.len(), 15 * SEGMENT_SIZE);
+        assert_eq!(prefixed_segment_count(header.data_len, header.erasure()), 15);
+        assert_eq!(decode_spool(encoded.clone(), &header).unwrap(), data);
+
+        let gaps = without(&encoded, &[1, 3, 8, 14]);
+        let recovery = recover_segments(&gaps, &header).unwrap();
+        assert_eq!(recovery.recovered, vec![1, 3, 8]);
+        assert_eq!(decode_spool(gaps, &header).unwrap(), data);
+
+        let complete = recover_segments(&encoded, &header).unwrap();
+        assert!(complete.recovered.is_empty());
+    }
+
+    #[test]
+    fn test_fails_past_parity() {
+        let (encoded, header) = encoded(&[3; 500], "4:1");
+        assert!(decode_spool(without(&encoded, &[0, 1]), &header).is_err());
+        assert!(decode_spool(without(&encoded, &[0, 4]), &header).is_ok());
+    }
+
+    #[test]
+    fn test_params() {
+        let params: ErasureParams = "16:4".parse().unwrap();
+        assert_eq!(params.to_string(), "16:4");
+        assert_eq!(params.parity_count(17), 8);
+        assert_eq!(params.max_payload_segments(44), 32);
+        assert_eq!(params.max_payload_segments(45), 33);
+        assert_eq!(params.max_payload_segments(46), 34);
+        assert_eq!(prefixed_segment_count(33 * 120, Some(params)), 45);
+
+        for invalid in ["16", "0:4", "4:0", "200:100", "a:b"] {
+            assert!(invalid.parse::<ErasureParams>().is_err(), "{invalid}");
+        }
+    }
+}
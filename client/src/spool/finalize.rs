@@ -2,34 +2,24 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use solana_sdk::{
-    signature::{Keypair, Signer},
+    signature::Keypair,
     pubkey::Pubkey,
 };
-use spool_api::instruction::spool::build_finalize_ix;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use crate::utils::*;
 
-/// Finalizes the spool with the last segment's signature.
+use crate::{RpcBackend, SpoolClient};
+
+/// Finalizes the spool with the last segment's signature. The payer covers the fee and
+/// receives the writer's rent back.
 pub async fn finalize_spool(
-    client: &Arc<RpcClient>,
-    signer: &Keypair,
+    client: &Arc<impl RpcBackend + 'static>,
+    payer: &Keypair,
+    authority: &Keypair,
     spool_address: Pubkey,
     writer_address: Pubkey,
 ) -> Result<()> {
-
-    let finalize_ix = build_finalize_ix(
-        signer.pubkey(),
-        spool_address,
-        writer_address,
-    );
-
-    build_send_and_confirm_tx(
-        &[finalize_ix],
-        client,
-        signer.pubkey(),
-        &[signer]
-    ).await?;
+    SpoolClient::preset(client.clone(), payer.insecure_clone())
+        .finalize_spool_with_authority(authority, spool_address, writer_address)
+        .await?;
 
     Ok(())
 }
-
@@ -1,15 +1,15 @@
 use std::sync::Arc;
 
-use spool_api::instruction::spool::build_set_header_ix;
 use anyhow::{Result, anyhow, bail};
 use bytemuck::{Pod, Zeroable};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use solana_sdk::{
-    signature::{Keypair, Signer, Signature},
+    signature::{Keypair, Signature},
     pubkey::Pubkey,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
-use crate::utils::*;
+use crate::{RpcBackend, SpoolClient};
+
+use super::ErasureParams;
 
 /// A 4-byte "magic" prefix to identify the header format.
 pub const HEADER_MAGIC: [u8; 4] = *b"SPOL";
@@ -34,6 +34,24 @@ pub enum SpoolFlags {
     /// quickly.
     Prefixed = 1 << 0,
 
+    /// The spool data starts with a key envelope: the content key of the (encrypted) payload,
+    /// wrapped for each recipient's wallet. See `Envelope`.
+    Envelope = 1 << 1,
+
+    /// The spool data is a manifest listing the part spools that hold the payload, which is too
+    /// large for a single spool. See `Manifest`. The rest of the header describes the payload
+    /// stitched back from the parts.
+    Manifest = 1 << 2,
+
+    /// The decoded spool data is a directory listing: paths of files mapped to the spools that
+    /// hold them. See `Directory`.
+    Directory = 1 << 3,
+
+    /// Parity segments follow the prefixed payload segments, so segments missing from a read
+    /// can be rebuilt. Requires `Prefixed`; the header's `erasure_data` and `erasure_parity`
+    /// give the stripe layout. See `ErasureParams`.
+    ErasureCoded = 1 << 4,
+
     // Extend as needed...
 }
 
@@ -68,7 +86,22 @@ pub struct SpoolHeader {
     pub encryption_algo: u8,
 
     _alignment: [u8; 3], // reserved for future use
-    _unused: [u8; 32],   // reserved for future use
+
+    /// Second half of the 24-byte nonce, for algorithms that need one (XChaCha20-Poly1305).
+    pub iv_ext: [u8; 12],
+
+    /// Argon2 salt if the key was derived from a passphrase, all zeros for raw keys.
+    pub kdf_salt: [u8; 16],
+
+    /// Compression level used by the encoder (0 for the codec default). Informational only,
+    /// decoders don't need it.
+    pub compression_level: u8,
+
+    /// Payload and parity segments per stripe if the `ErasureCoded` flag is set, zero otherwise.
+    pub erasure_data: u8,
+    pub erasure_parity: u8,
+
+    _unused: [u8; 1],    // reserved for future use
 }
 
 impl SpoolHeader {
@@ -88,10 +121,68 @@ impl SpoolHeader {
             data_len         : 0,
             iv               : [0; 12],
             _alignment       : [0; 3],
-            _unused          : [0; 32],
+            iv_ext           : [0; 12],
+            kdf_salt         : [0; 16],
+            compression_level: 0,
+            erasure_data     : 0,
+            erasure_parity   : 0,
+            _unused          : [0; 1],
         }
     }
 
+    /// Whether `flag` is set.
+    pub fn has_flag(&self, flag: SpoolFlags) -> bool {
+        self.flags & flag as u8 != 0
+    }
+
+    /// Flags set in the header.
+    pub fn flag_list(&self) -> Vec<SpoolFlags> {
+        [
+            SpoolFlags::Prefixed,
+            SpoolFlags::Envelope,
+            SpoolFlags::Manifest,
+            SpoolFlags::Directory,
+            SpoolFlags::ErasureCoded,
+        ]
+            .into_iter()
+            .filter(|flag| self.has_flag(*flag))
+            .collect()
+    }
+
+    /// Erasure coding parameters, if the `ErasureCoded` flag is set and they are valid.
+    pub fn erasure(&self) -> Option<ErasureParams> {
+        if !self.has_flag(SpoolFlags::ErasureCoded) {
+            return None;
+        }
+        ErasureParams::new(self.erasure_data as usize, self.erasure_parity as usize).ok()
+    }
+
+    /// Sets (or clears, with `None`) erasure coding. Erasure-coded spools are always prefixed.
+    pub fn set_erasure(&mut self, erasure: Option<ErasureParams>) {
+        match erasure {
+            Some(params) => {
+                self.flags |= SpoolFlags::ErasureCoded as u8 | SpoolFlags::Prefixed as u8;
+                self.erasure_data = params.data_segments() as u8;
+                self.erasure_parity = params.parity_segments() as u8;
+            }
+            None => {
+                self.flags &= !(SpoolFlags::ErasureCoded as u8);
+                self.erasure_data = 0;
+                self.erasure_parity = 0;
+            }
+        }
+    }
+
+    /// Whether the payload is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption_algo != EncryptionAlgo::None as u8
+    }
+
+    /// Argon2 salt of the payload key, if it was derived from a passphrase.
+    pub fn kdf_salt(&self) -> Option<[u8; 16]> {
+        (self.kdf_salt != [0; 16]).then_some(self.kdf_salt)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         bytemuck::bytes_of(self).to_vec()
     }
@@ -131,8 +222,13 @@ impl std::fmt::Debug for SpoolHeader {
             .field("flags", &self.flags)
             .field("mime_type", &self.mime_type)
             .field("compression", &self.compression)
+            .field("compression_level", &self.compression_level)
+            .field("erasure_data", &self.erasure_data)
+            .field("erasure_parity", &self.erasure_parity)
             .field("encryption_algo", &self.encryption_algo)
             .field("iv", &self.iv)
+            .field("iv_ext", &self.iv_ext)
+            .field("kdf_salt", &self.kdf_salt)
             .finish()
     }
 }
@@ -143,8 +239,35 @@ impl std::fmt::Debug for SpoolHeader {
 pub enum CompressionAlgo {
     None   = 0,
     Gzip   = 1,
+    Zstd   = 2,
+    Brotli = 3,
+    Lz4    = 4,
 
     // Extend as needed...
+
+    /// Let `encode_spool` pick: no compression for already-compressed MIME types, otherwise
+    /// whichever codec gives the smallest output. The choice is recorded in the header, so this
+    /// value is never written on-chain.
+    Auto   = 0xFF,
+}
+
+impl std::str::FromStr for CompressionAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none"   => Ok(CompressionAlgo::None),
+            "gzip"   => Ok(CompressionAlgo::Gzip),
+            "zstd"   => Ok(CompressionAlgo::Zstd),
+            "brotli" => Ok(CompressionAlgo::Brotli),
+            "lz4"    => Ok(CompressionAlgo::Lz4),
+            "auto"   => Ok(CompressionAlgo::Auto),
+            _ => bail!(
+                "Unknown compression algorithm: {} (expected none, gzip, zstd, brotli, lz4 or auto)",
+                s
+            ),
+        }
+    }
 }
 
 #[repr(u8)]
@@ -152,6 +275,8 @@ pub enum CompressionAlgo {
 /// The encryption algorithm used on the payload (if any).
 pub enum EncryptionAlgo {
     None               = 0,
+    Aes256Gcm          = 1,
+    XChaCha20Poly1305  = 2,
 
     // Extend as needed...
 }
@@ -217,31 +342,41 @@ pub enum MimeType {
     ApplicationYaml        = 72,  // application/x-yaml
 }
 
+impl MimeType {
+    /// Whether the format is already compressed, so compressing it again won't help.
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            MimeType::ImagePng
+                | MimeType::ImageJpeg
+                | MimeType::ImageGif
+                | MimeType::ImageWebp
+                | MimeType::ApplicationDocx
+                | MimeType::ApplicationOdt
+                | MimeType::AudioMpeg
+                | MimeType::AudioOgg
+                | MimeType::AudioFlac
+                | MimeType::VideoMp4
+                | MimeType::VideoWebm
+                | MimeType::VideoMpeg
+                | MimeType::ApplicationZip
+                | MimeType::ApplicationGzip
+                | MimeType::FontWoff
+                | MimeType::FontWoff2
+        )
+    }
+}
+
 /// Sets the spool header for a given spool account.
 pub async fn set_header(
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     signer: &Keypair,
     spool_address: Pubkey,
     header: SpoolHeader,
 ) -> Result<(Pubkey, Signature)> {
-
-    let header_data = &header.to_bytes().try_into()
-        .map_err(|_| anyhow::anyhow!("Failed to convert header to bytes"))?;
-
-    let set_header_ix = build_set_header_ix(
-        signer.pubkey(), 
-        spool_address,
-        header_data
-    );
-
-    let signature = build_send_and_confirm_tx(
-        &[set_header_ix],
-        client,
-        signer.pubkey(),
-        &[signer]
-    ).await?;
-
-    Ok((spool_address, signature))
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .set_header(spool_address, header)
+        .await
 }
 
 
@@ -265,6 +400,8 @@ mod tests {
         assert_eq!(header.compression, CompressionAlgo::None as u8);
         assert_eq!(header.encryption_algo, EncryptionAlgo::None as u8);
         assert_eq!(header.iv, [0; 12]);
+        assert!(!header.is_encrypted());
+        assert_eq!(header.kdf_salt(), None);
     }
 
     #[test]
@@ -278,5 +415,6 @@ mod tests {
 
         let bytes = header.to_bytes();
         assert_eq!(bytes.len(), std::mem::size_of::<SpoolHeader>());
+        assert_eq!(bytes.len(), 64);
     }
 }
@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::utils::SegmentVersion;
+
+/// Every version of the segments of a spool seen on-chain, ordered by slot, transaction index
+/// and instruction index. Writes and updates of a segment replace each other in that order, no
+/// matter the order they were read in.
+#[derive(Debug, Default, Clone)]
+pub struct SpoolHistory {
+    segments: BTreeMap<u64, Vec<SegmentVersion>>,
+}
+
+impl SpoolHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a version, keeping the versions of its segment ordered. Returns false if a version
+    /// at the same position was already recorded.
+    pub fn record(&mut self, version: SegmentVersion) -> bool {
+        let versions = self.segments.entry(version.segment_number).or_default();
+
+        match versions.binary_search_by_key(&version.position(), SegmentVersion::position) {
+            Ok(_) => false,
+            Err(index) => {
+                versions.insert(index, version);
+                true
+            }
+        }
+    }
+
+    /// The versions of a segment, oldest first.
+    pub fn versions(&self, segment_number: u64) -> &[SegmentVersion] {
+        self.segments
+            .get(&segment_number)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The version of a segment as of the end of `slot` (the latest if `None`).
+    pub fn segment_at(&self, segment_number: u64, slot: Option<u64>) -> Option<&SegmentVersion> {
+        let versions = self.versions(segment_number);
+        match slot {
+            Some(slot) => versions.iter().rev().find(|version| version.slot <= slot),
+            None => versions.last(),
+        }
+    }
+
+    /// The content of every segment as of the end of `slot` (the latest if `None`).
+    pub fn segments_at(&self, slot: Option<u64>) -> HashMap<u64, Vec<u8>> {
+        self.segment_numbers()
+            .filter_map(|n| self.segment_at(n, slot).map(|version| (n, version.data.clone())))
+            .collect()
+    }
+
+    /// The numbers of the segments with at least one version, in order.
+    pub fn segment_numbers(&self) -> impl Iterator<Item = u64> + '_ {
+        self.segments.keys().copied()
+    }
+
+    /// The slots in which the spool's segments changed, in order.
+    pub fn slots(&self) -> BTreeSet<u64> {
+        self.segments.values().flatten().map(|version| version.slot).collect()
+    }
+
+    /// The number of versions recorded, over all segments.
+    pub fn len(&self) -> usize {
+        self.segments.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::SegmentChange;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn version(segment_number: u64, slot: u64, tx_index: usize, change: SegmentChange, byte: u8) -> SegmentVersion {
+        SegmentVersion {
+            address: Pubkey::default(),
+            segment_number,
+            slot,
+            tx_index,
+            ix_index: 0,
+            prev_slot: 0,
+            change,
+            data: vec![byte; 4],
+        }
+    }
+
+    #[test]
+    fn test_history_orders_versions() {
+        let mut history = SpoolHistory::new();
+
+        // Recorded newest first, as the block walk sees them
+        assert!(history.record(version(0, 20, 3, SegmentChange::Update, 3)));
+        assert!(history.record(version(0, 20, 1, SegmentChange::Update, 2)));
+        assert!(history.record(version(1, 10, 0, SegmentChange::Write, 9)));
+        assert!(history.record(version(0, 10, 0, SegmentChange::Write, 1)));
+        assert!(!history.record(version(0, 10, 0, SegmentChange::Write, 1)));
+
+        let bytes: Vec<u8> = history.versions(0).iter().map(|v| v.data[0]).collect();
+        assert_eq!(bytes, vec![1, 2, 3]);
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.slots().into_iter().collect::<Vec<_>>(), vec![10, 20]);
+
+        assert_eq!(history.segment_at(0, None).unwrap().data[0], 3);
+        assert_eq!(history.segment_at(0, Some(19)).unwrap().data[0], 1);
+        assert!(history.segment_at(0, Some(9)).is_none());
+
+        let at_10 = history.segments_at(Some(10));
+        assert_eq!(at_10.len(), 2);
+        assert_eq!(at_10[&0][0], 1);
+        assert!(history.segments_at(Some(5)).is_empty());
+    }
+}
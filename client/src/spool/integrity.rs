@@ -0,0 +1,446 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use spool_api::prelude::*;
+
+use super::{prefixed_segment_count, SpoolFlags, SpoolHeader};
+
+/// Outcome of comparing what is on-chain against the encoded payload of an upload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Segments in the payload.
+    pub expected_segments: u64,
+    /// Segments in the spool account.
+    pub onchain_segments: u64,
+    /// Payload segments with no matching copy on-chain.
+    pub missing: Vec<u64>,
+    /// Payload segments written more than once (harmless for prefixed spools, but paid for).
+    pub duplicated: Vec<u64>,
+    /// On-chain segments whose data is not part of the payload.
+    pub mismatched: Vec<u64>,
+    /// Root of the segment tree rebuilt locally from the payload.
+    pub expected_root: [u8; 32],
+    /// Root stored in the spool account.
+    pub onchain_root: [u8; 32],
+}
+
+impl IntegrityReport {
+    /// The spool holds exactly the payload (up to duplicates) and the roots agree.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.mismatched.is_empty()
+            && self.expected_root == self.onchain_root
+    }
+
+    /// The spool holds data that isn't part of the payload; writing more won't fix it. That's
+    /// also the case when nothing is missing but the roots still differ, as with sequential
+    /// spools whose on-chain segments can't be told apart.
+    pub fn is_corrupt(&self) -> bool {
+        !self.mismatched.is_empty()
+            || (self.missing.is_empty() && self.expected_root != self.onchain_root)
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "segments: expected {}, on-chain {}", self.expected_segments, self.onchain_segments)?;
+        writeln!(f, "missing: {}", summarize(&self.missing))?;
+        writeln!(f, "duplicated: {}", summarize(&self.duplicated))?;
+        writeln!(f, "mismatched: {}", summarize(&self.mismatched))?;
+        writeln!(f, "expected root: {}", Pubkey::new_from_array(self.expected_root))?;
+        write!(f, "on-chain root: {}", Pubkey::new_from_array(self.onchain_root))
+    }
+}
+
+/// Outcome of checking the segments collected by a read against the spool account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadVerification {
+    /// Segments in the spool account.
+    pub onchain_segments: u64,
+    /// Segments collected by the read.
+    pub read_segments: u64,
+    /// Segments of the spool the read didn't find.
+    pub missing: Vec<u64>,
+    /// Segments the read found past the end of the spool.
+    pub unexpected: Vec<u64>,
+    /// Prefixed segments whose index is past the end of the payload, or whose data differs from
+    /// another copy of the same payload segment.
+    pub inconsistent: Vec<u64>,
+    /// Root of the segment tree rebuilt from the segments read, unless some are missing.
+    pub computed_root: Option<[u8; 32]>,
+    /// Root stored in the spool account.
+    pub onchain_root: [u8; 32],
+}
+
+impl ReadVerification {
+    /// Every segment of the spool was read and they hash to the on-chain root.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.inconsistent.is_empty()
+            && self.computed_root == Some(self.onchain_root)
+    }
+}
+
+impl fmt::Display for ReadVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "segments: on-chain {}, read {}", self.onchain_segments, self.read_segments)?;
+        writeln!(f, "missing: {}", summarize(&self.missing))?;
+        writeln!(f, "unexpected: {}", summarize(&self.unexpected))?;
+        writeln!(f, "inconsistent: {}", summarize(&self.inconsistent))?;
+        match self.computed_root {
+            Some(root) => writeln!(f, "computed root: {}", Pubkey::new_from_array(root))?,
+            None => writeln!(f, "computed root: unknown, segments are missing")?,
+        }
+        write!(f, "on-chain root: {}", Pubkey::new_from_array(self.onchain_root))
+    }
+}
+
+/// Lists the first few segment numbers of a diff.
+fn summarize(segments: &[u64]) -> String {
+    const SHOWN: usize = 16;
+
+    if segments.is_empty() {
+        return "none".to_string();
+    }
+
+    let shown: Vec<String> = segments.iter().take(SHOWN).map(|s| s.to_string()).collect();
+    if segments.len() > SHOWN {
+        format!("{} (and {} more)", shown.join(", "), segments.len() - SHOWN)
+    } else {
+        shown.join(", ")
+    }
+}
+
+/// Splits an encoded payload into the segments it occupies on-chain.
+pub fn payload_segments(payload: &[u8]) -> Vec<[u8; SEGMENT_SIZE]> {
+    payload
+        .chunks(SEGMENT_SIZE)
+        .map(padded_array::<SEGMENT_SIZE>)
+        .collect()
+}
+
+/// Rebuilds the segment tree of a spool from segments in on-chain order and returns its root.
+pub fn segment_root(spool_address: &Pubkey, segments: &[[u8; SEGMENT_SIZE]]) -> Result<[u8; 32]> {
+    tree_root(spool_address, segments.iter().copied())
+}
+
+fn tree_root(
+    spool_address: &Pubkey,
+    segments: impl Iterator<Item = [u8; SEGMENT_SIZE]>,
+) -> Result<[u8; 32]> {
+    let mut tree = SegmentTree::new(&[spool_address.as_ref()]);
+
+    for (segment_number, segment) in segments.enumerate() {
+        tree.try_add_leaf(compute_leaf(segment_number as u64, &segment))
+            .map_err(|e| anyhow!("Failed to rebuild segment tree: {:?}", e))?;
+    }
+
+    Ok(tree.get_root().to_bytes())
+}
+
+/// Checks the segments collected by a read (keyed by on-chain segment number, as in
+/// `ReadState`) against the spool account: every segment must be there, and the segment tree
+/// rebuilt from them must have the on-chain root. Prefixed segments are also checked against
+/// each other, since a root mismatch alone can't tell which segments are wrong.
+pub fn verify_read(
+    spool_address: &Pubkey,
+    spool: &Spool,
+    segments: &HashMap<u64, Vec<u8>>,
+) -> Result<ReadVerification> {
+    let total = spool.total_segments;
+
+    let missing: Vec<u64> = (0..total).filter(|n| !segments.contains_key(n)).collect();
+    let mut unexpected: Vec<u64> = segments.keys().copied().filter(|&n| n >= total).collect();
+    unexpected.sort_unstable();
+
+    let header = SpoolHeader::try_from_bytes(&spool.header)?;
+    let inconsistent = if header.has_flag(SpoolFlags::Prefixed) && header.data_len > 0 {
+        inconsistent_prefixes(segments, total, prefixed_segment_count(header.data_len, header.erasure()))
+    } else {
+        Vec::new()
+    };
+
+    let computed_root = match (missing.is_empty(), total) {
+        (false, _) => None,
+        // The root is only set by the first write
+        (true, 0) => Some([0; 32]),
+        (true, _) => Some(tree_root(
+            spool_address,
+            (0..total).map(|n| padded_array::<SEGMENT_SIZE>(&segments[&n])),
+        )?),
+    };
+
+    Ok(ReadVerification {
+        onchain_segments: total,
+        read_segments: segments.len() as u64,
+        missing,
+        unexpected,
+        inconsistent,
+        computed_root,
+        onchain_root: spool.merkle_root,
+    })
+}
+
+/// On-chain segments whose prefix is past the `payload_segments` of the payload (parity
+/// included), or that disagree with another on-chain copy of the same payload segment.
+fn inconsistent_prefixes(segments: &HashMap<u64, Vec<u8>>, total: u64, payload_segments: u64) -> Vec<u64> {
+    let mut copies: HashMap<u64, (u64, [u8; SEGMENT_SIZE])> = HashMap::new();
+    let mut inconsistent = Vec::new();
+
+    for onchain_number in 0..total {
+        let Some(data) = segments.get(&onchain_number) else {
+            continue;
+        };
+        let segment = padded_array::<SEGMENT_SIZE>(data);
+        let payload_number = u64::from_be_bytes(segment[..8].try_into().unwrap());
+
+        if payload_number >= payload_segments {
+            inconsistent.push(onchain_number);
+            continue;
+        }
+
+        match copies.get(&payload_number) {
+            Some((first, copy)) if *copy != segment => {
+                inconsistent.extend([*first, onchain_number]);
+            }
+            Some(_) => {}
+            None => {
+                copies.insert(payload_number, (onchain_number, segment));
+            }
+        }
+    }
+
+    inconsistent.sort_unstable();
+    inconsistent.dedup();
+    inconsistent
+}
+
+/// Compares sequentially written segments against the payload. Without prefixes the on-chain
+/// order must be the payload order, so only the count and the root can be compared.
+pub fn check_sequential(
+    spool_address: &Pubkey,
+    payload: &[u8],
+    spool: &Spool,
+) -> Result<IntegrityReport> {
+    let expected = payload_segments(payload);
+    let expected_root = segment_root(spool_address, &expected)?;
+
+    Ok(IntegrityReport {
+        expected_segments: expected.len() as u64,
+        onchain_segments: spool.total_segments,
+        missing: (spool.total_segments..expected.len() as u64).collect(),
+        duplicated: Vec::new(),
+        mismatched: Vec::new(),
+        expected_root,
+        onchain_root: spool.merkle_root,
+    })
+}
+
+/// Compares prefixed segments read back from the chain (in on-chain order) against the
+/// payload. The expected tree uses the payload's copy of each segment, in the order the
+/// segments landed, so it only matches the spool if every segment on-chain is ours.
+pub fn check_prefixed(
+    spool_address: &Pubkey,
+    payload: &[u8],
+    spool: &Spool,
+    onchain: &[[u8; SEGMENT_SIZE]],
+) -> Result<IntegrityReport> {
+    let expected = payload_segments(payload);
+
+    let mut copies: HashMap<u64, u64> = HashMap::new();
+    let mut mismatched = Vec::new();
+    let mut ordered = Vec::with_capacity(onchain.len());
+
+    for (onchain_number, segment) in onchain.iter().enumerate() {
+        let prefix: [u8; 8] = segment[..8].try_into()?;
+        let payload_number = u64::from_be_bytes(prefix);
+
+        match expected.get(payload_number as usize) {
+            Some(data) if data == segment => {
+                *copies.entry(payload_number).or_default() += 1;
+                ordered.push(*data);
+            }
+            _ => {
+                mismatched.push(onchain_number as u64);
+                ordered.push(*segment);
+            }
+        }
+    }
+
+    let missing = (0..expected.len() as u64)
+        .filter(|n| !copies.contains_key(n))
+        .collect();
+
+    let mut duplicated: Vec<u64> = copies
+        .iter()
+        .filter(|(_, &count)| count > 1)
+        .map(|(&n, _)| n)
+        .collect();
+    duplicated.sort_unstable();
+
+    Ok(IntegrityReport {
+        expected_segments: expected.len() as u64,
+        onchain_segments: spool.total_segments,
+        missing,
+        duplicated,
+        mismatched,
+        expected_root: segment_root(spool_address, &ordered)?,
+        onchain_root: spool.merkle_root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use crate::prefix_segments;
+
+    fn spool_with(spool_address: &Pubkey, segments: &[[u8; SEGMENT_SIZE]]) -> Spool {
+        let mut spool = Spool::zeroed();
+        spool.total_segments = segments.len() as u64;
+        spool.merkle_root = segment_root(spool_address, segments).unwrap();
+        spool
+    }
+
+    #[test]
+    fn test_prefixed_out_of_order_is_ok() {
+        let spool_address = Pubkey::new_unique();
+        let payload = prefix_segments(&[7; 600]);
+        let mut onchain = payload_segments(&payload);
+        onchain.reverse();
+
+        let spool = spool_with(&spool_address, &onchain);
+        let report = check_prefixed(&spool_address, &payload, &spool, &onchain).unwrap();
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn test_prefixed_reports_missing_and_duplicates() {
+        let spool_address = Pubkey::new_unique();
+        let payload = prefix_segments(&[7; 600]);
+        let segments = payload_segments(&payload);
+        let onchain = vec![segments[0], segments[1], segments[1], segments[3]];
+
+        let spool = spool_with(&spool_address, &onchain);
+        let report = check_prefixed(&spool_address, &payload, &spool, &onchain).unwrap();
+        assert!(!report.is_ok());
+        assert!(!report.is_corrupt());
+        assert_eq!(report.missing, vec![2, 4]);
+        assert_eq!(report.duplicated, vec![1]);
+    }
+
+    #[test]
+    fn test_prefixed_reports_foreign_data() {
+        let spool_address = Pubkey::new_unique();
+        let payload = prefix_segments(&[7; 200]);
+        let mut onchain = payload_segments(&payload);
+        onchain[1][100] ^= 1;
+
+        let spool = spool_with(&spool_address, &onchain);
+        let report = check_prefixed(&spool_address, &payload, &spool, &onchain).unwrap();
+        assert!(report.is_corrupt());
+        assert_eq!(report.mismatched, vec![1]);
+        assert_eq!(report.missing, vec![1]);
+    }
+
+    fn read_segments(segments: &[[u8; SEGMENT_SIZE]]) -> HashMap<u64, Vec<u8>> {
+        segments
+            .iter()
+            .enumerate()
+            .map(|(n, s)| (n as u64, s.to_vec()))
+            .collect()
+    }
+
+    fn prefixed_spool(spool_address: &Pubkey, data_len: usize, segments: &[[u8; SEGMENT_SIZE]]) -> Spool {
+        let mut header = SpoolHeader::new(
+            crate::MimeType::Unknown,
+            crate::CompressionAlgo::None,
+            crate::EncryptionAlgo::None,
+            SpoolFlags::Prefixed,
+        );
+        header.data_len = data_len as u64;
+
+        let mut spool = spool_with(spool_address, segments);
+        spool.header = header.to_bytes().try_into().unwrap();
+        spool
+    }
+
+    #[test]
+    fn test_verify_read() {
+        let spool_address = Pubkey::new_unique();
+        let segments = payload_segments(&prefix_segments(&[7; 600]));
+        let spool = prefixed_spool(&spool_address, 600, &segments);
+
+        let report = verify_read(&spool_address, &spool, &read_segments(&segments)).unwrap();
+        assert!(report.is_ok(), "{report}");
+
+        let mut read = read_segments(&segments);
+        read.remove(&2);
+        read.insert(9, segments[0].to_vec());
+        let report = verify_read(&spool_address, &spool, &read).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.missing, vec![2]);
+        assert_eq!(report.unexpected, vec![9]);
+        assert_eq!(report.computed_root, None);
+    }
+
+    #[test]
+    fn test_verify_read_reports_inconsistent_segments() {
+        let spool_address = Pubkey::new_unique();
+        let payload: Vec<u8> = (0..600u32).map(|i| i as u8).collect();
+        let segments = payload_segments(&prefix_segments(&payload));
+        let spool = prefixed_spool(&spool_address, payload.len(), &segments);
+
+        // Segment 3 claims to be payload segment 1 with other data, segment 4 is past the end
+        let mut read = read_segments(&segments);
+        read.get_mut(&3).unwrap()[..8].copy_from_slice(&1u64.to_be_bytes());
+        read.get_mut(&4).unwrap()[..8].copy_from_slice(&100u64.to_be_bytes());
+
+        let report = verify_read(&spool_address, &spool, &read).unwrap();
+        assert_eq!(report.inconsistent, vec![1, 3, 4]);
+        assert!(report.missing.is_empty());
+        assert_ne!(report.computed_root, Some(report.onchain_root));
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_sequential_root() {
+        let spool_address = Pubkey::new_unique();
+        let payload = vec![9; 300];
+        let segments = payload_segments(&payload);
+
+        let spool = spool_with(&spool_address, &segments);
+        assert!(check_sequential(&spool_address, &payload, &spool).unwrap().is_ok());
+
+        let spool = spool_with(&spool_address, &segments[..2]);
+        let report = check_sequential(&spool_address, &payload, &spool).unwrap();
+        assert_eq!(report.missing, vec![2]);
+        assert!(!report.is_ok());
+        assert!(!report.is_corrupt());
+    }
+
+    #[test]
+    fn test_sequential_wrong_root_is_corrupt() {
+        let spool_address = Pubkey::new_unique();
+        let payload = vec![9; 300];
+        let segments = payload_segments(&payload);
+
+        // Every segment is there, plus one that isn't ours
+        let mut extra = segments.clone();
+        extra.push([7; SEGMENT_SIZE]);
+        let spool = spool_with(&spool_address, &extra);
+        let report = check_sequential(&spool_address, &payload, &spool).unwrap();
+        assert!(report.missing.is_empty());
+        assert!(!report.is_ok());
+        assert!(report.is_corrupt());
+
+        // Same count, but the data differs
+        let mut foreign = segments.clone();
+        foreign[1] = [7; SEGMENT_SIZE];
+        let spool = spool_with(&spool_address, &foreign);
+        let report = check_sequential(&spool_address, &payload, &spool).unwrap();
+        assert!(report.is_corrupt());
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use spool_api::prelude::*;
+
+use super::SpoolHeader;
+
+/// Version of the on-disk journal format.
+pub const JOURNAL_VERSION: u8 = 1;
+
+/// On-disk record of an upload in progress, keyed by spool address. It is enough to finish the
+/// upload after a crash, given the same encoded payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UploadJournal {
+    pub version: u8,
+    pub spool_address: String,
+    pub writer_address: String,
+    pub name: String,
+    /// Blake3 hash of the encoded payload (hex).
+    pub payload_hash: String,
+    pub payload_len: u64,
+    /// Spool header to set before finalizing.
+    pub header: Vec<u8>,
+    /// Size of each write, in bytes (a multiple of `SEGMENT_SIZE`).
+    pub chunk_size: usize,
+    /// Address lookup table the writes load the spool and writer accounts through, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lookup_table: Option<String>,
+    pub chunks: Vec<JournalChunk>,
+}
+
+/// A single write of the upload plan.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JournalChunk {
+    /// Number of segments the chunk occupies on-chain.
+    pub segments: u64,
+    /// Signature of the last write sent for this chunk.
+    pub signature: Option<String>,
+    /// Last block height at which that write can land. Journals written before it was
+    /// recorded don't have it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_valid_block_height: Option<u64>,
+    /// Whether the write is known to have landed.
+    pub confirmed: bool,
+}
+
+impl UploadJournal {
+    pub fn new(
+        spool_address: Pubkey,
+        writer_address: Pubkey,
+        name: &str,
+        header: &SpoolHeader,
+        payload: &[u8],
+        chunk_size: usize,
+    ) -> Result<Self> {
+        if chunk_size == 0 || !chunk_size.is_multiple_of(SEGMENT_SIZE) {
+            bail!("Chunk size must be a non-zero multiple of {} bytes", SEGMENT_SIZE);
+        }
+
+        let chunks = payload
+            .chunks(chunk_size)
+            .map(|c| JournalChunk {
+                segments: c.len().div_ceil(SEGMENT_SIZE) as u64,
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Self {
+            version: JOURNAL_VERSION,
+            spool_address: spool_address.to_string(),
+            writer_address: writer_address.to_string(),
+            name: name.to_string(),
+            payload_hash: payload_hash(payload),
+            payload_len: payload.len() as u64,
+            header: header.to_bytes(),
+            chunk_size,
+            lookup_table: None,
+            chunks,
+        })
+    }
+
+    /// Path of the journal for `spool_address` in `dir`.
+    pub fn path(dir: &Path, spool_address: &Pubkey) -> PathBuf {
+        dir.join(format!("{spool_address}.json"))
+    }
+
+    pub fn load(dir: &Path, spool_address: &Pubkey) -> Result<Self> {
+        let path = Self::path(dir, spool_address);
+        let data = fs::read(&path)
+            .map_err(|e| anyhow!("Failed to read upload journal {}: {}", path.display(), e))?;
+        let journal: Self = serde_json::from_slice(&data)
+            .map_err(|e| anyhow!("Failed to parse upload journal {}: {}", path.display(), e))?;
+
+        if journal.version != JOURNAL_VERSION {
+            bail!(
+                "Unsupported upload journal version: found {}, expected {}",
+                journal.version, JOURNAL_VERSION
+            );
+        }
+
+        Ok(journal)
+    }
+
+    /// Finds the journal of an unfinished upload of `payload` in `dir`, if any.
+    pub fn find_by_payload(dir: &Path, payload: &[u8]) -> Result<Option<Self>> {
+        if !dir.exists() {
+            return Ok(None);
+        }
+
+        let hash = payload_hash(payload);
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(spool_address) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| Pubkey::from_str(s).ok())
+            else {
+                continue;
+            };
+
+            let journal = Self::load(dir, &spool_address)?;
+            if journal.payload_hash == hash {
+                return Ok(Some(journal));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Writes the journal to `dir`, replacing the previous copy atomically.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let path = Self::path(dir, &self.spool_address()?);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, &path)
+            .map_err(|e| anyhow!("Failed to write upload journal {}: {}", path.display(), e))
+    }
+
+    /// Removes the journal from `dir` once the upload is done.
+    pub fn remove(&self, dir: &Path) -> Result<()> {
+        let path = Self::path(dir, &self.spool_address()?);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn spool_address(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.spool_address)
+            .map_err(|_| anyhow!("Invalid spool address in journal: {}", self.spool_address))
+    }
+
+    pub fn writer_address(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.writer_address)
+            .map_err(|_| anyhow!("Invalid writer address in journal: {}", self.writer_address))
+    }
+
+    pub fn lookup_table(&self) -> Result<Option<Pubkey>> {
+        self.lookup_table
+            .as_deref()
+            .map(|address| {
+                Pubkey::from_str(address)
+                    .map_err(|_| anyhow!("Invalid lookup table address in journal: {}", address))
+            })
+            .transpose()
+    }
+
+    pub fn header(&self) -> Result<SpoolHeader> {
+        SpoolHeader::try_from_bytes(&self.header).copied()
+    }
+
+    /// Checks that `payload` is the payload this journal was created for.
+    pub fn verify_payload(&self, payload: &[u8]) -> Result<()> {
+        if payload.len() as u64 != self.payload_len || payload_hash(payload) != self.payload_hash {
+            bail!("Payload does not match the upload journal of spool {}", self.spool_address);
+        }
+        Ok(())
+    }
+
+    /// Data of chunk `index` in `payload`.
+    pub fn chunk<'a>(&self, payload: &'a [u8], index: usize) -> &'a [u8] {
+        let start = index * self.chunk_size;
+        let end = (start + self.chunk_size).min(payload.len());
+        &payload[start..end]
+    }
+
+    /// Indices of the chunks that still need to be written.
+    pub fn pending_chunks(&self) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.confirmed)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Indices of the pending chunks without a write in flight, which are the ones to send.
+    pub fn unsent_chunks(&self) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.confirmed && c.signature.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Highest block height at which a write sent but not confirmed yet can still land, if
+    /// any is in flight.
+    pub fn in_flight_until(&self) -> Option<u64> {
+        self.chunks
+            .iter()
+            .filter(|c| !c.confirmed && c.signature.is_some())
+            .filter_map(|c| c.last_valid_block_height)
+            .max()
+    }
+
+    /// Indices and signatures of the chunks that were sent but not confirmed yet.
+    pub fn unconfirmed_signatures(&self) -> Vec<(usize, Signature)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.confirmed)
+            .filter_map(|(i, c)| {
+                let sig = c.signature.as_deref()?;
+                Some((i, Signature::from_str(sig).ok()?))
+            })
+            .collect()
+    }
+
+    /// Number of segments the journal knows to be on-chain.
+    pub fn confirmed_segments(&self) -> u64 {
+        self.chunks
+            .iter()
+            .filter(|c| c.confirmed)
+            .map(|c| c.segments)
+            .sum()
+    }
+
+    /// Number of segments of the whole payload.
+    pub fn total_segments(&self) -> u64 {
+        self.chunks.iter().map(|c| c.segments).sum()
+    }
+
+    /// Marks the chunks holding any of the `missing` payload segments as not written.
+    pub fn mark_missing(&mut self, missing: &[u64]) {
+        let mut first_segment = 0;
+        for chunk in self.chunks.iter_mut() {
+            let segments = first_segment..first_segment + chunk.segments;
+            if missing.iter().any(|n| segments.contains(n)) {
+                chunk.confirmed = false;
+                chunk.signature = None;
+                chunk.last_valid_block_height = None;
+            }
+            first_segment = segments.end;
+        }
+    }
+
+    pub fn is_written(&self) -> bool {
+        self.chunks.iter().all(|c| c.confirmed)
+    }
+}
+
+/// Blake3 hash of an encoded payload, as recorded in the journal.
+pub fn payload_hash(payload: &[u8]) -> String {
+    blake3::hash(payload).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompressionAlgo, EncryptionAlgo, MimeType, SpoolFlags};
+
+    fn journal(payload: &[u8]) -> UploadJournal {
+        let header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            SpoolFlags::Prefixed,
+        );
+        UploadJournal::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            "journal",
+            &header,
+            payload,
+            SEGMENT_SIZE * 2,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_journal_chunk_plan() {
+        let payload = vec![1; SEGMENT_SIZE * 5 + 1];
+        let journal = journal(&payload);
+
+        assert_eq!(journal.chunks.len(), 3);
+        assert_eq!(journal.total_segments(), 6);
+        assert_eq!(journal.chunk(&payload, 2).len(), SEGMENT_SIZE + 1);
+        assert_eq!(journal.pending_chunks(), vec![0, 1, 2]);
+        assert!(journal.verify_payload(&payload).is_ok());
+        assert!(journal.verify_payload(&payload[1..]).is_err());
+    }
+
+    #[test]
+    fn test_journal_mark_missing() {
+        let payload = vec![1; SEGMENT_SIZE * 5 + 1];
+        let mut journal = journal(&payload);
+        for chunk in journal.chunks.iter_mut() {
+            chunk.confirmed = true;
+        }
+
+        journal.mark_missing(&[3, 5]);
+        assert_eq!(journal.pending_chunks(), vec![1, 2]);
+        assert_eq!(journal.confirmed_segments(), 2);
+    }
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("spool-journal-{}", Pubkey::new_unique()));
+        let payload = vec![2; SEGMENT_SIZE * 3];
+
+        let mut journal = journal(&payload);
+        journal.chunks[0].confirmed = true;
+        journal.chunks[1].signature = Some(Signature::default().to_string());
+        journal.chunks[1].last_valid_block_height = Some(150);
+        journal.save(&dir).unwrap();
+
+        let spool_address = journal.spool_address().unwrap();
+        let loaded = UploadJournal::load(&dir, &spool_address).unwrap();
+        assert_eq!(loaded.confirmed_segments(), 2);
+        assert_eq!(loaded.pending_chunks(), vec![1]);
+        assert!(loaded.unsent_chunks().is_empty());
+        assert_eq!(loaded.in_flight_until(), Some(150));
+        assert_eq!(loaded.unconfirmed_signatures(), vec![(1, Signature::default())]);
+        assert_eq!(loaded.header().unwrap(), journal.header().unwrap());
+
+        let found = UploadJournal::find_by_payload(&dir, &payload).unwrap().unwrap();
+        assert_eq!(found.spool_address, journal.spool_address);
+        assert!(UploadJournal::find_by_payload(&dir, &payload[1..]).unwrap().is_none());
+
+        journal.remove(&dir).unwrap();
+        assert!(UploadJournal::load(&dir, &spool_address).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
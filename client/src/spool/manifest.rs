@@ -0,0 +1,334 @@
+use std::io::{self, Cursor, Read};
+
+use anyhow::{bail, Result};
+use solana_sdk::pubkey::Pubkey;
+use spool_api::prelude::*;
+
+use super::{recover_segments, ErasureParams, SpoolFlags, SpoolHeader, UnprefixReader, PREFIX_LEN};
+
+/// A 4-byte "magic" prefix to identify the manifest format.
+pub const MANIFEST_MAGIC: [u8; 4] = *b"SPMF";
+
+/// The version of the manifest format.
+pub const MANIFEST_VERSION: u8 = 1;
+
+/// Largest payload a single prefixed spool can hold, in bytes. Larger payloads are split into
+/// parts of this size (smaller for erasure-coded payloads, see `max_payload_len`).
+pub const MAX_SPOOL_PAYLOAD_LEN: usize = MAX_SEGMENTS_PER_SPOOL * (SEGMENT_SIZE - PREFIX_LEN);
+
+/// Magic, version, 3 reserved bytes, part count and payload length.
+const MANIFEST_HEADER_LEN: usize = 20;
+
+/// Part spool, length and blake3 hash of the part.
+const PART_LEN: usize = 32 + 8 + 32;
+
+/// Most parts a manifest can list and still fit in a single spool.
+const MAX_MANIFEST_PARTS: usize = (MAX_SPOOL_PAYLOAD_LEN - MANIFEST_HEADER_LEN) / PART_LEN;
+
+/// The part spools of a payload too large for a single spool.
+///
+/// The encoded payload (compressed, encrypted and with its key envelope, as it would be stored in
+/// a single spool) is split into parts of at most `MAX_SPOOL_PAYLOAD_LEN` bytes, each uploaded
+/// as a plain spool of its own. The manifest is stored in a spool with the `Manifest` flag whose
+/// header otherwise describes the payload, so readers fetch the parts, check them against their
+/// hashes and decode the stitched payload as usual.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub payload_len: u64,
+    pub parts: Vec<ManifestPart>,
+}
+
+/// A part of the payload of a manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestPart {
+    pub address: Pubkey,
+    pub len: u64,
+    pub hash: [u8; 32],
+}
+
+impl ManifestPart {
+    pub fn new(address: Pubkey, data: &[u8]) -> Self {
+        Self {
+            address,
+            len: data.len() as u64,
+            hash: *blake3::hash(data).as_bytes(),
+        }
+    }
+
+    /// Checks decoded part data against the length and hash of the part.
+    pub fn check(&self, data: &[u8]) -> Result<()> {
+        if data.len() as u64 != self.len {
+            bail!("Part spool {} has {} bytes, expected {}", self.address, data.len(), self.len);
+        }
+        if *blake3::hash(data).as_bytes() != self.hash {
+            bail!("Part spool {} doesn't match its hash in the manifest", self.address);
+        }
+        Ok(())
+    }
+}
+
+impl Manifest {
+    pub fn new(parts: Vec<ManifestPart>) -> Self {
+        Self {
+            payload_len: parts.iter().map(|part| part.len).sum(),
+            parts,
+        }
+    }
+
+    /// Serialized length, in bytes.
+    pub fn len(&self) -> usize {
+        MANIFEST_HEADER_LEN + self.parts.len() * PART_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.len());
+        output.extend_from_slice(&MANIFEST_MAGIC);
+        output.push(MANIFEST_VERSION);
+        output.extend_from_slice(&[0; 3]);
+        output.extend_from_slice(&(self.parts.len() as u32).to_le_bytes());
+        output.extend_from_slice(&self.payload_len.to_le_bytes());
+
+        for part in &self.parts {
+            output.extend_from_slice(&part.address.to_bytes());
+            output.extend_from_slice(&part.len.to_le_bytes());
+            output.extend_from_slice(&part.hash);
+        }
+
+        output
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < MANIFEST_HEADER_LEN {
+            bail!("Invalid manifest: data too short");
+        }
+
+        let count = parse_part_count(&data[..MANIFEST_HEADER_LEN])?;
+        let payload_len = u64::from_le_bytes(data[12..20].try_into().unwrap());
+        let end = MANIFEST_HEADER_LEN + count * PART_LEN;
+        if data.len() < end {
+            bail!("Invalid manifest: data too short");
+        }
+
+        let parts: Vec<ManifestPart> = data[MANIFEST_HEADER_LEN..end]
+            .chunks_exact(PART_LEN)
+            .map(|chunk| ManifestPart {
+                address: Pubkey::new_from_array(chunk[0..32].try_into().unwrap()),
+                len: u64::from_le_bytes(chunk[32..40].try_into().unwrap()),
+                hash: chunk[40..72].try_into().unwrap(),
+            })
+            .collect();
+
+        if parts.iter().map(|part| part.len).sum::<u64>() != payload_len {
+            bail!("Invalid manifest: part lengths don't add up to the payload length");
+        }
+
+        Ok(Self { payload_len, parts })
+    }
+
+    /// Reads the manifest at the start of `reader`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut data = vec![0u8; MANIFEST_HEADER_LEN];
+        read_manifest_bytes(reader, &mut data)?;
+
+        // The count is checked before it sizes the buffer
+        let count = parse_part_count(&data)?;
+        data.resize(MANIFEST_HEADER_LEN + count * PART_LEN, 0);
+        read_manifest_bytes(reader, &mut data[MANIFEST_HEADER_LEN..])?;
+
+        Self::parse(&data)
+    }
+
+    /// The header to decode the stitched payload with, from the header of the manifest spool.
+    pub fn payload_header(&self, header: &SpoolHeader) -> SpoolHeader {
+        let mut header = *header;
+        header.flags &= !(SpoolFlags::Manifest as u8 | SpoolFlags::Prefixed as u8);
+        header.set_erasure(None);
+        header.data_len = self.payload_len;
+        header
+    }
+
+    /// Stitches the decoded data of the parts, in manifest order, back into the payload. Each
+    /// part is checked against its length and hash.
+    pub fn stitch(&self, parts: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+        if parts.len() != self.parts.len() {
+            bail!("Manifest has {} parts, got {}", self.parts.len(), parts.len());
+        }
+
+        let mut payload = Vec::with_capacity(self.payload_len as usize);
+        for (part, data) in self.parts.iter().zip(parts) {
+            part.check(&data)?;
+            payload.extend(data);
+        }
+
+        Ok(payload)
+    }
+}
+
+/// Checks the magic and version of a manifest header and returns its part count.
+fn parse_part_count(header: &[u8]) -> Result<usize> {
+    if header[0..4] != MANIFEST_MAGIC {
+        bail!("Invalid manifest: invalid magic number");
+    }
+    if header[4] != MANIFEST_VERSION {
+        bail!("Invalid manifest: unsupported version {}", header[4]);
+    }
+
+    let count = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+    if count > MAX_MANIFEST_PARTS {
+        bail!("Invalid manifest: {count} parts, at most {MAX_MANIFEST_PARTS} fit in a spool");
+    }
+
+    Ok(count)
+}
+
+fn read_manifest_bytes<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => anyhow::anyhow!("Invalid manifest: data too short"),
+        _ => anyhow::Error::from(e),
+    })
+}
+
+/// Parses the manifest in the raw data of a manifest spool.
+pub fn read_manifest(data: Vec<u8>, header: &SpoolHeader) -> Result<Manifest> {
+    if !header.has_flag(SpoolFlags::Manifest) {
+        bail!("The spool is not a manifest");
+    }
+
+    if header.has_flag(SpoolFlags::ErasureCoded) {
+        let recovery = recover_segments(&data, header)?;
+        Manifest::read_from(&mut UnprefixReader::new(Cursor::new(recovery.data), header.data_len))
+    } else if header.has_flag(SpoolFlags::Prefixed) {
+        Manifest::read_from(&mut UnprefixReader::new(Cursor::new(data), header.data_len))
+    } else {
+        Manifest::read_from(&mut Cursor::new(data))
+    }
+}
+
+/// Largest payload a single prefixed spool can hold with `erasure` coding, in bytes.
+pub fn max_payload_len(erasure: Option<ErasureParams>) -> usize {
+    match erasure {
+        Some(params) => {
+            params.max_payload_segments(MAX_SEGMENTS_PER_SPOOL) * (SEGMENT_SIZE - PREFIX_LEN)
+        }
+        None => MAX_SPOOL_PAYLOAD_LEN,
+    }
+}
+
+/// Whether an encoded payload of `payload_len` bytes (before prefixing) must be split into parts.
+pub fn needs_manifest(payload_len: usize, erasure: Option<ErasureParams>) -> bool {
+    payload_len > max_payload_len(erasure)
+}
+
+/// Name of the spool of part `index` of the payload uploaded as `name`: derived from both so
+/// uploading the same name again finds the same parts.
+pub fn manifest_part_name(name: &str, index: usize) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(name.as_bytes());
+    hasher.update(&(index as u64).to_le_bytes());
+    hasher.finalize().to_hex()[..NAME_LEN].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_spool, encode_manifest_spool, encode_spool, CompressionAlgo, EncryptionAlgo,
+        MimeType,
+    };
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let payload: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let parts: Vec<&[u8]> = payload.chunks(300).collect();
+
+        let manifest = Manifest::new(
+            parts.iter().map(|part| ManifestPart::new(Pubkey::new_unique(), part)).collect(),
+        );
+        assert_eq!(manifest.payload_len, 1000);
+        assert_eq!(Manifest::parse(&manifest.to_bytes()).unwrap(), manifest);
+
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::None,
+            SpoolFlags::Prefixed,
+        );
+        let encoded = encode_manifest_spool(&manifest, &mut header).unwrap();
+        assert!(header.has_flag(SpoolFlags::Manifest));
+        assert!(decode_spool(encoded.clone(), &header).is_err());
+
+        let parsed = read_manifest(encoded, &header).unwrap();
+        assert_eq!(parsed, manifest);
+
+        let payload_header = parsed.payload_header(&header);
+        assert!(!payload_header.has_flag(SpoolFlags::Manifest));
+        assert!(!payload_header.has_flag(SpoolFlags::Prefixed));
+        assert_eq!(payload_header.data_len, 1000);
+
+        let stitched = parsed.stitch(parts.iter().map(|part| part.to_vec()).collect()).unwrap();
+        assert_eq!(stitched, payload);
+
+        let mut tampered: Vec<Vec<u8>> = parts.iter().map(|part| part.to_vec()).collect();
+        tampered[1][0] ^= 1;
+        assert!(parsed.stitch(tampered).is_err());
+        assert!(parsed.stitch(vec![payload.clone()]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_part_count() {
+        let manifest = Manifest::new(vec![ManifestPart::new(Pubkey::new_unique(), b"part")]);
+        let mut data = manifest.to_bytes();
+        data[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let err = Manifest::read_from(&mut Cursor::new(data.clone())).unwrap_err();
+        assert!(err.to_string().contains("parts"));
+        assert!(Manifest::parse(&data).is_err());
+
+        // The magic is checked before the count is trusted
+        data[0] = 0;
+        let err = Manifest::read_from(&mut Cursor::new(data)).unwrap_err();
+        assert!(err.to_string().contains("magic"));
+    }
+
+    #[test]
+    fn test_stitched_payload_decodes() {
+        let data = b"a payload split across spools ".repeat(100);
+        let mut header = SpoolHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::Zstd,
+            EncryptionAlgo::None,
+            SpoolFlags::None,
+        );
+        let encoded = encode_spool(&data, &mut header).unwrap();
+
+        let parts: Vec<Vec<u8>> = encoded.chunks(100).map(<[u8]>::to_vec).collect();
+        let manifest = Manifest::new(
+            parts.iter().map(|part| ManifestPart::new(Pubkey::new_unique(), part)).collect(),
+        );
+
+        let mut manifest_header = header;
+        let _ = encode_manifest_spool(&manifest, &mut manifest_header).unwrap();
+
+        let payload = manifest.stitch(parts).unwrap();
+        let decoded = decode_spool(payload, &manifest.payload_header(&manifest_header)).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_part_names() {
+        let name = manifest_part_name("1700000000", 0);
+        assert_eq!(name.len(), NAME_LEN);
+        assert_eq!(name, manifest_part_name("1700000000", 0));
+        assert_ne!(name, manifest_part_name("1700000000", 1));
+        assert!(!needs_manifest(MAX_SPOOL_PAYLOAD_LEN, None));
+        assert!(needs_manifest(MAX_SPOOL_PAYLOAD_LEN + 1, None));
+
+        let erasure = Some(ErasureParams::new(16, 4).unwrap());
+        assert!(max_payload_len(erasure) < MAX_SPOOL_PAYLOAD_LEN);
+        assert!(needs_manifest(MAX_SPOOL_PAYLOAD_LEN, erasure));
+    }
+}
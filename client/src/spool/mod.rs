@@ -1,16 +1,38 @@
 mod header;
 mod encoding;
+mod stream;
+mod erasure;
+mod encryption;
+mod envelope;
+mod manifest;
+mod directory;
 mod create;
 mod read;
+mod history;
 mod write;
 mod finalize;
 mod subsidize;
+mod ownership;
+mod journal;
+mod bundle;
+mod integrity;
 
 pub use header::*;
 pub use encoding::*;
+pub use stream::*;
+pub use erasure::*;
+pub use encryption::*;
+pub use envelope::*;
+pub use manifest::*;
+pub use directory::*;
 pub use create::*;
 pub use read::*;
+pub use history::*;
 pub use write::*;
 pub use finalize::*;
 pub use subsidize::*;
+pub use ownership::*;
+pub use journal::*;
+pub use bundle::*;
+pub use integrity::*;
 
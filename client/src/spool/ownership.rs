@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use solana_sdk::{
+    signature::{Keypair, Signature},
+    pubkey::Pubkey,
+};
+
+use crate::{RpcBackend, SpoolClient};
+
+/// Mints the ownership token for a spool to the authority and returns the mint address and
+/// signature. After this, the spool authority follows whoever holds the token.
+pub async fn mint_ownership_token(
+    client: &Arc<impl RpcBackend + 'static>,
+    payer: &Keypair,
+    authority: &Keypair,
+    spool_address: Pubkey,
+) -> Result<(Pubkey, Signature)> {
+    SpoolClient::preset(client.clone(), payer.insecure_clone())
+        .mint_ownership_token_with_authority(authority, spool_address)
+        .await
+}
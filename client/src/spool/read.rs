@@ -1,22 +1,104 @@
 use anyhow::{Result, anyhow};
-use log::debug;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use log::{debug, warn};
+use reqwest::Client as HttpClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
 use solana_transaction_status_client_types::TransactionDetails;
-use std::{collections::{BinaryHeap, HashMap, HashSet}, sync::Arc};
+use std::{collections::{BinaryHeap, HashMap, HashSet}, io::{self, Read}, pin::Pin, str::FromStr, sync::Arc, task::{Context, Poll}};
+use tokio::{io::{AsyncRead, ReadBuf}, sync::Semaphore, task::JoinSet};
 use spool_api::prelude::*;
+use crate::backend::RpcBackend;
+use crate::consts::*;
 use crate::utils::*;
+use super::{verify_read, SpoolHistory};
 
 pub struct ReadState {
     pub segments: HashMap<u64, Vec<u8>>,
     pub visited: HashSet<u64>,
     pub queue: BinaryHeap<u64>,
+    /// The versions of the segments read from the chain.
+    pub history: SpoolHistory,
+    /// Read the spool as of the end of this slot instead of its latest state.
+    pub until_slot: Option<u64>,
 }
 
 impl ReadState {
     pub fn segments_len(&self) -> usize {
         self.segments.len()
     }
+
+    /// Records a segment version read from the chain and updates `segments` with the version of
+    /// the segment as of `until_slot`. Segments put in `segments` by other sources (archive
+    /// nodes) are kept.
+    pub fn record(&mut self, version: SegmentVersion) {
+        let segment_number = version.segment_number;
+        if self.segments.contains_key(&segment_number) && self.history.versions(segment_number).is_empty() {
+            return;
+        }
+
+        self.history.record(version);
+
+        if let Some(version) = self.history.segment_at(segment_number, self.until_slot) {
+            self.segments.insert(segment_number, version.data.clone());
+        }
+    }
+
+    /// Whether segments `0..total_segments` have all been collected.
+    pub fn has_segments(&self, total_segments: u64) -> bool {
+        self.segments.len() as u64 >= total_segments
+            && (0..total_segments).all(|n| self.segments.contains_key(&n))
+    }
+
+    /// Reads the collected segments in order, padded as by `finalize_read`, dropping each one
+    /// once it has been read. Feed it to a `SpoolDecoder` to decode without concatenating them.
+    pub fn into_reader(self) -> SegmentsReader {
+        let mut keys: Vec<u64> = self.segments.keys().cloned().collect();
+        keys.sort();
+
+        SegmentsReader {
+            segments: self.segments,
+            keys: keys.into_iter(),
+            current: [0; SEGMENT_SIZE],
+            pos: SEGMENT_SIZE,
+        }
+    }
+}
+
+/// Reader over the segments of a `ReadState`, see `ReadState::into_reader`.
+pub struct SegmentsReader {
+    segments: HashMap<u64, Vec<u8>>,
+    keys: std::vec::IntoIter<u64>,
+    current: [u8; SEGMENT_SIZE],
+    pos: usize,
+}
+
+impl Read for SegmentsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == SEGMENT_SIZE {
+            let Some(segment) = self.keys.next().and_then(|key| self.segments.remove(&key)) else {
+                return Ok(0);
+            };
+            self.current = padded_array::<SEGMENT_SIZE>(&segment);
+            self.pos = 0;
+        }
+
+        let n = buf.len().min(SEGMENT_SIZE - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// The segments are already in memory, so reads never wait.
+impl AsyncRead for SegmentsReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = self.get_mut().read(buf.initialize_unfilled())?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
 }
 
 pub fn init_read(start_slot: u64) -> ReadState {
@@ -26,11 +108,22 @@ pub fn init_read(start_slot: u64) -> ReadState {
         segments: HashMap::new(),
         visited: HashSet::new(),
         queue,
+        history: SpoolHistory::new(),
+        until_slot: None,
+    }
+}
+
+/// Like `init_read`, for reading the spool as of the end of `until_slot`. Blocks after it are
+/// still walked to follow the write chain back, but their writes are only kept in the history.
+pub fn init_read_until(start_slot: u64, until_slot: u64) -> ReadState {
+    ReadState {
+        until_slot: Some(until_slot),
+        ..init_read(start_slot)
     }
 }
 
 pub async fn process_next_block(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     spool_address: &Pubkey,
     state: &mut ReadState,
 ) -> Result<bool> {
@@ -46,25 +139,20 @@ pub async fn process_next_block(
 
         let mut parents: HashSet<u64> = HashSet::new();
 
-        for (key, data) in &processed.segment_writes {
-            if key.address != *spool_address {
+        for version in processed.segment_versions {
+            if version.address != *spool_address {
                 continue;
             }
 
-            // TODO: Check if this works for updates (if we go to a previous slot it might
-            // overwrite)
-
-            state.segments
-                .entry(key.segment_number)
-                .or_insert(data.clone());
-
-            if key.prev_slot != 0 {
-                if key.prev_slot > current_slot {
+            if version.prev_slot != 0 {
+                if version.prev_slot > current_slot {
                     return Err(anyhow!("Parent slot must be earlier than current"));
                 }
 
-                parents.insert(key.prev_slot);
+                parents.insert(version.prev_slot);
             }
+
+            state.record(version);
         }
 
         for parent in parents {
@@ -78,21 +166,256 @@ pub async fn process_next_block(
 }
 
 pub fn finalize_read(state: ReadState) -> Result<Vec<u8>> {
-    // Sort segments by key and concatenate data
-    let mut keys: Vec<u64> = state.segments.keys().cloned().collect();
-    keys.sort();
+    let mut output = Vec::with_capacity(state.segments.len() * SEGMENT_SIZE);
+    state.into_reader().read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Collects segments `0..total_segments` of a spool from archive nodes, trying the nodes in
+/// order for each segment. Segments no node serves are left out of `state`.
+pub async fn fetch_archive_segments(
+    http: &HttpClient,
+    nodes: &[ArchiveNode],
+    spool_address: &Pubkey,
+    total_segments: u64,
+    state: &mut ReadState,
+    on_progress: impl Fn(usize),
+) {
+    let nodes = Arc::new(nodes.to_vec());
+    let semaphore = Arc::new(Semaphore::new(ARCHIVE_CONCURRENCY));
+    let mut tasks = JoinSet::new();
 
-    let mut output = Vec::new();
-    for key in keys {
-        let segment = padded_array::<SEGMENT_SIZE>(&state.segments[&key]);
-        output.extend_from_slice(&segment);
+    for segment_number in (0..total_segments).filter(|n| !state.segments.contains_key(n)) {
+        let http = http.clone();
+        let nodes = nodes.clone();
+        let semaphore = semaphore.clone();
+        let spool_address = *spool_address;
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok()?;
+            for node in nodes.iter() {
+                match get_archive_segment(&http, node, &spool_address, segment_number).await {
+                    Ok(Some(segment)) => return Some((segment_number, segment)),
+                    Ok(None) => {}
+                    Err(e) => debug!("Archive node {} failed to serve segment {}: {}", node, segment_number, e),
+                }
+            }
+            None
+        });
     }
 
-    Ok(output)
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(Some((segment_number, segment))) = result {
+            state.segments.insert(segment_number, segment.to_vec());
+            on_progress(state.segments_len());
+        }
+    }
+}
+
+/// How reads find the segments of a spool on-chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadStrategy {
+    /// Walk back from the tail slot of the spool through the `prev_slot` links of its writes,
+    /// one block at a time.
+    #[default]
+    Blocks,
+    /// List the transactions that touched the spool with `getSignaturesForAddress` and fetch
+    /// them in parallel. Doesn't depend on the `prev_slot` chain, but needs an RPC node that
+    /// keeps the transaction history of the spool.
+    Signatures,
+}
+
+impl FromStr for ReadStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "blocks" => Ok(ReadStrategy::Blocks),
+            "signatures" => Ok(ReadStrategy::Signatures),
+            _ => Err(anyhow!("Unknown read strategy: {} (expected blocks or signatures)", s)),
+        }
+    }
+}
+
+/// Lists the signatures of the successful transactions that touched `address`, newest first.
+pub async fn get_spool_signatures(client: &impl RpcBackend, address: &Pubkey) -> Result<Vec<Signature>> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    loop {
+        let page = get_signatures_for_address(client, address, before, None, Some(SIGNATURE_PAGE_SIZE)).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        let is_last_page = page.len() < SIGNATURE_PAGE_SIZE;
+
+        for status in page.into_iter().filter(|status| status.err.is_none()) {
+            signatures.push(Signature::from_str(&status.signature)?);
+        }
+
+        if is_last_page {
+            break;
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Collects the segments of a spool from the transactions that touched it (see
+/// `ReadStrategy::Signatures`). Segments already in `state` are kept.
+pub async fn read_spool_from_signatures<R: RpcBackend + ?Sized + 'static>(
+    client: &Arc<R>,
+    spool_address: &Pubkey,
+    state: &mut ReadState,
+    on_progress: impl Fn(usize),
+) -> Result<()> {
+    let signatures = get_spool_signatures(client, spool_address).await?;
+    debug!("Found {} transactions for spool {}", signatures.len(), spool_address);
+
+    let semaphore = Arc::new(Semaphore::new(TRANSACTION_FETCH_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    let count = signatures.len();
+
+    for (index, signature) in signatures.into_iter().enumerate() {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+
+        // Signatures come newest first, so within a slot the later ones executed earlier
+        let tx_index = count - 1 - index;
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let tx = get_transaction_with_meta(&client, &signature).await?;
+            Ok::<_, anyhow::Error>(process_single_transaction(&tx.transaction, tx.slot, tx_index)?)
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        let processed = result??;
+
+        for version in processed.segment_versions {
+            if version.address == *spool_address {
+                state.record(version);
+            }
+        }
+
+        on_progress(state.segments_len());
+    }
+
+    Ok(())
+}
+
+/// Collects the segments of a spool, from archive nodes first. The chain is only read with
+/// `strategy` for the segments no node serves; the block walk stops once every segment is
+/// collected. If the segment tree rebuilt with archive data doesn't have the on-chain root, the
+/// archive data is dropped and the whole spool is read from the chain.
+pub async fn collect_spool_segments<R: RpcBackend + ?Sized + 'static>(
+    client: &Arc<R>,
+    http: &HttpClient,
+    nodes: &[ArchiveNode],
+    strategy: ReadStrategy,
+    spool_address: &Pubkey,
+    spool: &Spool,
+    on_progress: impl Fn(usize),
+) -> Result<ReadState> {
+    let mut state = init_read(spool.tail_slot);
+
+    if !nodes.is_empty() {
+        fetch_archive_segments(http, nodes, spool_address, spool.total_segments, &mut state, &on_progress).await;
+        debug!("Archive nodes served {}/{} segments", state.segments_len(), spool.total_segments);
+    }
+    let from_archives = state.segments_len();
+
+    collect_onchain_segments(client, strategy, spool_address, spool, &mut state, &on_progress).await?;
+
+    if from_archives > 0 {
+        let report = verify_read(spool_address, spool, &state.segments)?;
+        if report.missing.is_empty() && report.computed_root != Some(report.onchain_root) {
+            warn!(
+                "Segments from archive nodes don't match the merkle root of spool {}, reading it from the chain",
+                spool_address
+            );
+
+            state = init_read(spool.tail_slot);
+            collect_onchain_segments(client, strategy, spool_address, spool, &mut state, &on_progress).await?;
+        }
+    }
+
+    Ok(state)
+}
+
+/// Reads the segments missing from `state` from the chain with `strategy`.
+async fn collect_onchain_segments<R: RpcBackend + ?Sized + 'static>(
+    client: &Arc<R>,
+    strategy: ReadStrategy,
+    spool_address: &Pubkey,
+    spool: &Spool,
+    state: &mut ReadState,
+    on_progress: impl Fn(usize),
+) -> Result<()> {
+    if state.has_segments(spool.total_segments) {
+        return Ok(());
+    }
+
+    match strategy {
+        ReadStrategy::Blocks => {
+            while !state.has_segments(spool.total_segments)
+                && process_next_block(client, spool_address, state).await?
+            {
+                on_progress(state.segments_len());
+            }
+        }
+        ReadStrategy::Signatures => {
+            read_spool_from_signatures(client, spool_address, state, on_progress).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the segments of a spool as they were at the end of `slot`, from the chain only
+/// (archive nodes serve the latest segments). Segments that didn't exist yet are left out.
+pub async fn collect_spool_segments_at<R: RpcBackend + ?Sized + 'static>(
+    client: &Arc<R>,
+    strategy: ReadStrategy,
+    spool_address: &Pubkey,
+    spool: &Spool,
+    slot: u64,
+    on_progress: impl Fn(usize),
+) -> Result<ReadState> {
+    let mut state = init_read_until(spool.tail_slot, slot);
+    collect_onchain_segments(client, strategy, spool_address, spool, &mut state, on_progress).await?;
+    Ok(state)
+}
+
+/// Reads every version of the segments of a spool from the chain.
+pub async fn read_spool_history<R: RpcBackend + ?Sized + 'static>(
+    client: &Arc<R>,
+    strategy: ReadStrategy,
+    spool_address: &Pubkey,
+    spool: &Spool,
+    on_progress: impl Fn(usize),
+) -> Result<SpoolHistory> {
+    let mut state = init_read(spool.tail_slot);
+
+    match strategy {
+        ReadStrategy::Blocks => {
+            while process_next_block(client, spool_address, &mut state).await? {
+                on_progress(state.history.len());
+            }
+        }
+        ReadStrategy::Signatures => {
+            read_spool_from_signatures(client, spool_address, &mut state, |_| {}).await?;
+            on_progress(state.history.len());
+        }
+    }
+
+    Ok(state.history)
 }
 
 pub async fn get_spool_segments_starting_at(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     spool_address: &Pubkey,
     slot: u64,
 ) -> Result<Vec<u8>> {
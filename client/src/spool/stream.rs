@@ -0,0 +1,752 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Cursor, Read, Write},
+};
+
+use anyhow::{anyhow, bail, Result};
+use log::debug;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use spool_api::prelude::*;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+    task,
+};
+use tokio_util::io::SyncIoBridge;
+
+use crate::utils::*;
+use super::{
+    CompressionAlgo, EncryptionError, Envelope, ErasureParams, MimeType, SpoolFlags, SpoolHeader,
+    SpoolKey, StripeEncoder, decrypt, encrypt, ensure_nonce, missing_key, recover_segments,
+    seal_for_header,
+};
+
+/// Length of the index that prefixes each segment of a prefixed spool.
+pub(crate) const PREFIX_LEN: usize = 8;
+
+/// Size of the reads and writes of the async helpers.
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Decoded chunks `decode_spool_async` buffers ahead of its writer.
+const DECODE_CHANNEL_CAPACITY: usize = 4;
+
+/// Splits the payload written to it into segments, prefixing each with its index when
+/// `prefixed` (see `prefix_segments`). Only the segment being filled is buffered, plus the
+/// parity segments of erasure-coded payloads, which are written by `finish`.
+pub struct SegmentWriter<W: Write> {
+    inner: W,
+    prefixed: bool,
+    segment: [u8; SEGMENT_SIZE],
+    len: usize,
+    index: u64,
+    data_len: u64,
+    erasure: Option<StripeEncoder>,
+}
+
+impl<W: Write> SegmentWriter<W> {
+    pub fn new(inner: W, prefixed: bool) -> Self {
+        Self {
+            inner,
+            prefixed,
+            segment: [0; SEGMENT_SIZE],
+            len: PREFIX_LEN,
+            index: 0,
+            data_len: 0,
+            erasure: None,
+        }
+    }
+
+    /// Writes prefixed segments followed by their parity segments (see `ErasureParams`). Every
+    /// segment is written whole, the last payload segment padded with zeros.
+    pub fn with_erasure(inner: W, params: ErasureParams) -> Result<Self> {
+        Ok(Self {
+            erasure: Some(StripeEncoder::new(params)?),
+            ..Self::new(inner, true)
+        })
+    }
+
+    /// The writer for the segments of a spool with `header`.
+    pub fn for_header(inner: W, header: &SpoolHeader) -> Result<Self> {
+        if !header.has_flag(SpoolFlags::ErasureCoded) {
+            return Ok(Self::new(inner, header.has_flag(SpoolFlags::Prefixed)));
+        }
+
+        match header.erasure() {
+            Some(params) if header.has_flag(SpoolFlags::Prefixed) => Self::with_erasure(inner, params),
+            Some(_) => bail!("Erasure-coded spools must be prefixed"),
+            None => bail!("Invalid erasure coding parameters in the header"),
+        }
+    }
+
+    /// Bytes of payload written so far, the `data_len` of the header.
+    pub fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes the last, partial segment (and the parity segments, if any) and returns the inner
+    /// writer and the payload length.
+    pub fn finish(mut self) -> io::Result<(W, u64)> {
+        self.write_segment()?;
+
+        if let Some(erasure) = self.erasure.take() {
+            for parity in erasure.finish() {
+                self.inner.write_all(&self.index.to_be_bytes())?;
+                self.inner.write_all(&parity)?;
+                self.index += 1;
+            }
+        }
+        Ok((self.inner, self.data_len))
+    }
+
+    fn write_segment(&mut self) -> io::Result<()> {
+        if self.len > PREFIX_LEN {
+            self.segment[..PREFIX_LEN].copy_from_slice(&self.index.to_be_bytes());
+
+            match &mut self.erasure {
+                Some(erasure) => {
+                    self.segment[self.len..].fill(0);
+                    erasure.push(&self.segment[PREFIX_LEN..]);
+                    self.inner.write_all(&self.segment)?;
+                }
+                None => self.inner.write_all(&self.segment[..self.len])?,
+            }
+
+            self.index += 1;
+            self.len = PREFIX_LEN;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SegmentWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.prefixed {
+            let n = self.inner.write(buf)?;
+            self.data_len += n as u64;
+            return Ok(n);
+        }
+
+        let n = buf.len().min(SEGMENT_SIZE - self.len);
+        self.segment[self.len..self.len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        self.data_len += n as u64;
+
+        if self.len == SEGMENT_SIZE {
+            self.write_segment()?;
+        }
+        Ok(n)
+    }
+
+    /// Flushes the inner writer; a partial segment stays buffered until `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the payload of prefixed segments (see `unprefix_segments`) in index order, truncated to
+/// `data_len`. Segments may come in any order: those ahead of the next index are held until it
+/// shows up, and repeated indexes are skipped, assuming they are identical.
+pub struct UnprefixReader<R: Read> {
+    inner: R,
+    remaining: u64,
+    next: u64,
+    pending: BTreeMap<u64, Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> UnprefixReader<R> {
+    pub fn new(inner: R, data_len: u64) -> Self {
+        Self {
+            inner,
+            remaining: data_len,
+            next: 0,
+            pending: BTreeMap::new(),
+            current: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Payload of the next segment in index order, or `None` once the input is exhausted.
+    fn next_segment(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(data) = self.pending.remove(&self.next) {
+                self.next += 1;
+                return Ok(Some(data));
+            }
+
+            let mut segment = Vec::with_capacity(SEGMENT_SIZE);
+            (&mut self.inner).take(SEGMENT_SIZE as u64).read_to_end(&mut segment)?;
+
+            if segment.is_empty() {
+                return match self.pending.keys().next() {
+                    None => Ok(None),
+                    Some(_) if self.next == 0 => Err(invalid_data("Segments do not start from 0")),
+                    Some(index) => {
+                        debug!("Segment {} is not consecutive with segment {}", self.next - 1, index);
+                        Err(invalid_data("Non-consecutive segments detected"))
+                    }
+                };
+            }
+            if segment.len() < PREFIX_LEN {
+                return Err(invalid_data("Invalid segment size: too small"));
+            }
+
+            let index = u64::from_be_bytes(segment[..PREFIX_LEN].try_into().unwrap());
+            segment.drain(..PREFIX_LEN);
+
+            if index == self.next {
+                self.next += 1;
+                return Ok(Some(segment));
+            }
+            if index > self.next {
+                self.pending.entry(index).or_insert(segment);
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for UnprefixReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            match self.next_segment()? {
+                Some(segment) => {
+                    self.current = segment;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf
+            .len()
+            .min(self.current.len() - self.pos)
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Streaming counterpart of `encode_spool_with_key`: compresses the data written to it and
+/// writes the encoded segments to `W` as they fill up. Call `finish` to get the header to set,
+/// with its `data_len`.
+///
+/// Encrypted payloads are buffered after compression, since the authentication tag covers the
+/// whole payload. `CompressionAlgo::Auto` can't compare codecs on a stream, so it picks zstd, or
+/// no compression for already-compressed MIME types.
+pub struct SpoolEncoder<W: Write> {
+    compressor: Compressor<EncoderSink<W>>,
+    header: SpoolHeader,
+}
+
+enum EncoderSink<W: Write> {
+    Segments(SegmentWriter<W>),
+    Encrypt {
+        buffer: Vec<u8>,
+        segments: SegmentWriter<W>,
+        key: SpoolKey,
+        envelope: Option<Envelope>,
+    },
+}
+
+impl<W: Write> SpoolEncoder<W> {
+    pub fn new(writer: W, header: SpoolHeader) -> Result<Self> {
+        Self::with_key(writer, header, None)
+    }
+
+    /// Encodes with `key`, which is required when the header sets an encryption algorithm.
+    pub fn with_key(writer: W, mut header: SpoolHeader, key: Option<&SpoolKey>) -> Result<Self> {
+        let segments = SegmentWriter::for_header(writer, &header)?;
+
+        let sink = if header.is_encrypted() {
+            let key = key.ok_or_else(|| missing_key(&header))?;
+            ensure_nonce(&mut header)?;
+            EncoderSink::Encrypt {
+                buffer: Vec::new(),
+                segments,
+                key: key.clone(),
+                envelope: None,
+            }
+        } else {
+            EncoderSink::Segments(segments)
+        };
+
+        Self::with_sink(sink, header)
+    }
+
+    /// Encodes for `recipients`, as `encode_spool_for_recipients`.
+    pub fn for_recipients(
+        writer: W,
+        mut header: SpoolHeader,
+        sender: &Keypair,
+        recipients: &[Pubkey],
+    ) -> Result<Self> {
+        if !header.is_encrypted() {
+            return Err(anyhow!("Spools with recipients must be encrypted"));
+        }
+
+        header.flags |= SpoolFlags::Envelope as u8;
+        ensure_nonce(&mut header)?;
+
+        let (key, envelope) = seal_for_header(&header, sender, recipients)?;
+        let sink = EncoderSink::Encrypt {
+            buffer: Vec::new(),
+            segments: SegmentWriter::for_header(writer, &header)?,
+            key,
+            envelope: Some(envelope),
+        };
+
+        Self::with_sink(sink, header)
+    }
+
+    fn with_sink(sink: EncoderSink<W>, mut header: SpoolHeader) -> Result<Self> {
+        let algo = match CompressionAlgo::try_from(header.compression)
+            .map_err(|_| anyhow!("Invalid compression algorithm"))?
+        {
+            CompressionAlgo::Auto => {
+                let mime_type = MimeType::try_from(header.mime_type).unwrap_or(MimeType::Unknown);
+                if mime_type.is_compressed() {
+                    CompressionAlgo::None
+                } else {
+                    CompressionAlgo::Zstd
+                }
+            }
+            algo => algo,
+        };
+        header.compression = algo.into();
+
+        Ok(Self {
+            compressor: Compressor::new(sink, algo, header.compression_level)?,
+            header,
+        })
+    }
+
+    /// The header so far, with the codec and nonce resolved; `data_len` is set by `finish`.
+    pub fn header(&self) -> &SpoolHeader {
+        &self.header
+    }
+
+    /// The writer of the encoded segments. Complete segments written so far can be taken from
+    /// it, e.g. to pass them on to an async writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        match self.compressor.get_mut() {
+            EncoderSink::Segments(segments) => segments.get_mut(),
+            EncoderSink::Encrypt { segments, .. } => segments.get_mut(),
+        }
+    }
+
+    /// Writes the end of the payload and returns the writer and the header to set.
+    pub fn finish(self) -> Result<(W, SpoolHeader)> {
+        let mut header = self.header;
+
+        let (writer, data_len) = match self.compressor.finish()? {
+            EncoderSink::Segments(segments) => segments.finish()?,
+            EncoderSink::Encrypt { buffer, mut segments, key, envelope } => {
+                if let Some(envelope) = envelope {
+                    segments.write_all(&envelope.to_bytes())?;
+                }
+                segments.write_all(&encrypt(&buffer, &mut header, &key)?)?;
+                segments.finish()?
+            }
+        };
+
+        header.data_len = data_len;
+        Ok((writer, header))
+    }
+}
+
+impl<W: Write> Write for SpoolEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.compressor.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.compressor.flush()
+    }
+}
+
+impl<W: Write> Write for EncoderSink<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Segments(segments) => segments.write(buf),
+            Self::Encrypt { buffer, .. } => Write::write(buffer, buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Segments(segments) => segments.flush(),
+            Self::Encrypt { .. } => Ok(()),
+        }
+    }
+}
+
+/// Streaming counterpart of `decode_spool_with_key`: reads the raw segments of a spool (as
+/// returned by `ReadState::into_reader` or `finalize_read`) and yields the decoded payload.
+///
+/// Encrypted payloads are read in full and decrypted before decompression starts, since the
+/// authentication tag covers the whole payload.
+pub struct SpoolDecoder<'a> {
+    inner: Decompressor<Box<dyn Read + Send + 'a>>,
+}
+
+impl<'a> SpoolDecoder<'a> {
+    pub fn new<R: Read + Send + 'a>(reader: R, header: &SpoolHeader) -> Result<Self> {
+        Self::with_key(reader, header, None)
+    }
+
+    /// Decodes with `key`. Fails with `EncryptionError` if the spool is encrypted and `key` is
+    /// missing or wrong.
+    pub fn with_key<R: Read + Send + 'a>(
+        reader: R,
+        header: &SpoolHeader,
+        key: Option<&SpoolKey>,
+    ) -> Result<Self> {
+        let (envelope, source) = spool_source(reader, header)?;
+
+        if let Some(reference) = envelope.and_then(|e| e.reference) {
+            return Err(EncryptionError::Referenced(reference).into());
+        }
+
+        Self::decode(source, header, key)
+    }
+
+    /// Decodes a spool with a key envelope, as `decode_spool_with_keypair`.
+    pub fn with_keypair<R: Read + Send + 'a>(
+        reader: R,
+        header: &SpoolHeader,
+        keypair: &Keypair,
+    ) -> Result<Self> {
+        let (envelope, source) = spool_source(reader, header)?;
+        let envelope = envelope.ok_or(EncryptionError::NoEnvelope)?;
+
+        if let Some(reference) = envelope.reference {
+            return Err(EncryptionError::Referenced(reference).into());
+        }
+
+        let key = envelope.open(keypair)?;
+        Self::decode(source, header, Some(&key))
+    }
+
+    fn decode(
+        mut source: Box<dyn Read + Send + 'a>,
+        header: &SpoolHeader,
+        key: Option<&SpoolKey>,
+    ) -> Result<Self> {
+        if header.is_encrypted() {
+            let key = key.ok_or_else(|| missing_key(header))?;
+
+            let mut processed = Vec::new();
+            source.read_to_end(&mut processed)?;
+            source = Box::new(Cursor::new(decrypt(&processed, header, key)?));
+        }
+
+        let compression_algo = CompressionAlgo::try_from(header.compression)
+            .map_err(|_| anyhow!("Invalid compression algorithm"))?;
+
+        Ok(Self {
+            inner: Decompressor::new(source, compression_algo)?,
+        })
+    }
+}
+
+impl Read for SpoolDecoder<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// The payload of the raw segments in `reader`, after the key envelope, which is returned if the
+/// spool has one.
+pub(crate) fn spool_source<'a, R: Read + Send + 'a>(
+    mut reader: R,
+    header: &SpoolHeader,
+) -> Result<(Option<Envelope>, Box<dyn Read + Send + 'a>)> {
+    if header.has_flag(SpoolFlags::Manifest) {
+        bail!("The spool is a manifest, its payload must be stitched from its parts first");
+    }
+
+    let mut source: Box<dyn Read + Send + 'a> = if header.has_flag(SpoolFlags::ErasureCoded) {
+        // Missing segments can only be rebuilt once every segment has been read
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let recovery = recover_segments(&data, header)?;
+        if !recovery.recovered.is_empty() {
+            debug!("Recovered segments {:?} from parity", recovery.recovered);
+        }
+        Box::new(UnprefixReader::new(Cursor::new(recovery.data), header.data_len))
+    } else if header.has_flag(SpoolFlags::Prefixed) {
+        Box::new(UnprefixReader::new(reader, header.data_len))
+    } else {
+        Box::new(reader)
+    };
+
+    let envelope = if header.has_flag(SpoolFlags::Envelope) {
+        Some(Envelope::read_from(&mut source)?)
+    } else {
+        None
+    };
+
+    Ok((envelope, source))
+}
+
+/// Encodes the data read from `reader` with a `SpoolEncoder`, writing the encoded segments to
+/// `writer` as they are produced. Returns the header to set.
+pub async fn encode_spool_async<R, W>(
+    mut reader: R,
+    mut writer: W,
+    header: SpoolHeader,
+    key: Option<&SpoolKey>,
+) -> Result<SpoolHeader>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut encoder = SpoolEncoder::with_key(Vec::new(), header, key)?;
+    let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        encoder.write_all(&buf[..n])?;
+        let encoded = encoder.get_mut();
+        writer.write_all(encoded).await?;
+        encoded.clear();
+    }
+
+    let (encoded, header) = encoder.finish()?;
+    writer.write_all(&encoded).await?;
+    writer.flush().await?;
+
+    Ok(header)
+}
+
+/// Decodes the raw segments in `reader` with a `SpoolDecoder`, writing the payload to `writer` as
+/// it is decoded. Returns the number of bytes written.
+///
+/// Decompression and decryption run on the blocking thread pool, reading from `reader` through
+/// a `SyncIoBridge`; the decoded chunks are written to `writer` on the calling task.
+pub async fn decode_spool_async<R, W>(
+    reader: R,
+    header: &SpoolHeader,
+    key: Option<&SpoolKey>,
+    mut writer: W,
+) -> Result<u64>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    let reader = SyncIoBridge::new(reader);
+    let header = *header;
+    let key = key.cloned();
+    let (tx, mut rx) = mpsc::channel::<Vec<u8>>(DECODE_CHANNEL_CAPACITY);
+
+    let decode = task::spawn_blocking(move || -> Result<()> {
+        let mut decoder = SpoolDecoder::with_key(reader, &header, key.as_ref())?;
+
+        loop {
+            let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+            let n = decoder.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+
+            buf.truncate(n);
+            if tx.blocking_send(buf).is_err() {
+                // The writer failed, its error is returned below
+                return Ok(());
+            }
+        }
+    });
+
+    let mut written = 0;
+    while let Some(chunk) = rx.recv().await {
+        writer.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+
+    decode.await??;
+    writer.flush().await?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_spool, decode_spool_with_keypair, encode_spool, prefix_segments, EncryptionAlgo};
+    use solana_sdk::signature::Signer;
+
+    fn header(compression: CompressionAlgo, encryption: EncryptionAlgo) -> SpoolHeader {
+        SpoolHeader::new(MimeType::TextPlain, compression, encryption, SpoolFlags::Prefixed)
+    }
+
+    /// Writes `data` to the encoder in uneven pieces.
+    fn encode(mut encoder: SpoolEncoder<Vec<u8>>, data: &[u8]) -> (Vec<u8>, SpoolHeader) {
+        for piece in data.chunks(1000) {
+            encoder.write_all(piece).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_segment_writer_matches_prefix_segments() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+
+        for len in [0, 1, SEGMENT_SIZE - PREFIX_LEN, SEGMENT_SIZE, data.len()] {
+            let mut writer = SegmentWriter::new(Vec::new(), true);
+            for piece in data[..len].chunks(7) {
+                writer.write_all(piece).unwrap();
+            }
+            let (output, data_len) = writer.finish().unwrap();
+
+            assert_eq!(data_len, len as u64);
+            assert_eq!(output, prefix_segments(&data[..len]));
+        }
+    }
+
+    #[test]
+    fn test_unprefix_reader_reorders_and_truncates() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let prefixed = prefix_segments(&data);
+
+        // Reverse the segments, repeat one and pad the last one as read from the chain
+        let mut segments: Vec<Vec<u8>> = prefixed
+            .chunks(SEGMENT_SIZE)
+            .map(|s| padded_array::<SEGMENT_SIZE>(s).to_vec())
+            .collect();
+        segments.reverse();
+        segments.insert(3, segments[2].clone());
+
+        let mut output = Vec::new();
+        UnprefixReader::new(segments.concat().as_slice(), data.len() as u64)
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, data);
+
+        // A missing segment is an error
+        segments.remove(5);
+        let mut output = Vec::new();
+        let err = UnprefixReader::new(segments.concat().as_slice(), data.len() as u64)
+            .read_to_end(&mut output)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_stream_matches_buffers() {
+        let data = b"streamed a segment at a time. ".repeat(500);
+
+        for algo in [CompressionAlgo::None, CompressionAlgo::Gzip, CompressionAlgo::Zstd, CompressionAlgo::Brotli, CompressionAlgo::Lz4] {
+            let (encoded, header) = encode(SpoolEncoder::new(Vec::new(), header(algo, EncryptionAlgo::None)).unwrap(), &data);
+
+            let mut buffered_header = self::header(algo, EncryptionAlgo::None);
+            let buffered = encode_spool(&data, &mut buffered_header).unwrap();
+            assert_eq!(header.compression, buffered_header.compression);
+
+            let mut decoded = Vec::new();
+            SpoolDecoder::new(encoded.as_slice(), &header).unwrap().read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+            assert_eq!(decode_spool(encoded, &header).unwrap(), data);
+
+            let mut decoded = Vec::new();
+            SpoolDecoder::new(buffered.as_slice(), &buffered_header).unwrap().read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn test_stream_erasure_matches_buffers() {
+        let data = b"parity for every stripe. ".repeat(200);
+        let mut header = header(CompressionAlgo::None, EncryptionAlgo::None);
+        header.set_erasure(Some(ErasureParams::new(8, 3).unwrap()));
+
+        let (encoded, encoded_header) = encode(SpoolEncoder::new(Vec::new(), header).unwrap(), &data);
+        let buffered = encode_spool(&data, &mut header).unwrap();
+        assert_eq!(encoded, buffered);
+        assert_eq!(encoded_header.data_len, header.data_len);
+        assert_eq!(encoded.len() % SEGMENT_SIZE, 0);
+
+        // A segment of every stripe goes missing
+        let gaps: Vec<u8> = encoded
+            .chunks(SEGMENT_SIZE)
+            .enumerate()
+            .filter(|(n, _)| n % 8 != 5)
+            .flat_map(|(_, segment)| segment.to_vec())
+            .collect();
+        assert_eq!(decode_spool(gaps, &header).unwrap(), data);
+    }
+
+    #[test]
+    fn test_stream_encrypted_and_shared() {
+        let data = b"encrypted, then streamed. ".repeat(300);
+        let key = SpoolKey::generate();
+
+        let encoder = SpoolEncoder::with_key(Vec::new(), header(CompressionAlgo::Auto, EncryptionAlgo::Aes256Gcm), Some(&key)).unwrap();
+        assert_eq!(encoder.header().compression, CompressionAlgo::Zstd as u8);
+        let (encoded, header) = encode(encoder, &data);
+
+        assert!(SpoolDecoder::new(encoded.as_slice(), &header).is_err());
+        let mut decoded = Vec::new();
+        SpoolDecoder::with_key(encoded.as_slice(), &header, Some(&key)).unwrap().read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let sender = Keypair::new();
+        let recipient = Keypair::new();
+        let encoder = SpoolEncoder::for_recipients(
+            Vec::new(),
+            self::header(CompressionAlgo::Gzip, EncryptionAlgo::XChaCha20Poly1305),
+            &sender,
+            &[recipient.pubkey()],
+        )
+        .unwrap();
+        let (encoded, header) = encode(encoder, &data);
+
+        let mut decoded = Vec::new();
+        SpoolDecoder::with_keypair(encoded.as_slice(), &header, &recipient).unwrap().read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(decode_spool_with_keypair(encoded.clone(), &header, &recipient).unwrap(), data);
+        assert!(SpoolDecoder::with_keypair(encoded.as_slice(), &header, &sender).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_async_roundtrip() {
+        let data = b"async in, async out. ".repeat(400);
+
+        let mut encoded = Vec::new();
+        let header = encode_spool_async(data.as_slice(), &mut encoded, header(CompressionAlgo::Brotli, EncryptionAlgo::None), None)
+            .await
+            .unwrap();
+        assert_eq!(header.data_len as usize, encoded.len() - encoded.len().div_ceil(SEGMENT_SIZE) * PREFIX_LEN);
+
+        let mut decoded = Vec::new();
+        let written = decode_spool_async(Cursor::new(encoded.clone()), &header, None, &mut decoded).await.unwrap();
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(decoded, data);
+
+        // Decoding errors from the blocking task are returned
+        let key = SpoolKey::generate();
+        let mut encrypted = Vec::new();
+        let header = encode_spool_async(data.as_slice(), &mut encrypted, self::header(CompressionAlgo::Brotli, EncryptionAlgo::XChaCha20Poly1305), Some(&key))
+            .await
+            .unwrap();
+        let res = decode_spool_async(Cursor::new(encrypted), &header, None, &mut Vec::new()).await;
+        assert!(res.is_err());
+    }
+}
@@ -1,39 +1,21 @@
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signer, Signature},
+    signature::{Keypair, Signature},
     pubkey::Pubkey,
 };
-use spool_api::instruction::spool::build_subsidize_ix;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use crate::utils::*;
+
+use crate::{RpcBackend, SpoolClient};
 
 pub async fn subsidize_spool(
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     signer: &Keypair,
     spool_address: Pubkey,
     ata: Pubkey,
     amount: u64,
 ) -> Result<Signature> {
-
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(50_000);
-    let subsidize_ix = build_subsidize_ix(
-        signer.pubkey(),
-        ata,
-        spool_address,
-        amount,
-    );
-  
-    let signature = build_send_and_confirm_tx(
-        &[compute_budget_ix, subsidize_ix],
-        client,
-        signer.pubkey(),
-        &[signer]
-    )
-    .await
-    .map_err(|e| anyhow!("Failed to subsidize spool: {}", e))?;
-
-    Ok(signature)
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .subsidize_spool(spool_address, ata, amount)
+        .await
 }
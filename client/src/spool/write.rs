@@ -2,32 +2,20 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use solana_sdk::{
-    signature::{Keypair, Signer, Signature},
+    signature::{Keypair, Signature},
     pubkey::Pubkey,
 };
-use spool_api::instruction::spool::build_write_ix;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use crate::{
-    consts::*,
-    utils::*,
-};
+
+use crate::{RpcBackend, SpoolClient};
 
 pub async fn write_to_spool(
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     signer: &Keypair,
     spool_address: Pubkey,
     writer_address: Pubkey,
     data: &[u8],
 ) -> Result<Signature> {
-
-    let instruction = build_write_ix(
-        signer.pubkey(),
-        spool_address,
-        writer_address,
-        data,
-    );
-
-    let sig = send_with_retry(client, &instruction, signer, MAX_RETRIES).await?;
-    Ok(sig)
+    SpoolClient::preset(client.clone(), signer.insecure_clone())
+        .write_to_spool(spool_address, writer_address, data)
+        .await
 }
-
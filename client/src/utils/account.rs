@@ -1,16 +1,14 @@
-use std::sync::Arc;
-
 use anyhow::{Result, anyhow};
-use solana_client::nonblocking::rpc_client::RpcClient;
+use crate::backend::RpcBackend;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
 use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_sdk::{pubkey::Pubkey, account::Account};
-use spool_api::pda::{archive_pda, epoch_pda, block_pda};
-use spool_api::state::{Spool, Writer, Miner, Epoch, Block, Archive};
-use crate::utils::{deserialize, get_account, get_program_account};
+use spool_api::pda::{archive_pda, epoch_pda, block_pda, faucet_claim_pda, faucet_config_pda};
+use spool_api::state::{Spool, Writer, Miner, Epoch, Block, Archive, FaucetClaim, FaucetConfig};
+use crate::utils::{deserialize, get_account, get_account_if_exists, get_program_account};
 
-pub async fn get_spool_account(client: &Arc<RpcClient>, spool_address: &Pubkey) -> Result<(Spool, Pubkey)> {
+pub async fn get_spool_account(client: &impl RpcBackend, spool_address: &Pubkey) -> Result<(Spool, Pubkey)> {
     let account_bytes = get_account(client, spool_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Spool::unpack(&account.data)
@@ -19,7 +17,19 @@ pub async fn get_spool_account(client: &Arc<RpcClient>, spool_address: &Pubkey)
     Ok((account, *spool_address))
 }
 
-pub async fn find_spool_account(client: &Arc<RpcClient>, number: u64) -> Result<Option<(Pubkey, Account)>> {
+/// Returns the spool at `spool_address`, or `None` if it doesn't exist. Other RPC errors are
+/// returned as is.
+pub async fn get_spool_account_if_exists(client: &impl RpcBackend, spool_address: &Pubkey) -> Result<Option<Spool>> {
+    let Some(account) = get_account_if_exists(client, spool_address).await? else {
+        return Ok(None);
+    };
+    let account = Spool::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack spool account: {}", e))
+        .copied()?;
+    Ok(Some(account))
+}
+
+pub async fn find_spool_account(client: &impl RpcBackend, number: u64) -> Result<Option<(Pubkey, Account)>> {
     let number_bytes = number.to_le_bytes();
     let number_base64 = base64::encode(number_bytes);
 
@@ -49,7 +59,7 @@ pub async fn find_spool_account(client: &Arc<RpcClient>, number: u64) -> Result<
     Ok(accounts.into_iter().next())
 }
 
-pub async fn get_writer_account(client: &Arc<RpcClient>, writer_address: &Pubkey) -> Result<(Writer, Pubkey)> {
+pub async fn get_writer_account(client: &impl RpcBackend, writer_address: &Pubkey) -> Result<(Writer, Pubkey)> {
     let account_bytes = get_account(client, writer_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Writer::unpack(&account.data)
@@ -58,7 +68,7 @@ pub async fn get_writer_account(client: &Arc<RpcClient>, writer_address: &Pubkey
     Ok((account, *writer_address))
 }
 
-pub async fn get_miner_account(client: &Arc<RpcClient>, miner_address: &Pubkey) -> Result<(Miner, Pubkey)> {
+pub async fn get_miner_account(client: &impl RpcBackend, miner_address: &Pubkey) -> Result<(Miner, Pubkey)> {
     let account_bytes = get_account(client, miner_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Miner::unpack(&account.data)
@@ -67,7 +77,7 @@ pub async fn get_miner_account(client: &Arc<RpcClient>, miner_address: &Pubkey)
     Ok((account, *miner_address))
 }
 
-pub async fn get_epoch_account(client: &Arc<RpcClient>) -> Result<(Epoch, Pubkey)> {
+pub async fn get_epoch_account(client: &impl RpcBackend) -> Result<(Epoch, Pubkey)> {
     let (epoch_address, _bump) = epoch_pda();
     let account_bytes = get_account(client, &epoch_address).await?;
     let account: Account = deserialize(&account_bytes)?;
@@ -77,7 +87,7 @@ pub async fn get_epoch_account(client: &Arc<RpcClient>) -> Result<(Epoch, Pubkey
     Ok((account, epoch_address))
 }
 
-pub async fn get_block_account(client: &Arc<RpcClient>) -> Result<(Block, Pubkey)> {
+pub async fn get_block_account(client: &impl RpcBackend) -> Result<(Block, Pubkey)> {
     let (block_address, _bump) = block_pda();
     let account_bytes = get_account(client, &block_address).await?;
     let account: Account = deserialize(&account_bytes)?;
@@ -87,7 +97,7 @@ pub async fn get_block_account(client: &Arc<RpcClient>) -> Result<(Block, Pubkey
     Ok((account, block_address))
 }
 
-pub async fn get_archive_account(client: &Arc<RpcClient>) -> Result<(Archive, Pubkey)> {
+pub async fn get_archive_account(client: &impl RpcBackend) -> Result<(Archive, Pubkey)> {
     let (archive_address, _bump) = archive_pda();
     let account_bytes = get_account(client, &archive_address).await?;
     let account: Account = deserialize(&account_bytes)?;
@@ -96,3 +106,26 @@ pub async fn get_archive_account(client: &Arc<RpcClient>) -> Result<(Archive, Pu
         .copied()?;
     Ok((account, archive_address))
 }
+
+/// Returns the faucet claim tracker of `authority`, or `None` if the wallet never claimed.
+pub async fn get_faucet_claim_account(client: &impl RpcBackend, authority: &Pubkey) -> Result<Option<(FaucetClaim, Pubkey)>> {
+    let (faucet_claim_address, _bump) = faucet_claim_pda(*authority);
+    let Some(account) = get_account_if_exists(client, &faucet_claim_address).await? else {
+        return Ok(None);
+    };
+    let account = FaucetClaim::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack faucet claim account: {}", e))
+        .copied()?;
+    Ok(Some((account, faucet_claim_address)))
+}
+
+/// Returns the faucet config, or the default limits if the admin never set it.
+pub async fn get_faucet_config_account(client: &impl RpcBackend) -> Result<FaucetConfig> {
+    let (faucet_config_address, _bump) = faucet_config_pda();
+    let Some(account) = get_account_if_exists(client, &faucet_config_address).await? else {
+        return Ok(FaucetConfig::DEFAULT);
+    };
+    FaucetConfig::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack faucet config account: {}", e))
+        .copied()
+}
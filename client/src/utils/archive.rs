@@ -0,0 +1,143 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client as HttpClient;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use spool_api::prelude::*;
+
+/// Web RPC endpoint of an archive node. Nodes serve segments packed for their miner, so the
+/// miner pubkey is needed to unpack them; nodes that serve unpacked segments don't need it.
+///
+/// Parsed from `URL` or `MINER@URL`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveNode {
+    pub url: String,
+    pub miner: Option<Pubkey>,
+}
+
+impl ArchiveNode {
+    pub fn new(url: impl Into<String>, miner: Option<Pubkey>) -> Self {
+        Self {
+            url: url.into(),
+            miner,
+        }
+    }
+}
+
+impl FromStr for ArchiveNode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (miner, url) = match s.split_once('@') {
+            Some((miner, url)) => match Pubkey::from_str(miner) {
+                Ok(miner) => (Some(miner), url),
+                Err(_) => (None, s),
+            },
+            None => (None, s),
+        };
+
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("Invalid archive node {}: expected [MINER@]http(s)://HOST[:PORT]/api", s);
+        }
+
+        Ok(Self::new(url, miner))
+    }
+}
+
+impl fmt::Display for ArchiveNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.miner {
+            Some(miner) => write!(f, "{}@{}", miner, self.url),
+            None => f.write_str(&self.url),
+        }
+    }
+}
+
+/// Fetches a segment from an archive node with `getSegment`, unpacking it if the node serves
+/// packed segments. Returns `None` if the node doesn't have the spool or the segment.
+pub async fn get_archive_segment(
+    http: &HttpClient,
+    node: &ArchiveNode,
+    spool_address: &Pubkey,
+    segment_number: u64,
+) -> Result<Option<[u8; SEGMENT_SIZE]>> {
+    let response = http
+        .post(&node.url)
+        .json(&json!({
+            "jsonrpc": "2.0", "id": 1,
+            "method": "getSegment",
+            "params": {
+                "spool_address": spool_address.to_string(),
+                "segment_number": segment_number,
+            }
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    if let Some(error) = response.get("error") {
+        let message = error["message"].as_str().unwrap_or_default();
+        if message.contains("not found") {
+            return Ok(None);
+        }
+        bail!("Archive node {} returned an error: {}", node.url, error);
+    }
+
+    let encoded = response["result"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Invalid getSegment response from {}: {}", node.url, response))?;
+    let data = base64::decode(encoded)
+        .map_err(|e| anyhow!("Invalid segment data from {}: {}", node.url, e))?;
+
+    unpack_archive_segment(node, &data).map(Some)
+}
+
+/// Turns the data served by an archive node into a segment: packed segments are unpacked with
+/// the miner pubkey of the node, unpacked ones are taken as-is.
+pub fn unpack_archive_segment(node: &ArchiveNode, data: &[u8]) -> Result<[u8; SEGMENT_SIZE]> {
+    match data.len() {
+        PACKED_SEGMENT_SIZE => {
+            let miner = node.miner.ok_or_else(|| {
+                anyhow!("Archive node {} serves packed segments, its miner pubkey is required", node.url)
+            })?;
+            let solution = packx::Solution::from_bytes(data.try_into()?);
+            Ok(solution.unpack(&miner.to_bytes()))
+        }
+        len if len <= SEGMENT_SIZE => Ok(padded_array::<SEGMENT_SIZE>(data)),
+        len => bail!("Invalid segment from {}: {} bytes", node.url, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_archive_node() {
+        let miner = Pubkey::new_unique();
+
+        let node: ArchiveNode = format!("{miner}@http://127.0.0.1:3000/api").parse().unwrap();
+        assert_eq!(node, ArchiveNode::new("http://127.0.0.1:3000/api", Some(miner)));
+        assert_eq!(node.to_string().parse::<ArchiveNode>().unwrap(), node);
+
+        let node: ArchiveNode = "https://user@archive.example/api".parse().unwrap();
+        assert_eq!(node.miner, None);
+        assert_eq!(node.url, "https://user@archive.example/api");
+
+        assert!("archive.example".parse::<ArchiveNode>().is_err());
+    }
+
+    #[test]
+    fn test_unpack_archive_segment() {
+        let segment = [5u8; SEGMENT_SIZE];
+        let node = ArchiveNode::new("http://127.0.0.1:3000/api", None);
+
+        assert_eq!(unpack_archive_segment(&node, &segment).unwrap(), segment);
+        assert_eq!(unpack_archive_segment(&node, &segment[..10]).unwrap()[..10], segment[..10]);
+        assert!(unpack_archive_segment(&node, &[0; PACKED_SEGMENT_SIZE]).is_err());
+        assert!(unpack_archive_segment(&node, &[0; PACKED_SEGMENT_SIZE + 1]).is_err());
+    }
+}
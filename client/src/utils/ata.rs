@@ -1,7 +1,4 @@
-use std::sync::Arc;
-
 use anyhow::{Result, anyhow};
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
@@ -11,10 +8,11 @@ use spl_associated_token_account::instruction::create_associated_token_account;
 use spl_token::state::Account as TokenAccount;
 use solana_program::program_pack::Pack;
 
+use crate::backend::RpcBackend;
 use crate::utils::{deserialize, get_latest_blockhash, send_and_confirm_transaction};
 
 pub async fn create_ata(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     payer: &Keypair,
 ) -> Result<(Pubkey, Signature)> {
     let token_program_id = &spl_token::ID;
@@ -87,7 +85,7 @@ pub fn get_ata_address(owner: &Pubkey) -> Pubkey {
 }
 
 pub async fn get_token_balance(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     ata: &Pubkey,
 ) -> Result<u64> {
     let account = client.get_account(ata).await
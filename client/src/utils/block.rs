@@ -3,6 +3,7 @@ use thiserror::Error;
 use std::collections::HashMap;
 use solana_sdk::{
     pubkey::Pubkey, 
+    signature::Signature,
     bs58, 
 };
 use solana_transaction_status::{
@@ -39,6 +40,35 @@ pub struct SegmentKey {
     pub prev_slot: u64,
 }
 
+/// How a segment version came to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentChange {
+    Write,
+    Update,
+}
+
+/// The content of a spool segment after a write or an update, with where it happened on-chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentVersion {
+    pub address: Pubkey,
+    pub segment_number: u64,
+    pub slot: u64,
+    /// Index of the transaction in its block.
+    pub tx_index: usize,
+    /// Index of the instruction among the spool instructions processed with it.
+    pub ix_index: usize,
+    pub prev_slot: u64,
+    pub change: SegmentChange,
+    pub data: Vec<u8>,
+}
+
+impl SegmentVersion {
+    /// Where the version sits in the history of the chain; later versions replace earlier ones.
+    pub fn position(&self) -> (u64, usize, usize) {
+        (self.slot, self.tx_index, self.ix_index)
+    }
+}
+
 #[derive(Debug)]
 pub enum EventData {
     Write(WriteEvent),
@@ -60,10 +90,23 @@ pub enum InstructionData {
     Finalize { address: Pubkey },
 }
 
+/// A spool program event with the instruction that emitted it.
+#[derive(Debug)]
+pub struct SpoolEvent {
+    pub slot: u64,
+    pub signature: Signature,
+    /// Index of the transaction in its block.
+    pub tx_index: usize,
+    pub event: EventData,
+    pub instruction: InstructionData,
+}
+
 #[derive(Debug, Default)]
 pub struct SpoolBlock {
     pub events: Vec<EventData>,
     pub instructions: Vec<InstructionData>,
+    /// Index of the transaction of each instruction.
+    pub tx_indices: Vec<usize>,
 }
 
 #[derive(Debug, Default)]
@@ -71,29 +114,91 @@ pub struct ProcessedBlock {
     pub slot: u64,
     pub finalized_spools: HashMap<Pubkey, u64>,
     pub segment_writes: HashMap<SegmentKey, Vec<u8>>,
+    /// Every segment written or updated in the block, in execution order.
+    pub segment_versions: Vec<SegmentVersion>,
 }
 
 #[derive(Debug, Default)]
 struct MergedSpoolData {
     finalized_spools: HashMap<Pubkey, u64>,
     segment_writes: HashMap<SegmentKey, Vec<u8>>,
+    segment_versions: Vec<SegmentVersion>,
 }
 
 pub fn process_block(block: UiConfirmedBlock, slot: u64) -> Result<ProcessedBlock, BlockError> {
     let transactions = block.transactions.ok_or(BlockError::NoTransactions)?;
     let mut spool_block = SpoolBlock::default();
 
-    for tx in transactions {
-        process_transaction(&tx, &mut spool_block)?;
+    for (tx_index, tx) in transactions.iter().enumerate() {
+        process_transaction(tx, tx_index, &mut spool_block)?;
+    }
+
+    finish_block(&spool_block, slot)
+}
+
+/// Processes a single transaction of `slot` like `process_block`, for reads that fetch the
+/// transactions touching a spool instead of whole blocks. `tx_index` orders it against the other
+/// transactions of the slot.
+pub fn process_single_transaction(
+    tx: &EncodedTransactionWithStatusMeta,
+    slot: u64,
+    tx_index: usize,
+) -> Result<ProcessedBlock, BlockError> {
+    let mut spool_block = SpoolBlock::default();
+    process_transaction(tx, tx_index, &mut spool_block)?;
+
+    finish_block(&spool_block, slot)
+}
+
+/// Decodes the spool events of a block in execution order, each with its instruction, for
+/// subscribers that react to single events rather than rebuild spools.
+pub fn process_block_events(block: UiConfirmedBlock, slot: u64) -> Result<Vec<SpoolEvent>, BlockError> {
+    let transactions = block.transactions.ok_or(BlockError::NoTransactions)?;
+    let mut events = Vec::new();
+
+    for (tx_index, tx) in transactions.iter().enumerate() {
+        let mut spool_block = SpoolBlock::default();
+        process_transaction(tx, tx_index, &mut spool_block)?;
+        if spool_block.events.is_empty() {
+            continue;
+        }
+
+        verify_counts(&spool_block)?;
+        let signature = transaction_signature(tx)?;
+
+        for (event, instruction) in spool_block.events.into_iter().zip(spool_block.instructions) {
+            let paired = matches!(
+                (&event, &instruction),
+                (EventData::Write(_), InstructionData::Write { .. })
+                    | (EventData::Update(_), InstructionData::Update { .. })
+                    | (EventData::Finalize(_), InstructionData::Finalize { .. })
+            );
+            if !paired {
+                return Err(BlockError::InvalidData("Event/instruction type mismatch"));
+            }
+
+            events.push(SpoolEvent {
+                slot,
+                signature,
+                tx_index,
+                event,
+                instruction,
+            });
+        }
     }
 
-    verify_counts(&spool_block)?;
-    let merged = merge_events_and_instructions(&spool_block)?;
+    Ok(events)
+}
+
+fn finish_block(spool_block: &SpoolBlock, slot: u64) -> Result<ProcessedBlock, BlockError> {
+    verify_counts(spool_block)?;
+    let merged = merge_events_and_instructions(spool_block, slot)?;
 
     Ok(ProcessedBlock {
         slot,
         finalized_spools: merged.finalized_spools,
         segment_writes: merged.segment_writes,
+        segment_versions: merged.segment_versions,
     })
 }
 
@@ -145,19 +250,21 @@ fn verify_counts(spool_block: &SpoolBlock) -> Result<(), BlockError> {
 
 fn merge_events_and_instructions(
     spool_block: &SpoolBlock,
+    slot: u64,
 ) -> Result<MergedSpoolData, BlockError> {
     let mut merged = MergedSpoolData::default();
 
     // Iterate over events and instructions in parallel
-    for (event, instruction) in spool_block.events.iter().zip(&spool_block.instructions) {
+    for (ix_index, (event, instruction)) in spool_block.events.iter().zip(&spool_block.instructions).enumerate() {
+        let position = (slot, spool_block.tx_indices.get(ix_index).copied().unwrap_or_default(), ix_index);
 
         match (event, instruction) {
             (EventData::Write(write_event), InstructionData::Write { address, data }) => {
-                merge_write(write_event, address, data, &mut merged)?;
+                merge_write(write_event, address, data, position, &mut merged)?;
             }
 
             (EventData::Update(update_event), InstructionData::Update { address, segment_number, new_data, .. }) => {
-                merge_update(update_event, address, *segment_number, new_data, &mut merged)?;
+                merge_update(update_event, address, *segment_number, new_data, position, &mut merged)?;
             }
 
             (EventData::Finalize(finalize_event), InstructionData::Finalize { address }) => {
@@ -176,6 +283,7 @@ fn merge_write(
     write_event: &WriteEvent,
     address: &Pubkey,
     data: &[u8],
+    (slot, tx_index, ix_index): (u64, usize, usize),
     merged: &mut MergedSpoolData,
 ) -> Result<(), BlockError> {
     if write_event.address != address.to_bytes() {
@@ -203,6 +311,16 @@ fn merge_write(
             prev_slot: write_event.prev_slot,
         };
         merged.segment_writes.insert(key, segment.to_vec());
+        merged.segment_versions.push(SegmentVersion {
+            address: *address,
+            segment_number,
+            slot,
+            tx_index,
+            ix_index,
+            prev_slot: write_event.prev_slot,
+            change: SegmentChange::Write,
+            data: segment.to_vec(),
+        });
     }
 
     Ok(())
@@ -213,6 +331,7 @@ fn merge_update(
     address: &Pubkey,
     segment_number: u64,
     new_data: &[u8; SEGMENT_SIZE],
+    (slot, tx_index, ix_index): (u64, usize, usize),
     merged: &mut MergedSpoolData,
 ) -> Result<(), BlockError> {
     if update_event.address != address.to_bytes() {
@@ -231,6 +350,16 @@ fn merge_update(
 
     // Record the "new_data", effectively overwriting that segment
     merged.segment_writes.insert(key, new_data.to_vec());
+    merged.segment_versions.push(SegmentVersion {
+        address: *address,
+        segment_number,
+        slot,
+        tx_index,
+        ix_index,
+        prev_slot: update_event.prev_slot,
+        change: SegmentChange::Update,
+        data: new_data.to_vec(),
+    });
 
     // (optional) verify old_data + proof
 
@@ -253,6 +382,7 @@ fn merge_finalize(
 
 fn process_transaction(
     tx: &EncodedTransactionWithStatusMeta,
+    tx_index: usize,
     spool_block: &mut SpoolBlock,
 ) -> Result<(), BlockError> {
     if is_failed_transaction(tx) {
@@ -275,12 +405,15 @@ fn process_transaction(
                 }
             }
 
+            let account_keys = resolved_account_keys(&raw_message.account_keys, &tx.meta);
             process_top_level_instructions(
-                &raw_message.account_keys,
+                &account_keys,
                 &raw_message.instructions,
                 spool_block,
             )?;
-            process_inner_instructions(&raw_message.account_keys, &tx.meta, spool_block)?;
+            process_inner_instructions(&account_keys, &tx.meta, spool_block)?;
+
+            spool_block.tx_indices.resize(spool_block.instructions.len(), tx_index);
             Ok(())
         }
         _ => {
@@ -289,6 +422,24 @@ fn process_transaction(
     }
 }
 
+/// The account keys instructions index into: the static keys of the message, then the writable
+/// and readonly addresses loaded from lookup tables (v0 transactions).
+fn resolved_account_keys(
+    static_keys: &[String],
+    meta: &Option<UiTransactionStatusMeta>,
+) -> Vec<String> {
+    let mut account_keys = static_keys.to_vec();
+
+    if let Some(meta) = meta {
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            account_keys.extend(loaded.writable.iter().cloned());
+            account_keys.extend(loaded.readonly.iter().cloned());
+        }
+    }
+
+    account_keys
+}
+
 fn process_log_messages(
     log_messages: &[String],
     spool_block: &mut SpoolBlock,
@@ -453,6 +604,18 @@ fn process_instruction(
     }
 }
 
+fn transaction_signature(tx: &EncodedTransactionWithStatusMeta) -> Result<Signature, BlockError> {
+    let EncodedTransaction::Json(ui_tx) = &tx.transaction else {
+        return Err(BlockError::InvalidData("Expected JSON transaction encoding"));
+    };
+
+    ui_tx
+        .signatures
+        .first()
+        .and_then(|signature| signature.parse().ok())
+        .ok_or(BlockError::InvalidData("Invalid transaction signature"))
+}
+
 fn is_failed_transaction(tx: &EncodedTransactionWithStatusMeta) -> bool {
     if let Some(meta) = &tx.meta {
         if meta.status.is_err() {
@@ -490,3 +653,93 @@ fn get_event_data(log: &str) -> Option<Vec<u8>> {
     let encoded_data = log.strip_prefix("Program data: ")?;
     base64::decode(encoded_data).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError, VersionedTransaction},
+    };
+    use solana_transaction_status::{
+        BlockEncodingOptions,
+        ConfirmedBlock,
+        TransactionDetails,
+        TransactionStatusMeta,
+        TransactionWithStatusMeta,
+        UiTransactionEncoding,
+        VersionedTransactionWithStatusMeta,
+    };
+    use spool_api::instruction::spool::Finalize;
+
+    fn finalize_tx(payer: &Keypair, spool_address: Pubkey, failed: bool) -> TransactionWithStatusMeta {
+        let ix = Instruction {
+            program_id: spool_api::ID,
+            accounts: vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(spool_address, false),
+            ],
+            data: Finalize {}.to_bytes(),
+        };
+        let message = Message::new(&[ix], Some(&payer.pubkey()));
+        let tx = Transaction::new(&[payer], message, Hash::default());
+
+        let event = FinalizeEvent { spool: 7, address: spool_address.to_bytes() };
+        let meta = TransactionStatusMeta {
+            status: if failed { Err(TransactionError::AccountInUse) } else { Ok(()) },
+            log_messages: Some(vec![
+                format!("Program {} invoke [1]", spool_api::ID),
+                format!("Program data: {}", base64::encode(event.to_bytes())),
+                format!("Program {} success", spool_api::ID),
+            ]),
+            ..TransactionStatusMeta::default()
+        };
+
+        TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+            transaction: VersionedTransaction::from(tx),
+            meta,
+        })
+    }
+
+    #[test]
+    fn test_process_block_events() {
+        let payer = Keypair::new();
+        let spool_address = Pubkey::new_unique();
+        let transactions = vec![
+            finalize_tx(&payer, spool_address, true),
+            finalize_tx(&payer, spool_address, false),
+        ];
+        let signature = transactions[1].transaction_signature().to_owned();
+
+        let block = ConfirmedBlock {
+            previous_blockhash: Hash::default().to_string(),
+            blockhash: Hash::new_unique().to_string(),
+            parent_slot: 41,
+            transactions,
+            rewards: Vec::new(),
+            num_partitions: None,
+            block_time: None,
+            block_height: None,
+        }
+        .encode_with_options(
+            UiTransactionEncoding::Json,
+            BlockEncodingOptions {
+                transaction_details: TransactionDetails::Full,
+                show_rewards: false,
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .unwrap();
+
+        let events = process_block_events(block, 42).unwrap();
+        assert_eq!(events.len(), 1);
+
+        let event = &events[0];
+        assert_eq!((event.slot, event.tx_index, event.signature), (42, 1, signature));
+        assert!(matches!(event.event, EventData::Finalize(FinalizeEvent { spool: 7, .. })));
+        assert!(matches!(event.instruction, InstructionData::Finalize { address } if address == spool_address));
+    }
+}
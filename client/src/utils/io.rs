@@ -1,14 +1,25 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
-use std::io::{Read, Write};
+use std::io::{self, BufReader, Read, Write};
 use spool_api::consts::*;
 
+use crate::error::SpoolClientError;
+use crate::spool::CompressionAlgo;
+
+/// Zstd level used when the header doesn't set one.
+pub const ZSTD_DEFAULT_LEVEL: i32 = 19;
+/// Brotli quality used when the header doesn't set one.
+pub const BROTLI_DEFAULT_QUALITY: u32 = 11;
+/// Brotli window size (log2).
+const BROTLI_WINDOW: u32 = 22;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
 pub fn serialize<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
     bincode::serialize(value).map_err(|e| anyhow!("Serialization failed: {}", e))
 }
 
 pub fn deserialize<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T> {
-    bincode::deserialize(data).map_err(|e| anyhow!("Deserialization failed: {}", e))
+    bincode::deserialize(data).map_err(|e| SpoolClientError::Decode(e.to_string()).into())
 }
 
 pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
@@ -24,6 +35,153 @@ pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
     Ok(decompressed)
 }
 
+/// Compresses `data` with `algo` at `level`, clamped to the range of the codec (0 for the codec
+/// default; LZ4 has no levels).
+pub fn compress_with(data: &[u8], algo: CompressionAlgo, level: u8) -> Result<Vec<u8>> {
+    let mut compressor = Compressor::new(Vec::new(), algo, level)?;
+    compressor.write_all(data)?;
+    compressor.finish()
+}
+
+/// Decompresses `data` compressed with `algo`.
+pub fn decompress_with(data: &[u8], algo: CompressionAlgo) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    Decompressor::new(data, algo)?.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// A writer that compresses everything written to it into `W`. Call `finish` to flush the end
+/// of the stream.
+pub enum Compressor<W: Write> {
+    None(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Brotli(Box<brotli::CompressorWriter<W>>),
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+}
+
+impl<W: Write> Compressor<W> {
+    /// Compresses with `algo` at `level`, as `compress_with`.
+    pub fn new(writer: W, algo: CompressionAlgo, level: u8) -> Result<Self> {
+        let compressor = match algo {
+            CompressionAlgo::None => Self::None(writer),
+            CompressionAlgo::Gzip => {
+                let level = match level {
+                    0 => Compression::default(),
+                    level => Compression::new(level.min(9) as u32),
+                };
+                Self::Gzip(GzEncoder::new(writer, level))
+            }
+            CompressionAlgo::Zstd => {
+                let level = match level {
+                    0 => ZSTD_DEFAULT_LEVEL,
+                    level => (level as i32).min(*zstd::compression_level_range().end()),
+                };
+                Self::Zstd(zstd::Encoder::new(writer, level)?)
+            }
+            CompressionAlgo::Brotli => {
+                let quality = match level {
+                    0 => BROTLI_DEFAULT_QUALITY,
+                    level => (level as u32).min(11),
+                };
+                Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                    writer,
+                    BROTLI_BUFFER_SIZE,
+                    quality,
+                    BROTLI_WINDOW,
+                )))
+            }
+            CompressionAlgo::Lz4 => Self::Lz4(lz4_flex::frame::FrameEncoder::new(writer)),
+            CompressionAlgo::Auto => bail!("Auto compression must be resolved before compressing"),
+        };
+
+        Ok(compressor)
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        match self {
+            Self::None(writer) => writer,
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+            Self::Brotli(encoder) => encoder.get_mut(),
+            Self::Lz4(encoder) => encoder.get_mut(),
+        }
+    }
+
+    /// Ends the compressed stream and returns the inner writer.
+    pub fn finish(self) -> Result<W> {
+        match self {
+            Self::None(writer) => Ok(writer),
+            Self::Gzip(encoder) => encoder.finish().map_err(Into::into),
+            Self::Zstd(encoder) => encoder.finish().map_err(Into::into),
+            Self::Brotli(encoder) => Ok(encoder.into_inner()),
+            Self::Lz4(encoder) => encoder
+                .finish()
+                .map_err(|e| anyhow!("LZ4 compression failed: {}", e)),
+        }
+    }
+}
+
+impl<W: Write> Write for Compressor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(writer) => writer.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+            Self::Brotli(encoder) => encoder.write(buf),
+            Self::Lz4(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+            Self::Brotli(encoder) => encoder.flush(),
+            Self::Lz4(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// A reader that decompresses the data read from `R`.
+pub enum Decompressor<R: Read> {
+    None(R),
+    Gzip(GzDecoder<R>),
+    Zstd(zstd::Decoder<'static, BufReader<R>>),
+    Brotli(Box<brotli::Decompressor<R>>),
+    Lz4(lz4_flex::frame::FrameDecoder<R>),
+}
+
+impl<R: Read> Decompressor<R> {
+    pub fn new(reader: R, algo: CompressionAlgo) -> Result<Self> {
+        let decompressor = match algo {
+            CompressionAlgo::None => Self::None(reader),
+            CompressionAlgo::Gzip => Self::Gzip(GzDecoder::new(reader)),
+            CompressionAlgo::Zstd => Self::Zstd(zstd::Decoder::new(reader)?),
+            CompressionAlgo::Brotli => {
+                Self::Brotli(Box::new(brotli::Decompressor::new(reader, BROTLI_BUFFER_SIZE)))
+            }
+            CompressionAlgo::Lz4 => Self::Lz4(lz4_flex::frame::FrameDecoder::new(reader)),
+            CompressionAlgo::Auto => bail!("Invalid compression algorithm: Auto is never stored"),
+        };
+
+        Ok(decompressor)
+    }
+}
+
+impl<R: Read> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::None(reader) => reader.read(buf),
+            Self::Gzip(decoder) => decoder.read(buf),
+            Self::Zstd(decoder) => decoder.read(buf),
+            Self::Brotli(decoder) => decoder.read(buf),
+            Self::Lz4(decoder) => decoder.read(buf),
+        }
+    }
+}
+
 pub fn estimate_chunks(data_len: usize) -> usize {
     data_len / SEGMENT_SIZE + if data_len % SEGMENT_SIZE != 0 { 1 } else { 0 }
 }
@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{
+    address_lookup_table::{
+        self,
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::{AddressLookupTable, LOOKUP_TABLE_META_SIZE},
+        AddressLookupTableAccount,
+    },
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    slot_hashes,
+};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::backend::RpcBackend;
+use crate::consts::WAIT_TIME;
+use crate::utils::get_slot;
+
+/// Fetches an address lookup table, with the addresses it holds.
+pub async fn get_lookup_table(
+    client: &impl RpcBackend,
+    address: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = client
+        .get_account(address)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch lookup table {}: {}", address, e))?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack lookup table {}: {}", address, e))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Offset of the authority key in a lookup table account: after the state tag, the deactivation
+/// and last extended slots, the start index of the last extension and the `Option` tag.
+const LOOKUP_TABLE_AUTHORITY_OFFSET: usize = 4 + 8 + 8 + 1 + 1;
+
+/// Lookup tables of `authority` that can be closed: they were deactivated and the slot they
+/// were deactivated in has left the slot hashes.
+pub async fn find_closable_lookup_tables(
+    client: &impl RpcBackend,
+    authority: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new(
+            LOOKUP_TABLE_AUTHORITY_OFFSET,
+            MemcmpEncodedBytes::Base64(base64::encode(authority.to_bytes())),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset: 0, length: LOOKUP_TABLE_META_SIZE }),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let accounts = client
+        .get_program_accounts(&address_lookup_table::program::id(), config)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch lookup tables of {}: {}", authority, e))?;
+    let slot = get_slot(client).await?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let deactivation_slot = AddressLookupTable::deserialize(&account.data).ok()?.meta.deactivation_slot;
+            let cooled_down = deactivation_slot != Slot::MAX
+                && slot > deactivation_slot.saturating_add(slot_hashes::MAX_ENTRIES as u64);
+            cooled_down.then_some(address)
+        })
+        .collect())
+}
+
+/// Builds the instructions creating a lookup table owned by `authority` that holds `addresses`,
+/// and returns them with the table address. `recent_slot` must be a recent finalized slot, see
+/// `get_lookup_table_slot`.
+pub fn build_create_lookup_table_ixs(
+    authority: Pubkey,
+    recent_slot: u64,
+    addresses: Vec<Pubkey>,
+) -> (Vec<Instruction>, Pubkey) {
+    let (create_ix, table_address) = create_lookup_table(authority, authority, recent_slot);
+    let extend_ix = extend_lookup_table(table_address, authority, Some(authority), addresses);
+
+    (vec![create_ix, extend_ix], table_address)
+}
+
+/// A slot to derive a new lookup table from: tables are keyed by a slot the cluster still has
+/// the hash of, so it is taken at finalized commitment.
+pub async fn get_lookup_table_slot(client: &impl RpcBackend) -> Result<u64> {
+    client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .map_err(|e| anyhow!("Failed to fetch finalized slot: {}", e))
+}
+
+/// Waits until the addresses added to a lookup table in `extended_slot` can be loaded, which is
+/// from the next slot on.
+pub async fn wait_for_lookup_table(client: &impl RpcBackend, extended_slot: u64) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(WAIT_TIME);
+
+    while get_slot(client).await? <= extended_slot {
+        if Instant::now() >= deadline {
+            return Err(anyhow!("Cluster is stuck at slot {}", extended_slot));
+        }
+        sleep(Duration::from_millis(400)).await;
+    }
+
+    Ok(())
+}
@@ -4,6 +4,9 @@ mod io;
 mod retry;
 mod ata;
 mod block;
+mod archive;
+mod lookup_table;
+mod subscribe;
 
 pub use account::*;
 pub use rpc::*;
@@ -11,3 +14,6 @@ pub use io::*;
 pub use retry::*;
 pub use ata::*;
 pub use block::*;
+pub use archive::*;
+pub use lookup_table::*;
+pub use subscribe::*;
@@ -1,18 +1,21 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use log::{debug, error};
 use solana_client::{
     client_error::{ClientErrorKind, Result as ClientResult},
     rpc_response::RpcSimulateTransactionResult,
 };
 
-use solana_sdk::signature::Signature;
+use solana_sdk::{message::VersionedMessage, signature::Signature};
 use tokio::time::Duration;
 
+use crate::error::SpoolClientError;
+
 const MAX_RETRIES: u32 = 8;
 const INITIAL_BACKOFF: u64 = 200;
 const TIMEOUT: Duration = Duration::from_secs(8);
 
-/// Generic retry function for asynchronous operations with exponential backoff.
+/// Generic retry function for asynchronous operations with exponential backoff. Stops at the
+/// first error that is not retryable (see `SpoolClientError::is_retryable`).
 pub async fn retry<F, Fut, T>(f: F) -> Result<T, anyhow::Error>
 where
     F: Fn() -> Fut,
@@ -25,13 +28,13 @@ where
             Ok(Ok(result)) => {
                 return Ok(result);
             }
-            Ok(Err(e)) if attempt == MAX_RETRIES - 1 => {
+            Ok(Err(e)) if attempt == MAX_RETRIES - 1 || !is_retryable(&e) => {
                 error!("Attempt {} failed with error: {:?}", attempt + 1, e);
                 return Err(e);
             }
             Err(_) if attempt == MAX_RETRIES - 1 => {
                 error!("Attempt {} timed out after {:?}", attempt + 1, TIMEOUT);
-                return Err(anyhow::anyhow!("Retry failed"));
+                return Err(SpoolClientError::Timeout(TIMEOUT).into());
             }
             _ => {
                 error!("Attempt {} failed, retrying after backoff", attempt + 1);
@@ -46,8 +49,15 @@ where
     Err(anyhow::anyhow!("All retry attempts failed"))
 }
 
-/// Handles transaction simulation logs for failed transactions.
-pub fn with_logs(res: ClientResult<Signature>) -> Result<Signature> {
+/// Whether an operation that failed with `err` may succeed if tried again. Errors that are
+/// not a `SpoolClientError` are assumed to be.
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    SpoolClientError::find(err).is_none_or(SpoolClientError::is_retryable)
+}
+
+/// Handles transaction simulation logs for failed transactions, decoding the error against the
+/// transaction `message`.
+pub fn with_logs(res: ClientResult<Signature>, message: &VersionedMessage) -> Result<Signature, SpoolClientError> {
     match res {
         Ok(signature) => Ok(signature),
 
@@ -77,7 +87,7 @@ pub fn with_logs(res: ClientResult<Signature>) -> Result<Signature> {
             }
 
     
-            Err(anyhow!("Transaction failed: {}", e))
+            Err(SpoolClientError::from_send(e, message))
         }
     }
 }
@@ -1,12 +1,13 @@
-use std::sync::Arc;
-
 use anyhow::{anyhow, Result};
 use base64;
 use log::debug;
 use solana_client::{
-    nonblocking::rpc_client::RpcClient,
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::RpcError,
     rpc_client::GetConfirmedSignaturesForAddress2Config,
-    rpc_config::{RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_config::{
+        RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig,
+    },
     rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
 
@@ -14,9 +15,14 @@ use solana_transaction_status::UiConfirmedBlock;
 use solana_transaction_status_client_types::TransactionDetails;
 use solana_sdk::{
     account::Account,
+    address_lookup_table::AddressLookupTableAccount,
+    borsh1::try_from_slice_unchecked,
     commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
     hash::Hash,
     instruction::Instruction,
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
     transaction::{Transaction, VersionedTransaction},
@@ -25,11 +31,218 @@ use solana_sdk::{
 use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
 use tokio::time::{sleep, Duration};
 
+use crate::backend::RpcBackend;
+use crate::client::{ComputeUnitPolicy, PriorityFeePolicy};
+use crate::error::SpoolClientError;
 use crate::utils::{deserialize, serialize, retry, with_logs};
 
 /// Initial backoff duration for retries (milliseconds).
 const INITIAL_BACKOFF: u64 = 200;
 
+/// Highest compute unit limit a transaction can request; used while simulating.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Most accounts `getRecentPrioritizationFees` accepts in one request.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// How a transaction is sized and priced before it is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeStrategy {
+    pub compute_units: ComputeUnitPolicy,
+    pub priority_fee: PriorityFeePolicy,
+}
+
+impl FeeStrategy {
+    pub fn new(compute_units: ComputeUnitPolicy, priority_fee: PriorityFeePolicy) -> Self {
+        Self {
+            compute_units,
+            priority_fee,
+        }
+    }
+
+    /// Preset compute unit limits and no priority fee; needs no extra RPC calls.
+    pub fn preset() -> Self {
+        Self::new(ComputeUnitPolicy::Preset, PriorityFeePolicy::None)
+    }
+
+    /// Prepends the compute budget instructions for `ixs`, simulating them and querying recent
+    /// fees as the policies require. A limit or price already set in `ixs` is kept. If the
+    /// simulation or the fee lookup fails, `default_limit` and no priority fee are used instead.
+    pub async fn apply(
+        &self,
+        client: &impl RpcBackend,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        default_limit: Option<u32>,
+    ) -> Vec<Instruction> {
+        let (has_limit, has_price) = compute_budget_settings(ixs);
+
+        let limit = if has_limit {
+            None
+        } else {
+            self.unit_limit(client, ixs, payer, default_limit).await
+        };
+
+        let price = if has_price {
+            None
+        } else {
+            self.unit_price(client, ixs).await
+        };
+
+        with_compute_budget(ixs, limit, price)
+    }
+
+    async fn unit_limit(
+        &self,
+        client: &impl RpcBackend,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        default_limit: Option<u32>,
+    ) -> Option<u32> {
+        match self.compute_units {
+            ComputeUnitPolicy::Simulate { margin_percent } => {
+                match simulate_compute_units(client, ixs, payer).await {
+                    Ok(units) => Some(limit_with_margin(units, margin_percent)),
+                    Err(e) => {
+                        debug!("Simulation failed, using the preset compute unit limit: {e}");
+                        default_limit
+                    }
+                }
+            }
+            policy => policy.unit_limit(default_limit),
+        }
+    }
+
+    async fn unit_price(&self, client: &impl RpcBackend, ixs: &[Instruction]) -> Option<u64> {
+        match self.priority_fee {
+            PriorityFeePolicy::Recent { percentile, max_unit_price } => {
+                match estimate_priority_fee(client, ixs, percentile, max_unit_price).await {
+                    Ok(price) => Some(price).filter(|price| *price > 0),
+                    Err(e) => {
+                        debug!("Fee estimation failed, sending without a priority fee: {e}");
+                        None
+                    }
+                }
+            }
+            policy => policy.unit_price(),
+        }
+    }
+}
+
+/// Simulates `ixs` paid by `payer` (without signatures, against the latest blockhash) and
+/// returns the compute units they consume.
+pub async fn simulate_compute_units(
+    client: &impl RpcBackend,
+    ixs: &[Instruction],
+    payer: &Pubkey,
+) -> Result<u64> {
+    let mut sim_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+    sim_ixs.extend(
+        ixs.iter()
+            .filter(|ix| !matches!(parse_compute_budget(ix), Some(ComputeBudgetInstruction::SetComputeUnitLimit(_))))
+            .cloned(),
+    );
+
+    let tx = VersionedTransaction::from(Transaction::new_with_payer(&sim_ixs, Some(payer)));
+    let result = client.simulate_transaction(&tx).await?;
+    if let Some(err) = result.err {
+        return Err(anyhow!(
+            "Simulation failed: {}\n{}",
+            err,
+            result.logs.unwrap_or_default().join("\n")
+        ));
+    }
+
+    result
+        .units_consumed
+        .ok_or_else(|| anyhow!("Simulation did not report consumed compute units"))
+}
+
+/// Returns the `percentile` of the prioritization fees recently paid for the accounts `ixs`
+/// write to, capped at `max_unit_price` (micro-lamports per compute unit).
+pub async fn estimate_priority_fee(
+    client: &impl RpcBackend,
+    ixs: &[Instruction],
+    percentile: u8,
+    max_unit_price: u64,
+) -> Result<u64> {
+    let fees = client
+        .get_recent_prioritization_fees(&writable_accounts(ixs))
+        .await?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+
+    Ok(fee_percentile(fees, percentile).min(max_unit_price))
+}
+
+/// Prepends a compute unit limit and price to `ixs`, when set.
+pub fn with_compute_budget(
+    ixs: &[Instruction],
+    unit_limit: Option<u32>,
+    unit_price: Option<u64>,
+) -> Vec<Instruction> {
+    let mut output = Vec::with_capacity(ixs.len() + 2);
+
+    if let Some(limit) = unit_limit {
+        output.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+
+    if let Some(price) = unit_price {
+        output.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+    }
+
+    output.extend_from_slice(ixs);
+    output
+}
+
+/// Adds `margin_percent` to `units`, capped at the highest limit a transaction can request.
+pub fn limit_with_margin(units: u64, margin_percent: u32) -> u32 {
+    let limit = units.saturating_mul(100 + margin_percent as u64) / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Nearest-rank `percentile` (0-100) of `fees`, or zero if there are none.
+pub fn fee_percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * percentile.min(100) as usize / 100;
+    fees[index]
+}
+
+/// Whether `ixs` already set a compute unit limit and a compute unit price.
+fn compute_budget_settings(ixs: &[Instruction]) -> (bool, bool) {
+    ixs.iter()
+        .filter_map(parse_compute_budget)
+        .fold((false, false), |(limit, price), ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitLimit(_) => (true, price),
+            ComputeBudgetInstruction::SetComputeUnitPrice(_) => (limit, true),
+            _ => (limit, price),
+        })
+}
+
+fn parse_compute_budget(ix: &Instruction) -> Option<ComputeBudgetInstruction> {
+    if ix.program_id != compute_budget::ID {
+        return None;
+    }
+    try_from_slice_unchecked(&ix.data).ok()
+}
+
+/// Distinct writable accounts of `ixs`, as many as a fee lookup accepts.
+fn writable_accounts(ixs: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in ixs.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    accounts
+}
+
 /// Returns the default transaction configuration for RPC calls.
 pub fn rpc_tx_config() -> RpcTransactionConfig {
     RpcTransactionConfig {
@@ -39,28 +252,76 @@ pub fn rpc_tx_config() -> RpcTransactionConfig {
     }
 }
 
+/// Compiles `ixs` paid by `payer` into a v0 message, loading the accounts found in `tables`
+/// through them.
+pub fn compile_message(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage> {
+    let message = v0::Message::try_compile(payer, ixs, tables, recent_blockhash)
+        .map_err(|e| anyhow!("Failed to compile transaction message: {}", e))?;
+    Ok(VersionedMessage::V0(message))
+}
+
+/// Builds and signs a v0 transaction of `ixs` paid by `payer`, see `compile_message`.
+pub fn build_versioned_tx(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&Keypair],
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let message = compile_message(ixs, payer, tables, recent_blockhash)?;
+    VersionedTransaction::try_new(message, signers)
+        .map_err(|e| anyhow!("Failed to sign transaction: {}", e))
+}
+
+/// Serialized size of a transaction with `message` once signed, in bytes.
+pub fn transaction_size(message: &VersionedMessage) -> usize {
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message: message.clone(),
+    };
+    bincode::serialized_size(&tx).map_or(usize::MAX, |size| size as usize)
+}
+
+/// Whether a transaction with `message` fits in a packet.
+pub fn fits_in_packet(message: &VersionedMessage) -> bool {
+    transaction_size(message) <= PACKET_DATA_SIZE
+}
+
 /// Sends a transaction and returns its serialized signature.
-pub async fn send_transaction(client: &Arc<RpcClient>, tx: &Transaction) -> Result<Vec<u8>> {
-    let signature: Signature = with_logs(client.send_transaction(tx).await)?;
+pub async fn send_transaction(
+    client: &impl RpcBackend,
+    tx: &Transaction,
+) -> Result<Vec<u8>> {
+    let tx = VersionedTransaction::from(tx.clone());
+    let signature: Signature = with_logs(client.send_transaction(&tx).await, &tx.message)?;
     serialize(&signature)
 }
 
 /// Sends and confirms a transaction, returning its serialized signature.
-pub async fn send_and_confirm_transaction(client: &Arc<RpcClient>, tx: &Transaction) -> Result<Vec<u8>> {
-    let signature: Signature = with_logs(client.send_and_confirm_transaction(tx).await)?;
+pub async fn send_and_confirm_transaction(
+    client: &impl RpcBackend,
+    tx: &Transaction,
+) -> Result<Vec<u8>> {
+    let tx = VersionedTransaction::from(tx.clone());
+    let signature: Signature = with_logs(client.send_and_confirm_transaction(&tx).await, &tx.message)?;
     serialize(&signature)
 }
 
 /// Fetches the latest blockhash and returns it serialized.
-pub async fn get_latest_blockhash(client: &Arc<RpcClient>) -> Result<Vec<u8>> {
+pub async fn get_latest_blockhash(client: &impl RpcBackend) -> Result<Vec<u8>> {
     let hash: Hash = client.get_latest_blockhash().await?;
     serialize(&hash)
 }
 
 /// Fetches a transaction by signature, returning its serialized data.
-pub async fn get_transaction(client: &Arc<RpcClient>, signature: &Signature) -> Result<Vec<u8>> {
+pub async fn get_transaction(client: &impl RpcBackend, signature: &Signature) -> Result<Vec<u8>> {
     let tx: EncodedConfirmedTransactionWithStatusMeta = client
-        .get_transaction_with_config(signature, rpc_tx_config())
+        .get_transaction(signature, rpc_tx_config())
         .await?;
 
     let tx = tx.transaction.transaction;
@@ -74,62 +335,87 @@ pub async fn get_transaction(client: &Arc<RpcClient>, signature: &Signature) ->
 }
 
 /// Sends a transaction and returns its signature.
-pub async fn send(client: &Arc<RpcClient>, tx: &Transaction) -> Result<Signature> {
-    let signature_bytes = send_transaction(client, tx).await?;
-    deserialize(&signature_bytes)
+pub async fn send(
+    client: &impl RpcBackend,
+    tx: &VersionedTransaction,
+) -> Result<Signature, SpoolClientError> {
+    with_logs(client.send_transaction(tx).await, &tx.message)
 }
 
 
 // Build Tx from ixs , send and confirm
 pub async fn build_send_and_confirm_tx(
     ixs: &[Instruction],
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     payer: Pubkey,
     signers : &[&Keypair]
 ) -> Result<Signature> {
+    build_send_and_confirm_tx_with_fees(ixs, client, payer, signers, &FeeStrategy::preset(), None).await
+}
+
+/// Like `build_send_and_confirm_tx`, sizing and pricing the transaction with `strategy`.
+/// `default_limit` is the compute unit preset for `ixs`.
+pub async fn build_send_and_confirm_tx_with_fees(
+    ixs: &[Instruction],
+    client: &impl RpcBackend,
+    payer: Pubkey,
+    signers: &[&Keypair],
+    strategy: &FeeStrategy,
+    default_limit: Option<u32>,
+) -> Result<Signature> {
+    build_send_and_confirm_versioned_tx(ixs, client, payer, signers, strategy, default_limit, &[]).await
+}
+
+/// Like `build_send_and_confirm_tx_with_fees`, loading the accounts found in `tables` through
+/// them.
+pub async fn build_send_and_confirm_versioned_tx(
+    ixs: &[Instruction],
+    client: &impl RpcBackend,
+    payer: Pubkey,
+    signers: &[&Keypair],
+    strategy: &FeeStrategy,
+    default_limit: Option<u32>,
+    tables: &[AddressLookupTableAccount],
+) -> Result<Signature> {
+    let ixs = strategy.apply(client, ixs, &payer, default_limit).await;
 
     let blockhash_bytes = get_latest_blockhash(client).await?;
     let recent_blockhash = deserialize(&blockhash_bytes)?;
-    let tx = Transaction::new_signed_with_payer(
-        ixs,
-        Some(&payer),
-        signers,
-        recent_blockhash,
-    );
+    let tx = build_versioned_tx(&ixs, &payer, signers, tables, recent_blockhash)?;
 
-    let signature_bytes = send_and_confirm_transaction(client, &tx).await?;
-    deserialize(&signature_bytes)
+    Ok(send_and_confirm(client, &tx).await?)
 }
 
-
 /// Sends and confirms a transaction, returning its signature.
-pub async fn send_and_confirm(client: &Arc<RpcClient>, tx: &Transaction) -> Result<Signature> {
-    let signature_bytes = send_and_confirm_transaction(client, tx).await?;
-    deserialize(&signature_bytes)
+pub async fn send_and_confirm(
+    client: &impl RpcBackend,
+    tx: &VersionedTransaction,
+) -> Result<Signature, SpoolClientError> {
+    with_logs(client.send_and_confirm_transaction(tx).await, &tx.message)
 }
 
-/// Sends a transaction with retry logic, returning its signature.
+/// Sends a transaction with retry logic, returning its signature. Errors that are not
+/// retryable are returned right away.
 pub async fn send_with_retry(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     instruction: &Instruction,
     payer: &Keypair,
     max_retries: u32,
 ) -> Result<Signature> {
+    let ixs = FeeStrategy::preset()
+        .apply(client, std::slice::from_ref(instruction), &payer.pubkey(), None)
+        .await;
+
     let mut attempts = 0;
     loop {
         let blockhash_bytes = get_latest_blockhash(client).await?;
         let recent_blockhash = deserialize(&blockhash_bytes)?;
 
-        let tx = Transaction::new_signed_with_payer(
-            &[instruction.clone()],
-            Some(&payer.pubkey()),
-            &[payer],
-            recent_blockhash,
-        );
+        let tx = build_versioned_tx(&ixs, &payer.pubkey(), &[payer], &[], recent_blockhash)?;
 
         match send(client, &tx).await {
             Ok(signature) => return Ok(signature),
-            Err(e) if attempts < max_retries => {
+            Err(e) if attempts < max_retries && e.is_retryable() => {
                 attempts += 1;
                 let delay_ms = INITIAL_BACKOFF * (1 << attempts);
 
@@ -140,20 +426,39 @@ pub async fn send_with_retry(
                 sleep(Duration::from_millis(delay_ms)).await;
                 continue;
             }
+            Err(e) if !e.is_retryable() => return Err(e.into()),
             Err(e) => {
-                return Err(anyhow!(
-                    "Failed to send transaction after {} attempts: {}",
-                    max_retries,
-                    e
-                ))
+                return Err(anyhow::Error::new(e)
+                    .context(format!("Failed to send transaction after {max_retries} attempts")))
             }
         }
     }
 }
 
+/// Fetches a transaction by signature in JSON encoding, with its status, logs and inner
+/// instructions, with retry logic.
+pub async fn get_transaction_with_meta(
+    client: &impl RpcBackend,
+    signature: &Signature,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    retry(|| async {
+        client
+            .get_transaction(signature, config)
+            .await
+            .map_err(|e| SpoolClientError::rpc(e, format!("Failed to fetch transaction {signature}")))
+    })
+    .await
+}
+
 /// Fetches a transaction with retry logic, returning the deserialized transaction.
 pub async fn get_transaction_with_retry(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     signature: &Signature,
     max_retries: u32,
 ) -> Result<VersionedTransaction> {
@@ -183,25 +488,42 @@ pub async fn get_transaction_with_retry(
 }
 
 /// Fetches an account by address and returns its serialized data.
-pub async fn get_account(client: &Arc<RpcClient>, address: &Pubkey) -> Result<Vec<u8>> {
+pub async fn get_account(client: &impl RpcBackend, address: &Pubkey) -> Result<Vec<u8>> {
     let account: Account = client.get_account(address).await?;
     serialize(&account)
 }
 
+/// Fetches an account by address, returning `None` when it doesn't exist. Any other error is
+/// returned as is.
+pub async fn get_account_if_exists(client: &impl RpcBackend, address: &Pubkey) -> Result<Option<Account>> {
+    match client.get_account(address).await {
+        Ok(account) => Ok(Some(account)),
+        Err(e) if is_account_not_found(&e) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn is_account_not_found(err: &ClientError) -> bool {
+    matches!(
+        err.kind(),
+        ClientErrorKind::RpcError(RpcError::ForUser(message)) if message.starts_with("AccountNotFound")
+    )
+}
+
 /// Fetches program accounts with the given configuration.
 pub async fn get_program_account(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     config: RpcProgramAccountsConfig,
 ) -> Result<Vec<(Pubkey, Account)>> {
     client
-        .get_program_accounts_with_config(&spool_api::ID, config)
+        .get_program_accounts(&spool_api::ID, config)
         .await
         .map_err(|e| anyhow!("Failed to fetch program accounts: {}", e))
 }
 
 /// Fetches a block by slot number with retry logic, using the specified transaction details.
 pub async fn get_block_by_number(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     slot_number: u64,
     transaction_details: TransactionDetails,
 ) -> Result<UiConfirmedBlock> {
@@ -215,55 +537,49 @@ pub async fn get_block_by_number(
 
     retry(|| async {
         client
-            .get_block_with_config(slot_number, config)
+            .get_block(slot_number, config)
             .await
-            .map_err(|e| anyhow!("Failed to fetch block {}: {}", slot_number, e))
+            .map_err(|e| SpoolClientError::rpc(e, format!("Failed to fetch block {slot_number}")))
     })
     .await
 }
 
 /// Fetches the latest confirmed block height with retry logic.
-pub async fn get_block_height(client: &Arc<RpcClient>) -> Result<u64> {
+pub async fn get_block_height(client: &impl RpcBackend) -> Result<u64> {
     retry(|| async {
         client
             .get_block_height()
             .await
-            .map_err(|e| {
-                anyhow!("Failed to fetch block height: {}", e)
-            })
+            .map_err(|e| SpoolClientError::rpc(e, "Failed to fetch block height"))
     })
     .await
 }
 
 /// Fetches the current slot with retry logic.
-pub async fn get_slot(client: &Arc<RpcClient>) -> Result<u64> {
+pub async fn get_slot(client: &impl RpcBackend) -> Result<u64> {
     retry(|| async {
         client
             .get_slot()
             .await
-            .map_err(|e| {
-                anyhow!("Failed to fetch current slot: {}", e)
-            })
+            .map_err(|e| SpoolClientError::rpc(e, "Failed to fetch current slot"))
     })
     .await
 }
 
 /// Fetches a list of confirmed slots starting from `start_slot` with a `limit`, using retry logic.
-pub async fn get_blocks_with_limit(client: &Arc<RpcClient>, start_slot: u64, limit: usize) -> Result<Vec<u64>> {
+pub async fn get_blocks_with_limit(client: &impl RpcBackend, start_slot: u64, limit: usize) -> Result<Vec<u64>> {
     retry(|| async {
         client
             .get_blocks_with_limit(start_slot, limit)
             .await
-            .map_err(|e| {
-                anyhow!("Failed to fetch blocks from slot {}: {}", start_slot, e)
-            })
+            .map_err(|e| SpoolClientError::rpc(e, format!("Failed to fetch blocks from slot {start_slot}")))
     })
     .await
 }
 
 /// Fetches transaction signatures for an address with the given configuration, with retry logic.
 pub async fn get_signatures_for_address(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     address: &Pubkey,
     before: Option<Signature>,
     until: Option<Signature>,
@@ -278,9 +594,72 @@ pub async fn get_signatures_for_address(
             commitment: None,
         };
         client
-            .get_signatures_for_address_with_config(&address, config)
+            .get_signatures_for_address(&address, config)
             .await
-            .map_err(|e| anyhow!("Failed to fetch signatures for address {}: {}", address, e))
+            .map_err(|e| SpoolClientError::rpc(e, format!("Failed to fetch signatures for address {address}")))
     })
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy_ix() -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![])
+    }
+
+    #[test]
+    fn test_with_compute_budget() {
+        assert_eq!(with_compute_budget(&[dummy_ix()], None, None).len(), 1);
+
+        let ixs = with_compute_budget(&[dummy_ix()], Some(42_000), Some(1_000));
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[0], ComputeBudgetInstruction::set_compute_unit_limit(42_000));
+        assert_eq!(ixs[1], ComputeBudgetInstruction::set_compute_unit_price(1_000));
+        assert_eq!(compute_budget_settings(&ixs), (true, true));
+        assert_eq!(compute_budget_settings(&ixs[1..]), (false, true));
+    }
+
+    #[test]
+    fn test_limit_with_margin() {
+        assert_eq!(limit_with_margin(10_000, 10), 11_000);
+        assert_eq!(limit_with_margin(10_000, 0), 10_000);
+        assert_eq!(limit_with_margin(1_300_000, 50), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_versioned_tx_size() {
+        let payer = Keypair::new();
+        let ixs = [dummy_ix()];
+
+        let message = compile_message(&ixs, &payer.pubkey(), &[], Hash::default()).unwrap();
+        let tx = build_versioned_tx(&ixs, &payer.pubkey(), &[&payer], &[], Hash::default()).unwrap();
+        assert_eq!(transaction_size(&message), bincode::serialize(&tx).unwrap().len());
+        assert!(fits_in_packet(&message));
+
+        let large = [Instruction::new_with_bytes(Pubkey::new_unique(), &[0; PACKET_DATA_SIZE], vec![])];
+        let message = compile_message(&large, &payer.pubkey(), &[], Hash::default()).unwrap();
+        assert!(!fits_in_packet(&message));
+    }
+
+    #[test]
+    fn test_fee_percentile() {
+        assert_eq!(fee_percentile(vec![], 50), 0);
+        assert_eq!(fee_percentile(vec![5, 1, 4, 2, 3], 0), 1);
+        assert_eq!(fee_percentile(vec![5, 1, 4, 2, 3], 50), 3);
+        assert_eq!(fee_percentile(vec![5, 1, 4, 2, 3], 100), 5);
+        assert_eq!(fee_percentile(vec![5, 1, 4, 2, 3], 255), 5);
+    }
+
+    #[test]
+    fn test_is_account_not_found() {
+        let missing = ClientError::from(RpcError::ForUser(
+            format!("AccountNotFound: pubkey={}", Pubkey::new_unique()),
+        ));
+        assert!(is_account_not_found(&missing));
+
+        let unavailable = ClientError::from(RpcError::RpcRequestError("connection refused".into()));
+        assert!(!is_account_not_found(&unavailable));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    collections::BTreeSet,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt};
+use log::{debug, warn};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{
+        RpcBlockSubscribeConfig, RpcBlockSubscribeFilter, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::{UiConfirmedBlock, UiTransactionEncoding};
+use solana_transaction_status_client_types::TransactionDetails;
+use tokio::{
+    sync::mpsc,
+    time::{sleep, Duration},
+};
+
+use crate::backend::RpcBackend;
+use crate::consts::*;
+use crate::utils::{get_block_by_number, get_blocks_with_limit, get_slot, process_block_events, SpoolEvent};
+
+/// Events buffered for a subscriber that doesn't keep up.
+const EVENT_BUFFER_SIZE: usize = 1024;
+/// Slots before the backfill position that are still remembered, so late notifications for
+/// them are not yielded twice.
+const PROCESSED_SLOT_WINDOW: u64 = 1024;
+
+/// The websocket subscription events are followed with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// `logsSubscribe`: notifies the slots of spool transactions, whose blocks are then fetched
+    /// for the instruction data. Served by every RPC node.
+    #[default]
+    Logs,
+    /// `blockSubscribe`: pushes the blocks with spool transactions. Nodes only serve it when
+    /// started with `--rpc-pubsub-enable-block-subscription`.
+    Blocks,
+}
+
+/// Live events of the spool program, see `subscribe_events`. Dropping it ends the subscription.
+pub struct EventStream {
+    rx: mpsc::Receiver<Result<SpoolEvent>>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<SpoolEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Follows the spool program over the websocket endpoint `ws_url` of the node `rpc` talks to,
+/// yielding its events with their decoded instructions from `from_slot` on, or from the current
+/// slot. Must be called within a tokio runtime.
+///
+/// The websocket is reconnected when it drops, and the blocks confirmed in the meantime (and
+/// before the subscription, from `from_slot`) are backfilled with `get_blocks_with_limit`, so
+/// events are neither missed nor yielded twice. Events of a block come in execution order,
+/// blocks in the order they are notified. Failures to fetch or decode a block are yielded
+/// without ending the stream.
+pub fn subscribe_events<R: RpcBackend + ?Sized + 'static>(
+    rpc: Arc<R>,
+    ws_url: &str,
+    kind: SubscriptionKind,
+    from_slot: Option<u64>,
+) -> EventStream {
+    let (tx, rx) = mpsc::channel(EVENT_BUFFER_SIZE);
+    let follower = EventFollower {
+        rpc,
+        ws_url: ws_url.to_string(),
+        kind,
+        tx,
+        next_slot: 0,
+        processed: BTreeSet::new(),
+    };
+
+    tokio::spawn(follower.run(from_slot));
+
+    EventStream { rx }
+}
+
+struct EventFollower<R: ?Sized> {
+    rpc: Arc<R>,
+    ws_url: String,
+    kind: SubscriptionKind,
+    tx: mpsc::Sender<Result<SpoolEvent>>,
+    /// First slot the backfill starts from.
+    next_slot: u64,
+    /// Slots whose events were yielded, from `next_slot - PROCESSED_SLOT_WINDOW` on.
+    processed: BTreeSet<u64>,
+}
+
+impl<R: RpcBackend + ?Sized> EventFollower<R> {
+    async fn run(mut self, from_slot: Option<u64>) {
+        let tx = self.tx.clone();
+
+        tokio::select! {
+            _ = tx.closed() => debug!("Event stream dropped, unsubscribing"),
+            res = self.follow(from_slot) => {
+                if let Err(e) = res {
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+        }
+    }
+
+    /// Follows the program until the stream is dropped, reconnecting when the websocket drops.
+    async fn follow(&mut self, from_slot: Option<u64>) -> Result<()> {
+        self.next_slot = match from_slot {
+            Some(slot) => slot,
+            None => get_slot(&self.rpc).await?,
+        };
+
+        loop {
+            if let Err(e) = self.subscribe().await {
+                warn!("Event subscription to {} dropped: {}", self.ws_url, e);
+                self.send(Err(e)).await?;
+            }
+
+            sleep(Duration::from_secs(SUBSCRIBE_RECONNECT_DELAY)).await;
+        }
+    }
+
+    /// Subscribes over a new connection, backfills the slots since `next_slot` and yields the
+    /// notified events until the connection drops.
+    async fn subscribe(&mut self) -> Result<()> {
+        let pubsub = PubsubClient::new(&self.ws_url)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to {}: {}", self.ws_url, e))?;
+
+        match self.kind {
+            SubscriptionKind::Logs => {
+                let (mut notifications, _unsubscribe) = pubsub
+                    .logs_subscribe(
+                        RpcTransactionLogsFilter::Mentions(vec![spool_api::ID.to_string()]),
+                        RpcTransactionLogsConfig {
+                            commitment: Some(CommitmentConfig::confirmed()),
+                        },
+                    )
+                    .await
+                    .map_err(|e| anyhow!("Failed to subscribe to logs: {}", e))?;
+
+                self.backfill().await?;
+
+                while let Some(notification) = notifications.next().await {
+                    // Failed transactions don't emit events
+                    if notification.value.err.is_none() {
+                        self.process_slot(notification.context.slot, None).await?;
+                    }
+                }
+            }
+
+            SubscriptionKind::Blocks => {
+                let config = RpcBlockSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    encoding: Some(UiTransactionEncoding::Json),
+                    transaction_details: Some(TransactionDetails::Full),
+                    show_rewards: Some(false),
+                    max_supported_transaction_version: Some(0),
+                };
+                let (mut notifications, _unsubscribe) = pubsub
+                    .block_subscribe(
+                        RpcBlockSubscribeFilter::MentionsAccountOrProgram(spool_api::ID.to_string()),
+                        Some(config),
+                    )
+                    .await
+                    .map_err(|e| anyhow!("Failed to subscribe to blocks: {}", e))?;
+
+                self.backfill().await?;
+
+                while let Some(notification) = notifications.next().await {
+                    // Blocks the node couldn't encode are fetched instead
+                    let update = notification.value;
+                    self.process_slot(update.slot, update.block).await?;
+                }
+            }
+        }
+
+        Err(anyhow!("Subscription closed by {}", self.ws_url))
+    }
+
+    /// Yields the events of the blocks confirmed from `next_slot` on.
+    async fn backfill(&mut self) -> Result<()> {
+        let mut start = self.next_slot;
+
+        loop {
+            let slots = get_blocks_with_limit(&self.rpc, start, BACKFILL_BLOCKS_PER_REQUEST).await?;
+            debug!("Backfilling {} blocks from slot {}", slots.len(), start);
+
+            let count = slots.len();
+            for slot in slots {
+                start = slot + 1;
+                self.process_slot(slot, None).await?;
+            }
+
+            if count < BACKFILL_BLOCKS_PER_REQUEST {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Yields the events of the block of `slot`, fetching it if `block` is `None`, unless they
+    /// were already yielded. The slot is only recorded once its events are sent, so a block
+    /// that couldn't be fetched or decoded is processed again if it is notified again.
+    async fn process_slot(&mut self, slot: u64, block: Option<UiConfirmedBlock>) -> Result<()> {
+        if slot + PROCESSED_SLOT_WINDOW < self.next_slot || self.processed.contains(&slot) {
+            return Ok(());
+        }
+
+        let block = match block {
+            Some(block) => Ok(block),
+            None => get_block_by_number(&self.rpc, slot, TransactionDetails::Full).await,
+        };
+        let events = block.and_then(|block| {
+            process_block_events(block, slot)
+                .map_err(|e| anyhow::Error::new(e).context(format!("Failed to decode the events of slot {slot}")))
+        });
+
+        let events = match events {
+            Ok(events) => events,
+            Err(e) => return self.send(Err(e)).await,
+        };
+        for event in events {
+            self.send(Ok(event)).await?;
+        }
+
+        self.processed.insert(slot);
+        self.next_slot = self.next_slot.max(slot + 1);
+        self.processed = self
+            .processed
+            .split_off(&self.next_slot.saturating_sub(PROCESSED_SLOT_WINDOW));
+
+        Ok(())
+    }
+
+    async fn send(&self, item: Result<SpoolEvent>) -> Result<()> {
+        self.tx
+            .send(item)
+            .await
+            .map_err(|_| anyhow!("Event stream dropped"))
+    }
+}
+
+#[cfg(all(test, feature = "litesvm"))]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use litesvm::LiteSVM;
+    use solana_sdk::{
+        message::Message,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+    use spool_api::prelude::*;
+    use spool_api::instruction::spool::build_write_ix;
+
+    use crate::backend::LiteSvmBackend;
+    use crate::utils::{EventData, InstructionData};
+    use crate::SpoolClient;
+
+    fn follower<R: RpcBackend>(
+        rpc: Arc<R>,
+        tx: mpsc::Sender<Result<SpoolEvent>>,
+        next_slot: u64,
+    ) -> EventFollower<R> {
+        EventFollower {
+            rpc,
+            ws_url: String::new(),
+            kind: SubscriptionKind::Logs,
+            tx,
+            next_slot,
+            processed: BTreeSet::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill() {
+        let backend = Arc::new(LiteSvmBackend::new(LiteSVM::new()));
+        let payer = Keypair::new();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let start = backend.get_slot().await.unwrap();
+        for _ in 0..3 {
+            let blockhash = backend.with_svm(|svm| svm.latest_blockhash());
+            let ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000_000);
+            let tx = Transaction::new(&[&payer], Message::new(&[ix], Some(&payer.pubkey())), blockhash);
+            backend.send_and_confirm_transaction(&tx.into()).await.unwrap();
+        }
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut follower = follower(backend, tx, start);
+
+        follower.backfill().await.unwrap();
+        assert_eq!(follower.next_slot, start + 4);
+        assert_eq!(follower.processed, BTreeSet::from([start + 1, start + 2, start + 3]));
+
+        // Late notifications of backfilled slots are skipped
+        follower.process_slot(start + 2, None).await.unwrap();
+        assert_eq!(follower.next_slot, start + 4);
+        assert_eq!(follower.processed.len(), 3);
+
+        // Transfers emit no spool events
+        drop(follower);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_slots_are_not_recorded() {
+        let backend = Arc::new(LiteSvmBackend::new(LiteSVM::new()));
+        let start = backend.get_slot().await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut follower = follower(backend, tx, start);
+
+        // The slot was skipped, fetching its block fails
+        follower.process_slot(start, None).await.unwrap();
+        assert!(rx.recv().await.unwrap().is_err());
+        assert_eq!(follower.next_slot, start);
+        assert!(follower.processed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_yields_decoded_events() {
+        let mut so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        so_path.push("../target/deploy/spool.so");
+        let mut svm = LiteSVM::new();
+        svm.add_program(spool_api::ID, &std::fs::read(so_path).unwrap());
+
+        let backend = Arc::new(LiteSvmBackend::new(svm));
+        let payer = Keypair::new();
+        backend.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let client = SpoolClient::new(backend.clone(), payer);
+        let (spool_address, writer_address, _) = client.create_spool("events").await.unwrap();
+
+        let start = backend.get_slot().await.unwrap();
+        let data = vec![7; 2 * SEGMENT_SIZE];
+        let write_ix = build_write_ix(client.pubkey(), spool_address, writer_address, &data);
+        let signature = client.send_and_confirm(&[write_ix], None, &[]).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut follower = follower(backend, tx, start + 1);
+        follower.backfill().await.unwrap();
+        assert_eq!(follower.processed, BTreeSet::from([start + 1]));
+        drop(follower);
+
+        let event = rx.recv().await.unwrap().unwrap();
+        assert_eq!(event.slot, start + 1);
+        assert_eq!(event.signature, signature);
+        assert!(matches!(event.event, EventData::Write(write) if write.address == spool_address.to_bytes()));
+        match event.instruction {
+            InstructionData::Write { address, data: written } => {
+                assert_eq!(address, spool_address);
+                assert_eq!(written, data);
+            }
+            other => panic!("Expected a write instruction, got {other:?}"),
+        }
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -1,39 +1,221 @@
 #![allow(unexpected_cfgs)]
-#![allow(unused)]
 
-use spool_api::instruction::spool::*;
+use spool_api::prelude::*;
+use spool_api::cpi;
 use steel::*;
 
-declare_id!("Gzuu6orA9tz2ifE7zyupNiuhogYkRBmbuQpWJme5dGhJ"); 
+declare_id!("Gzuu6orA9tz2ifE7zyupNiuhogYkRBmbuQpWJme5dGhJ");
+
+/// Seed of the PDA that owns this program's spools.
+pub const OWNER: &[u8] = b"owner";
+
+/// Derive the PDA that owns this program's spools.
+pub fn owner_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OWNER], &crate::ID)
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+pub enum ExampleInstruction {
+    Create = 0, // Create a spool owned by the program
+    Write,      // Write data to the spool
+    Update,     // Update a segment of the spool
+    SetHeader,  // Set the header of the spool
+    Finalize,   // Finalize the spool
+}
+
+instruction!(ExampleInstruction, Create);
+instruction!(ExampleInstruction, Write);
+instruction!(ExampleInstruction, Update);
+instruction!(ExampleInstruction, SetHeader);
+instruction!(ExampleInstruction, Finalize);
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Create {
+    pub name: [u8; NAME_LEN],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Write {
+    // Phantom Vec<u8> to ensure the size is dynamic
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Update {
+    pub segment_number: [u8; 8],
+    pub old_data: [u8; SEGMENT_SIZE],
+    pub new_data: [u8; SEGMENT_SIZE],
+    pub proof: ProofPath,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetHeader {
+    pub header: [u8; HEADER_SIZE],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Finalize {}
 
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
+    let (ix, data) = parse_instruction::<ExampleInstruction>(&crate::ID, program_id, data)?;
+
+    solana_program::msg!("<Your program functionality here>");
+
+    match ix {
+        ExampleInstruction::Create => process_create(accounts, data)?,
+        ExampleInstruction::Write => process_write(accounts, data)?,
+        ExampleInstruction::Update => process_update(accounts, data)?,
+        ExampleInstruction::SetHeader => process_set_header(accounts, data)?,
+        ExampleInstruction::Finalize => process_finalize(accounts, data)?,
+    }
+
+    Ok(())
+}
+
+fn process_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = Create::try_from_bytes(data)?;
     let [
-        signer_info, 
+        payer_info,
+        owner_info,
         spool_info,
-        writer_info, 
+        writer_info,
+        system_program_info,
+        rent_sysvar_info,
         spool_program_info,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    solana_program::msg!("<Your program functionality here>");
+    let (_owner_address, owner_bump) = owner_pda();
 
-    let your_data = vec![42; 1024]; // (you can be creative here)
-    let ix = &build_write_ix(
-        *signer_info.key,
-        *spool_info.key,
-        *writer_info.key,
-        &your_data
-    );
+    // The payer funds the spool; the owner PDA signs as its authority
+    cpi::create(
+        payer_info,
+        owner_info,
+        spool_info,
+        writer_info,
+        system_program_info,
+        rent_sysvar_info,
+        spool_program_info,
+        &from_name(&args.name),
+        &[&[OWNER, &[owner_bump]]],
+    )
+}
+
+fn process_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let [
+        owner_info,
+        spool_info,
+        writer_info,
+        spool_program_info,
+        ownership_info @ ..,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
 
-    solana_program::program::invoke(ix, accounts);
+    let (_owner_address, owner_bump) = owner_pda();
 
-    Ok(())
+    cpi::write(
+        owner_info,
+        spool_info,
+        writer_info,
+        ownership_info.first(),
+        spool_program_info,
+        data,
+        &[&[OWNER, &[owner_bump]]],
+    )
 }
 
-entrypoint!(process_instruction);
+fn process_update(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = Update::try_from_bytes(data)?;
+    let [
+        owner_info,
+        spool_info,
+        writer_info,
+        spool_program_info,
+        ownership_info @ ..,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let (_owner_address, owner_bump) = owner_pda();
+
+    cpi::update(
+        owner_info,
+        spool_info,
+        writer_info,
+        ownership_info.first(),
+        spool_program_info,
+        u64::from_le_bytes(args.segment_number),
+        args.old_data,
+        args.new_data,
+        args.proof,
+        &[&[OWNER, &[owner_bump]]],
+    )
+}
+
+fn process_set_header(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetHeader::try_from_bytes(data)?;
+    let [
+        owner_info,
+        spool_info,
+        spool_program_info,
+        ownership_info @ ..,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
 
+    let (_owner_address, owner_bump) = owner_pda();
+
+    cpi::set_header(
+        owner_info,
+        spool_info,
+        ownership_info.first(),
+        spool_program_info,
+        &args.header,
+        &[&[OWNER, &[owner_bump]]],
+    )
+}
+
+fn process_finalize(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let _args = Finalize::try_from_bytes(data)?;
+    let [
+        payer_info,
+        owner_info,
+        spool_info,
+        writer_info,
+        archive_info,
+        system_program_info,
+        rent_sysvar_info,
+        spool_program_info,
+        ownership_info @ ..,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let (_owner_address, owner_bump) = owner_pda();
+
+    cpi::finalize(
+        payer_info,
+        owner_info,
+        spool_info,
+        writer_info,
+        archive_info,
+        system_program_info,
+        rent_sysvar_info,
+        ownership_info.first(),
+        spool_program_info,
+        &[&[OWNER, &[owner_bump]]],
+    )
+}
+
+entrypoint!(process_instruction);
@@ -7,7 +7,7 @@ use steel::*;
 use spool_api::prelude::*;
 
 use solana_sdk::{
-    signature::Keypair, 
+    signature::Keypair,
     signer::Signer,
     transaction::Transaction,
     clock::Clock,
@@ -23,34 +23,30 @@ fn run_integration() {
     let payer = create_payer(&mut svm);
     let payer_pk = payer.pubkey();
 
-    // Create a spool that we can write to
-    let (spool_address, writer_address) = create_spool(&mut svm, &payer, "spool-name");
-
-    // Call our example program
-    let ix = Instruction {
-        program_id: example::ID,
-        accounts: vec![
-            AccountMeta::new(payer_pk, true),
-            AccountMeta::new(spool_address, false),
-            AccountMeta::new(writer_address, false),
-            AccountMeta::new_readonly(spool_api::ID, false),
-        ],
-        data: vec![],
-    };
+    // The spool is owned by a PDA of our example program, not by the payer
+    let (owner_address, _owner_bump) = example::owner_pda();
+    let (spool_address, _spool_bump) = spool_pda(owner_address, &to_name("spool-name"));
+    let (writer_address, _writer_bump) = writer_pda(spool_address);
 
-    let blockhash = svm.latest_blockhash();
-    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&payer], blockhash);
-    let res = send_tx(&mut svm, tx);
+    // Create the spool through our example program
+    let ix = build_example_create_ix(payer_pk, "spool-name");
+    let res = send_ix(&mut svm, &payer, ix);
     assert!(res.is_ok());
 
-    // Verify the on-chain state matches the local expectations
-
     let account = svm.get_account(&spool_address).unwrap();
     let spool = Spool::unpack(&account.data).unwrap();
+    assert_eq!(spool.authority, owner_address);
+    assert_eq!(spool.state, u64::from(SpoolState::Created));
 
-    let mut local_tree = SegmentTree::new(&[spool_address.as_ref()]); 
+    // Write to the spool through our example program
+    let data = vec![42; 1024]; // (you can be creative here)
+    let ix = build_example_write_ix(spool_address, writer_address, &data);
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_ok());
 
-    let data = vec![42; 1024];
+    // Verify the on-chain state matches the local expectations
+    let mut local_tree = SegmentTree::new(&[spool_address.as_ref()]);
+    let mut leaves = Vec::new();
 
     let segments = data.chunks(SEGMENT_SIZE);
     for (segment_number, segment) in segments.enumerate() {
@@ -61,8 +57,80 @@ fn run_integration() {
             segment_number as u64,
             &canonical_segment,
         ).is_ok());
+
+        leaves.push(compute_leaf(segment_number as u64, &canonical_segment));
     }
 
+    let account = svm.get_account(&spool_address).unwrap();
+    let spool = Spool::unpack(&account.data).unwrap();
     assert_eq!(spool.total_segments, 1024 / SEGMENT_SIZE as u64);
     assert_eq!(spool.merkle_root, local_tree.get_root().as_ref());
+
+    // The payer can't write to a spool it doesn't own
+    let ix = spool_api::instruction::spool::build_write_ix(
+        payer_pk,
+        spool_address,
+        writer_address,
+        &data,
+    );
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_err());
+
+    // Update the first segment through our example program
+    let proof_nodes: Vec<[u8; 32]> = local_tree
+        .get_proof(&leaves, 0)
+        .into_iter()
+        .map(|h| h.to_bytes())
+        .collect();
+    let proof = ProofPath::from_slice(&proof_nodes).unwrap();
+
+    let old_data = padded_array::<SEGMENT_SIZE>(&data);
+    let new_data = padded_array::<SEGMENT_SIZE>(b"<updated>");
+    let ix = build_example_update_ix(spool_address, writer_address, 0, old_data, new_data, proof);
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_ok());
+
+    assert!(update_segment(&mut local_tree, 0, &old_data, &new_data, &proof).is_ok());
+
+    let account = svm.get_account(&spool_address).unwrap();
+    let spool = Spool::unpack(&account.data).unwrap();
+    assert_eq!(spool.merkle_root, local_tree.get_root().as_ref());
+
+    // Set the header through our example program
+    let header = [7; HEADER_SIZE];
+    let ix = build_example_set_header_ix(spool_address, header);
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_ok());
+
+    // Anyone can subsidize the spool, so the payer does it directly
+    let ata = create_ata(&mut svm, &payer, &MINT_ADDRESS, &payer_pk);
+    let min_rent = min_finalization_rent(spool.total_segments);
+    set_ata_balance(&mut svm, &ata, min_rent);
+
+    let ix = spool_api::instruction::spool::build_subsidize_ix(payer_pk, ata, spool_address, min_rent);
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_ok());
+
+    // Finalize through our example program
+    let ix = build_example_finalize_ix(payer_pk, spool_address, writer_address);
+    let res = send_ix(&mut svm, &payer, ix);
+    assert!(res.is_ok());
+
+    let account = svm.get_account(&spool_address).unwrap();
+    let spool = Spool::unpack(&account.data).unwrap();
+    assert_eq!(spool.state, u64::from(SpoolState::Finalized));
+    assert_eq!(spool.header, header);
+    assert_eq!(spool.merkle_root, local_tree.get_root().as_ref());
+
+    let account = svm.get_account(&writer_address).unwrap();
+    assert!(account.data.is_empty());
+}
+
+#[allow(clippy::result_large_err)]
+fn send_ix(svm: &mut LiteSVM, payer: &Keypair, ix: Instruction) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    svm.expire_blockhash();
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+    send_tx(svm, tx)
 }
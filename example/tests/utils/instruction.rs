@@ -0,0 +1,114 @@
+use solana_sdk::pubkey::Pubkey;
+use steel::*;
+
+use spool_api::prelude::*;
+use example::{
+    owner_pda,
+    Create,
+    Write,
+    Update,
+    SetHeader,
+    Finalize,
+};
+
+pub fn build_example_create_ix(payer: Pubkey, name: &str) -> Instruction {
+    let (owner_address, _owner_bump) = owner_pda();
+    let (spool_address, _spool_bump) = spool_pda(owner_address, &to_name(name));
+    let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+    Instruction {
+        program_id: example::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(owner_address, false),
+            AccountMeta::new(spool_address, false),
+            AccountMeta::new(writer_address, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+            AccountMeta::new_readonly(spool_api::ID, false),
+        ],
+        data: Create {
+            name: to_name(name),
+        }.to_bytes(),
+    }
+}
+
+pub fn build_example_write_ix(spool: Pubkey, writer: Pubkey, data: &[u8]) -> Instruction {
+    let (owner_address, _owner_bump) = owner_pda();
+
+    let mut ix_data = Write {}.to_bytes();
+    ix_data.extend_from_slice(data);
+
+    Instruction {
+        program_id: example::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(owner_address, false),
+            AccountMeta::new(spool, false),
+            AccountMeta::new(writer, false),
+            AccountMeta::new_readonly(spool_api::ID, false),
+        ],
+        data: ix_data,
+    }
+}
+
+pub fn build_example_update_ix(
+    spool: Pubkey,
+    writer: Pubkey,
+    segment_number: u64,
+    old_data: [u8; SEGMENT_SIZE],
+    new_data: [u8; SEGMENT_SIZE],
+    proof: ProofPath,
+) -> Instruction {
+    let (owner_address, _owner_bump) = owner_pda();
+
+    Instruction {
+        program_id: example::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(owner_address, false),
+            AccountMeta::new(spool, false),
+            AccountMeta::new(writer, false),
+            AccountMeta::new_readonly(spool_api::ID, false),
+        ],
+        data: Update {
+            segment_number: segment_number.to_le_bytes(),
+            old_data,
+            new_data,
+            proof,
+        }.to_bytes(),
+    }
+}
+
+pub fn build_example_set_header_ix(spool: Pubkey, header: [u8; HEADER_SIZE]) -> Instruction {
+    let (owner_address, _owner_bump) = owner_pda();
+
+    Instruction {
+        program_id: example::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(owner_address, false),
+            AccountMeta::new(spool, false),
+            AccountMeta::new_readonly(spool_api::ID, false),
+        ],
+        data: SetHeader {
+            header,
+        }.to_bytes(),
+    }
+}
+
+pub fn build_example_finalize_ix(payer: Pubkey, spool: Pubkey, writer: Pubkey) -> Instruction {
+    let (owner_address, _owner_bump) = owner_pda();
+
+    Instruction {
+        program_id: example::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(owner_address, false),
+            AccountMeta::new(spool, false),
+            AccountMeta::new(writer, false),
+            AccountMeta::new(ARCHIVE_ADDRESS, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+            AccountMeta::new_readonly(spool_api::ID, false),
+        ],
+        data: Finalize {}.to_bytes(),
+    }
+}
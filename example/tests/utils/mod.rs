@@ -1,7 +1,9 @@
 pub mod svm;
 pub mod token;
 pub mod spool;
+pub mod instruction;
 
 pub use svm::*;
 pub use token::*;
 pub use spool::*;
+pub use instruction::*;
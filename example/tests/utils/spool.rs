@@ -33,7 +33,7 @@ pub fn create_spool(svm: &mut LiteSVM, payer: &Keypair, spool_name: &str) -> (Pu
     let (writer_address, _) = writer_pda(spool_address);
 
     let blockhash = svm.latest_blockhash();
-    let ix = build_create_ix(payer_pk, spool_name);
+    let ix = build_create_ix(payer_pk, payer_pk, spool_name);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&payer], blockhash);
     let res = send_tx(svm, tx);
     assert!(res.is_ok());
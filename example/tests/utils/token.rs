@@ -1,4 +1,4 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signature::Keypair};
 use litesvm::{types::FailedTransactionMetadata, LiteSVM};
 use litesvm_token::{
     CreateAssociatedTokenAccount, 
@@ -28,6 +28,14 @@ pub fn get_ata_balance(svm: &LiteSVM, ata: &Pubkey) -> u64 {
     info.amount
 }
 
+pub fn set_ata_balance(svm: &mut LiteSVM, ata: &Pubkey, amount: u64) {
+    let mut account = svm.get_account(ata).unwrap();
+    let mut info : Account = get_spl_account(svm, ata).unwrap();
+    info.amount = amount;
+    info.pack_into_slice(&mut account.data);
+    svm.set_account(*ata, account).unwrap();
+}
+
 pub fn get_mint(svm: &LiteSVM, mint: &Pubkey) -> Mint {
     get_spl_account(svm, mint).unwrap()
 }
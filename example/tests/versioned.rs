@@ -0,0 +1,126 @@
+#![cfg(test)]
+#![allow(unused)]
+
+pub mod utils;
+use utils::*;
+use spool_api::prelude::*;
+use spool_api::instruction::spool::build_write_ix;
+use spool_client::{build_versioned_tx, segments_per_write, with_compute_budget};
+
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        AddressLookupTableAccount,
+    },
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use litesvm::LiteSVM;
+
+#[test]
+fn run_versioned_writes() {
+    let mut svm = setup_svm();
+
+    let payer = create_payer(&mut svm);
+    let payer_pk = payer.pubkey();
+
+    // The same payload goes to one spool with legacy transactions and to another with v0
+    // transactions loading the spool and writer accounts through a lookup table
+    let (legacy_spool, legacy_writer) = create_spool(&mut svm, &payer, "legacy");
+    let (table_spool, table_writer) = create_spool(&mut svm, &payer, "lookup-table");
+
+    let (create_table_ix, table_address) = create_lookup_table(payer_pk, payer_pk, 0);
+    let extend_ix = extend_lookup_table(
+        table_address,
+        payer_pk,
+        Some(payer_pk),
+        vec![table_spool, table_writer],
+    );
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &[create_table_ix, extend_ix],
+        Some(&payer_pk),
+        &[&payer],
+        blockhash,
+    );
+    assert!(send_tx(&mut svm, tx).is_ok());
+    svm.warp_to_slot(1); // the table can't be used in the slot it was extended in
+
+    let table = AddressLookupTableAccount {
+        key: table_address,
+        addresses: vec![table_spool, table_writer],
+    };
+
+    let data: Vec<u8> = (0..100 * SEGMENT_SIZE).map(|i| (i % 251) as u8).collect();
+
+    // The table trims every write, but not by enough to fit another segment
+    let legacy_segments = segments_per_write(payer_pk, legacy_spool, legacy_writer, None);
+    let table_segments = segments_per_write(payer_pk, table_spool, table_writer, Some(&table));
+    assert_eq!(table_segments, legacy_segments);
+
+    let legacy_sizes = write_payload(&mut svm, &payer, legacy_spool, legacy_writer, &data, legacy_segments, None);
+    let table_sizes = write_payload(&mut svm, &payer, table_spool, table_writer, &data, table_segments, Some(&table));
+
+    for sizes in [&legacy_sizes, &table_sizes] {
+        assert!(sizes.iter().all(|size| *size <= PACKET_DATA_SIZE));
+    }
+    assert_eq!(table_sizes.len(), legacy_sizes.len());
+    for (table_size, legacy_size) in table_sizes.iter().zip(&legacy_sizes) {
+        assert!(table_size < legacy_size);
+        assert!(legacy_size - table_size < SEGMENT_SIZE);
+    }
+
+    for spool_address in [legacy_spool, table_spool] {
+        let mut local_tree = SegmentTree::new(&[spool_address.as_ref()]);
+        for (segment_number, segment) in data.chunks(SEGMENT_SIZE).enumerate() {
+            let canonical_segment = padded_array::<SEGMENT_SIZE>(segment);
+            assert!(write_segment(&mut local_tree, segment_number as u64, &canonical_segment).is_ok());
+        }
+
+        let account = svm.get_account(&spool_address).unwrap();
+        let spool = Spool::unpack(&account.data).unwrap();
+        assert_eq!(spool.total_segments, 100);
+        assert_eq!(spool.merkle_root, local_tree.get_root().as_ref());
+    }
+}
+
+/// Writes `data` to the spool `segments` segments at a time, with the compute budget instructions
+/// `SpoolClient` adds, and returns the size of every transaction. Legacy transactions are sent
+/// without a table.
+fn write_payload(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    spool_address: Pubkey,
+    writer_address: Pubkey,
+    data: &[u8],
+    segments: usize,
+    table: Option<&AddressLookupTableAccount>,
+) -> Vec<usize> {
+    let payer_pk = payer.pubkey();
+    let mut sizes = Vec::new();
+
+    for chunk in data.chunks(segments * SEGMENT_SIZE) {
+        let write_ix = build_write_ix(payer_pk, spool_address, writer_address, chunk);
+        let ixs = with_compute_budget(&[write_ix], Some(200_000), Some(1));
+
+        svm.expire_blockhash();
+        let blockhash = svm.latest_blockhash();
+        let tx: VersionedTransaction = match table {
+            Some(table) => {
+                build_versioned_tx(&ixs, &payer_pk, &[payer], std::slice::from_ref(table), blockhash)
+                    .unwrap()
+            }
+            None => Transaction::new_signed_with_payer(&ixs, Some(&payer_pk), &[payer], blockhash)
+                .into(),
+        };
+
+        sizes.push(bincode::serialize(&tx).unwrap().len());
+        assert!(svm.send_transaction(tx).is_ok());
+    }
+
+    sizes
+}
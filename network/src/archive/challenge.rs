@@ -1,10 +1,9 @@
 use std::sync::Arc;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 
 use spool_api::prelude::*;
 use spool_client::{
-    get_block_account, get_miner_account, get_epoch_account, get_spool_account
+    get_block_account, get_miner_account, get_epoch_account, get_spool_account, RpcBackend
 };
 
 use crate::store::*;
@@ -13,8 +12,8 @@ use super::sync::sync_segments_from_solana;
 use super::helpers;
 
 /// Orchestrator Task B – periodic miner-challenge sync.
-pub async fn run(
-    rpc: Arc<RpcClient>,
+pub async fn run<R: RpcBackend>(
+    rpc: Arc<R>,
     store: Arc<SpoolStore>,
     miner_address: Pubkey,
     _trusted_peer: Option<String>,
@@ -1,16 +1,15 @@
 use anyhow::{anyhow, Result};
 use std::sync::Arc;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_transaction_status_client_types::TransactionDetails;
 use spool_client::utils::process_block;
-use spool_client::{get_block_by_number, get_slot};
+use spool_client::{get_block_by_number, get_slot, RpcBackend};
 
 use crate::store::*;
 use super::queue::{Tx, SegmentJob};
 
 /// Orchestrator Task A – stream live blocks and push raw segments into `tx`.
-pub async fn run(
-    rpc: Arc<RpcClient>,
+pub async fn run<R: RpcBackend>(
+    rpc: Arc<R>,
     store: Arc<SpoolStore>,
     tx: Tx
 ) -> Result<()> {
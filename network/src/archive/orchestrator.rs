@@ -1,19 +1,20 @@
 use anyhow::Result;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use tokio::task::JoinSet;
 
+use spool_client::RpcBackend;
+
 use crate::store::SpoolStore;
 use crate::utils::wait_for_shutdown;
 use crate::metrics::{run_metrics_server, Process};
 use super::{ queue, live, challenge, pack, sync };
 
 /// Orchestrator for the archive processing tasks.
-pub async fn run(
+pub async fn run<R: RpcBackend + 'static>(
     miner: Pubkey, 
     store: Arc<SpoolStore>, 
-    rpc: Arc<RpcClient>,
+    rpc: Arc<R>,
     trusted_peer: Option<String>,
 ) -> Result<()> {
     let (tx, rx) = queue::channel();
@@ -38,9 +39,9 @@ pub async fn run(
     wait_for_shutdown(tasks).await
 }
 
-pub async fn init(
+pub async fn init<R: RpcBackend + 'static>(
     store: &Arc<SpoolStore>,
-    client: &Arc<RpcClient>,
+    client: &Arc<R>,
     trusted_peer: Option<String>,
 ) ->Result<()> {
     run_metrics_server(Process::Archive)?;
@@ -3,8 +3,7 @@ use anyhow::{anyhow, Result};
 use solana_sdk::pubkey::Pubkey;
 use brine_tree::{Leaf, Hash, MerkleTree};
 use spool_api::prelude::*;
-use spool_client::get_epoch_account;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use spool_client::{get_epoch_account, RpcBackend};
 use packx::{solve_with_memory, build_memory, SolverMemory};
 
 use crate::store::*;
@@ -13,7 +12,7 @@ use super::queue::Rx;
 type CanopyTree = MerkleTree<{ SEGMENT_TREE_HEIGHT - SECTOR_TREE_HEIGHT }>;
 
 /// Orchestrator Task C – CPU-heavy preprocessing (packx)
-pub async fn run(rpc: Arc<RpcClient>, mut rx: Rx, miner: Pubkey, store: Arc<SpoolStore>) -> Result<()> {
+pub async fn run<R: RpcBackend>(rpc: Arc<R>, mut rx: Rx, miner: Pubkey, store: Arc<SpoolStore>) -> Result<()> {
     let epoch = get_epoch_account(&rpc).await?.0;
     let packing_difficulty = epoch.packing_difficulty;
     let miner_bytes = miner.to_bytes();
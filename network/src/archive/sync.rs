@@ -1,5 +1,4 @@
 use anyhow::{anyhow, Result};
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_transaction_status_client_types::TransactionDetails;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
@@ -9,7 +8,7 @@ use tokio::task::JoinSet;
 use spool_api::{SEGMENT_SIZE, state::SpoolState};
 use spool_client::{
     get_block_by_number, get_archive_account, get_spool_account, find_spool_account, init_read,
-    process_next_block, get_epoch_account
+    process_next_block, get_epoch_account, RpcBackend
 };
 use spool_client::utils::{process_block, ProcessedBlock};
 
@@ -19,9 +18,9 @@ use super::pack::pack_segment;
 use super::queue::{Tx, SegmentJob};
 
 /// Syncs missing spool addresses from either a trusted peer or Solana RPC.
-pub async fn get_spool_addresses(
+pub async fn get_spool_addresses<R: RpcBackend + 'static>(
     store: &Arc<SpoolStore>,
-    client: &Arc<RpcClient>,
+    client: &Arc<R>,
     trusted_peer: Option<String>,
 ) -> Result<()> {
     log::debug!("Syncing missing spool addresses");
@@ -41,7 +40,7 @@ pub async fn get_spool_addresses(
 /// Syncs segments from Solana RPC.
 pub async fn sync_segments_from_solana(
     store: &SpoolStore,
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     spool_address: &Pubkey,
     tx: &Tx,
 ) -> anyhow::Result<()> {
@@ -102,7 +101,7 @@ pub async fn sync_segments_from_solana(
 /// Syncs spool addresses from a trusted peer.
 pub async fn sync_addresses_from_trusted_peer(
     store: &Arc<SpoolStore>,
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     trusted_peer_url: &str,
 ) -> Result<()> {
     let (archive, _) = get_archive_account(client).await?;
@@ -143,9 +142,9 @@ pub async fn sync_addresses_from_trusted_peer(
 }
 
 /// Syncs spool addresses from Solana RPC.
-pub async fn sync_addresses_from_solana(
+pub async fn sync_addresses_from_solana<R: RpcBackend + 'static>(
     store: &Arc<SpoolStore>,
-    client: &Arc<RpcClient>
+    client: &Arc<R>
     ) -> Result<()> {
     let (archive, _) = get_archive_account(client).await?;
     let total = archive.spools_stored;
@@ -186,7 +185,7 @@ pub async fn sync_addresses_from_solana(
 /// Syncs block data for a specific spool address starting from a given slot.
 pub async fn sync_from_block(
     store: &Arc<SpoolStore>,
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     spool_address: &Pubkey,
     miner_address: &Pubkey,
     starting_slot: u64,
@@ -217,6 +216,7 @@ pub async fn sync_from_block(
             segment_writes,
             slot,
             finalized_spools,
+            ..
         } = process_block(block, current_slot)?;
 
         log::debug!(
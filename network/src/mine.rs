@@ -2,9 +2,9 @@ use num_cpus;
 use anyhow::{anyhow, Result};
 use bytemuck::Zeroable;
 use log::{debug, error};
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{signature::Keypair, pubkey::Pubkey};
 use spool_client::mine::mine::perform_mining;
+use spool_client::RpcBackend;
 use tokio::time::{sleep, Duration};
 use brine_tree::{Hash, Leaf, get_cached_merkle_proof};
 
@@ -35,7 +35,7 @@ use spool_api::prelude::*;
 
 pub async fn mine_loop(
     store: SpoolStore, 
-    client: &Arc<RpcClient>, 
+    client: &Arc<impl RpcBackend + 'static>, 
     miner_address: &Pubkey,
     signer: &Keypair,
 ) -> Result<()> {
@@ -68,7 +68,7 @@ pub async fn mine_loop(
 }
 
 async fn get_mining_accounts(
-    client: &Arc<RpcClient>,
+    client: &impl RpcBackend,
     miner_address: &Pubkey
 ) -> Result<(Epoch, Block, Miner)> {
      
@@ -89,7 +89,7 @@ async fn get_mining_accounts(
 
 async fn try_mine_iteration(
     store: &SpoolStore,
-    client: &Arc<RpcClient>,
+    client: &Arc<impl RpcBackend + 'static>,
     miner_address: &Pubkey,
     signer: &Keypair,
 ) -> Result<()> {
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use litesvm::LiteSVM;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+};
+use tempdir::TempDir;
+use tokio::time::{sleep, Instant};
+
+use spool_api::prelude::*;
+use spool_client::{
+    get_miner_account,
+    mine::register::register_miner,
+    program::initialize,
+    LiteSvmBackend,
+};
+use spool_network::{
+    archive::orchestrator,
+    mine::mine_loop,
+    store::*,
+};
+
+/// How long the archive and the miner get to make progress.
+const DEADLINE: Duration = Duration::from_secs(60);
+
+fn program_bytes(path: &str) -> Vec<u8> {
+    let mut so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    so_path.push(path);
+    std::fs::read(so_path).unwrap()
+}
+
+/// A backend with the program initialized by `payer` and a miner registered by it.
+async fn setup() -> (Arc<LiteSvmBackend>, Keypair, Pubkey) {
+    let mut svm = LiteSVM::new();
+    svm.add_program(mpl_token_metadata::ID, &program_bytes("../program/tests/elfs/metadata.so"));
+    svm.add_program(spool_api::ID, &program_bytes("../target/deploy/spool.so"));
+
+    let backend = Arc::new(LiteSvmBackend::new(svm));
+    let payer = Keypair::new();
+    backend.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+
+    initialize(&backend, &payer).await.unwrap();
+
+    let name = "miner";
+    register_miner(&backend, &payer, name).await.unwrap();
+    let (miner_address, _) = miner_pda(payer.pubkey(), to_name(name));
+
+    (backend, payer, miner_address)
+}
+
+fn genesis_address(payer: &Keypair) -> Pubkey {
+    spool_pda(payer.pubkey(), &to_name("genesis")).0
+}
+
+/// Runs the archive until it has packed the genesis spool and cached its merkle layer.
+async fn archive_genesis(
+    backend: &Arc<LiteSvmBackend>,
+    payer: &Keypair,
+    miner_address: Pubkey,
+    path: &Path,
+) -> Arc<SpoolStore> {
+    let store = Arc::new(SpoolStore::new(path).unwrap());
+    let key = MerkleCacheKey::UnpackedSpoolLayer {
+        address: genesis_address(payer),
+        layer: SECTOR_TREE_HEIGHT as u8,
+    };
+
+    let archive = tokio::spawn(orchestrator::run(miner_address, store.clone(), backend.clone(), None));
+
+    let deadline = Instant::now() + DEADLINE;
+    while store.get_merkle_cache(&key).is_err() {
+        assert!(!archive.is_finished(), "The archive stopped");
+        assert!(Instant::now() < deadline, "The genesis spool wasn't archived");
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    archive.abort();
+    store
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_orchestrator_archives_genesis() {
+    let (backend, payer, miner_address) = setup().await;
+    let dir = TempDir::new("archive").unwrap();
+    let genesis = genesis_address(&payer);
+
+    let store = Arc::new(SpoolStore::new(dir.path()).unwrap());
+    orchestrator::init(&store, &backend, None).await.unwrap();
+    assert_eq!(store.get_spool_address(1).unwrap(), genesis);
+    drop(store);
+
+    let store = archive_genesis(&backend, &payer, miner_address, dir.path()).await;
+    assert_eq!(store.get_spool_address(1).unwrap(), genesis);
+    assert!(store.get_segment(&genesis, 0).is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mine_loop_submits_proofs() {
+    let (backend, payer, miner_address) = setup().await;
+    let dir = TempDir::new("archive").unwrap();
+    let secondary = TempDir::new("mine").unwrap();
+
+    let _primary = archive_genesis(&backend, &payer, miner_address, dir.path()).await;
+    let store = SpoolStore::new_secondary(dir.path(), secondary.path()).unwrap();
+
+    let miner = {
+        let backend = backend.clone();
+        let payer = payer.insecure_clone();
+        tokio::spawn(async move { mine_loop(store, &backend, &miner_address, &payer).await })
+    };
+
+    let deadline = Instant::now() + DEADLINE;
+    loop {
+        let (account, _) = get_miner_account(&backend, &miner_address).await.unwrap();
+        if account.total_proofs > 0 {
+            break;
+        }
+
+        assert!(!miner.is_finished(), "The mine loop stopped");
+        assert!(Instant::now() < deadline, "No proof landed");
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    miner.abort();
+}
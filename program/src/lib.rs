@@ -44,6 +44,8 @@ pub fn process_instruction(
             ProgramInstruction::Initialize => process_initialize(accounts, data)?,
             #[cfg(feature = "airdrop")]
             ProgramInstruction::Airdrop => process_airdrop(accounts, data)?,
+            #[cfg(feature = "airdrop")]
+            ProgramInstruction::ConfigureFaucet => process_configure_faucet(accounts, data)?,
             _ => return Err(ProgramError::InvalidInstructionData),
         }
     } else if let Ok(ix) = SpoolInstruction::try_from_primitive(discriminator) {
@@ -54,6 +56,7 @@ pub fn process_instruction(
             SpoolInstruction::Finalize => process_spool_finalize(accounts, data)?,
             SpoolInstruction::SetHeader => process_spool_set_header(accounts, data)?,
             SpoolInstruction::Subsidize => process_spool_subsidize_rent(accounts, data)?,
+            SpoolInstruction::MintOwnershipToken => process_spool_mint_ownership_token(accounts, data)?,
         }
     } else if let Ok(ix) = MinerInstruction::try_from_primitive(discriminator) {
         match ix {
@@ -3,23 +3,35 @@ use spool_api::prelude::*;
 use spool_api::instruction::program::Airdrop;
 
 pub fn process_airdrop(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
     let args = Airdrop::try_from_bytes(data)?;
     let [
         signer_info,
         beneficiary_info,
+        faucet_claim_info,
+        faucet_config_info,
         mint_info,
         treasury_info,
         token_program_info,
+        system_program_info,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     // Verify signer
-    signer_info.is_signer()?;
+    signer_info.is_signer()?.is_writable()?;
 
     // Verify accounts
     let (mint_address, _mint_bump) = mint_pda();
     let (treasury_address, _treasury_bump) = treasury_pda();
+    let (faucet_claim_address, _faucet_claim_bump) = faucet_claim_pda(*signer_info.key);
+    let (faucet_config_address, _faucet_config_bump) = faucet_config_pda();
+
+    faucet_claim_info
+        .is_writable()?
+        .has_address(&faucet_claim_address)?;
+    faucet_config_info
+        .has_address(&faucet_config_address)?;
 
     mint_info
         .is_writable()?
@@ -29,6 +41,8 @@ pub fn process_airdrop(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResu
         .has_address(&treasury_address)?;
     token_program_info
         .is_program(&spl_token::ID)?;
+    system_program_info
+        .is_program(&system_program::ID)?;
 
     // Verify beneficiary is a valid ATA
     beneficiary_info
@@ -39,6 +53,42 @@ pub fn process_airdrop(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResu
     // Parse amount
     let amount = u64::from_le_bytes(args.amount);
 
+    // Create the claim tracker on the first airdrop for this wallet
+    if faucet_claim_info.data_is_empty() {
+        create_program_account::<FaucetClaim>(
+            faucet_claim_info,
+            system_program_info,
+            signer_info,
+            &spool_api::ID,
+            &[FAUCET, signer_info.key.as_ref()],
+        )?;
+
+        let faucet_claim = faucet_claim_info.as_account_mut::<FaucetClaim>(&spool_api::ID)?;
+        faucet_claim.authority     = *signer_info.key;
+        faucet_claim.window_start  = now;
+        faucet_claim.claimed       = 0;
+        faucet_claim.last_claim_at = 0;
+    }
+
+    // The default limits apply until the admin sets the faucet config
+    let faucet_config = if faucet_config_info.data_is_empty() {
+        FaucetConfig::DEFAULT
+    } else {
+        *faucet_config_info.as_account::<FaucetConfig>(&spool_api::ID)?
+    };
+
+    // Enforce the per-window cap and the cooldown between claims
+    let faucet_claim = faucet_claim_info
+        .as_account_mut::<FaucetClaim>(&spool_api::ID)?
+        .assert_mut(|c| c.authority == *signer_info.key)?;
+
+    check_condition(
+        faucet_claim.can_claim(&faucet_config, amount, now),
+        SpoolError::FaucetRateLimited,
+    )?;
+
+    faucet_claim.record_claim(&faucet_config, amount, now);
+
     // Mint tokens to beneficiary's ATA
     mint_to_signed(
         mint_info,
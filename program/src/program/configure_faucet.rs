@@ -0,0 +1,60 @@
+use steel::*;
+use spool_api::prelude::*;
+use spool_api::instruction::program::ConfigureFaucet;
+
+pub fn process_configure_faucet(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = ConfigureFaucet::try_from_bytes(data)?;
+    let [
+        signer_info,
+        genesis_spool_info,
+        faucet_config_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify signer
+    signer_info.is_signer()?.is_writable()?;
+
+    // Only the genesis spool authority (the wallet that initialized the program) is the admin
+    genesis_spool_info
+        .as_account::<Spool>(&spool_api::ID)?
+        .assert(|s| s.number == GENESIS_SPOOL_NUMBER)?
+        .assert(|s| s.authority == *signer_info.key)?;
+
+    // Verify accounts
+    let (faucet_config_address, _faucet_config_bump) = faucet_config_pda();
+
+    faucet_config_info
+        .is_writable()?
+        .has_address(&faucet_config_address)?;
+    system_program_info
+        .is_program(&system_program::ID)?;
+
+    let config = FaucetConfig {
+        cap:              u64::from_le_bytes(args.cap),
+        window_seconds:   i64::from_le_bytes(args.window_seconds),
+        cooldown_seconds: i64::from_le_bytes(args.cooldown_seconds),
+    };
+
+    check_condition(
+        config.is_valid(),
+        SpoolError::InvalidFaucetConfig,
+    )?;
+
+    // Create the config on first use
+    if faucet_config_info.data_is_empty() {
+        create_program_account::<FaucetConfig>(
+            faucet_config_info,
+            system_program_info,
+            signer_info,
+            &spool_api::ID,
+            &[FAUCET_CONFIG],
+        )?;
+    }
+
+    let faucet_config = faucet_config_info.as_account_mut::<FaucetConfig>(&spool_api::ID)?;
+    *faucet_config = config;
+
+    Ok(())
+}
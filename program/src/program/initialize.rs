@@ -235,6 +235,7 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
     // Create the spool
     invoke(
         &build_create_ix(
+            *signer_info.key,
             *signer_info.key,
             name,
         ),
@@ -282,6 +283,7 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
     // Finalize the spool
     invoke(
         &build_finalize_ix(
+            *signer_info.key,
             *signer_info.key,
             spool_address,
             writer_address,
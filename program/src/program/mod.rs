@@ -1,5 +1,7 @@
 pub mod initialize;
 pub mod airdrop;
+pub mod configure_faucet;
 
 pub use initialize::*;
 pub use airdrop::*;
+pub use configure_faucet::*;
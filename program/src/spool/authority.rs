@@ -0,0 +1,40 @@
+use spool_api::prelude::*;
+use steel::*;
+
+/// Checks that the signer controls the spool. Before an ownership token is minted the signer must
+/// be the spool authority itself; afterwards the authority is the ownership mint, and the signer
+/// must present a token account holding it.
+pub fn check_spool_authority(
+    spool: &Spool,
+    spool_info: &AccountInfo<'_>,
+    signer_info: &AccountInfo<'_>,
+    ownership_info: Option<&AccountInfo<'_>>,
+) -> ProgramResult {
+    if spool.authority == *signer_info.key {
+        let (spool_address, _spool_bump) = spool_pda(*signer_info.key, &spool.name);
+        spool_info.has_address(&spool_address)?;
+        return Ok(());
+    }
+
+    let Some(ownership_info) = ownership_info else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+
+    // The spool account itself has already been checked by the caller (owner and discriminator),
+    // so binding the mint to its address is enough to tie the token to this spool.
+    let (mint_address, _mint_bump) = ownership_pda(*spool_info.key);
+
+    check_condition(
+        spool.authority == mint_address,
+        SpoolError::InvalidOwnershipToken,
+    )?;
+
+    ownership_info
+        .as_token_account()?
+        .assert_err(
+            |t| t.mint() == mint_address && t.owner() == *signer_info.key && t.amount() == 1,
+            SpoolError::InvalidOwnershipToken.into(),
+        )?;
+
+    Ok(())
+}
@@ -6,7 +6,8 @@ pub fn process_spool_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progra
     let current_slot = Clock::get()?.slot;
     let args = Create::try_from_bytes(data)?;
     let [
-        signer_info, 
+        payer_info,
+        authority_info,
         spool_info,
         writer_info, 
         system_program_info,
@@ -15,9 +16,10 @@ pub fn process_spool_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progra
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    signer_info.is_signer()?;
+    payer_info.is_signer()?.is_writable()?;
+    authority_info.is_signer()?;
 
-    let (spool_address, _spool_bump) = spool_pda(*signer_info.key, &args.name);
+    let (spool_address, _spool_bump) = spool_pda(*authority_info.key, &args.name);
     let (writer_address, _writer_bump) = writer_pda(spool_address);
 
     spool_info
@@ -36,18 +38,20 @@ pub fn process_spool_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progra
     rent_sysvar_info
         .is_sysvar(&sysvar::rent::ID)?;
 
+    // The payer funds both accounts; the spool is still derived from (and
+    // controlled by) the authority.
     create_program_account::<Spool>(
         spool_info,
         system_program_info,
-        signer_info,
+        payer_info,
         &spool_api::ID,
-        &[SPOOL, signer_info.key.as_ref(), &args.name],
+        &[SPOOL, authority_info.key.as_ref(), &args.name],
     )?;
 
     create_program_account::<Writer>(
         writer_info,
         system_program_info,
-        signer_info,
+        payer_info,
         &spool_api::ID,
         &[WRITER, spool_info.key.as_ref()],
     )?;
@@ -56,7 +60,7 @@ pub fn process_spool_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progra
     let writer = writer_info.as_account_mut::<Writer>(&spool_api::ID)?;
 
     spool.number            = 0; // (spools get a number when finalized)
-    spool.authority         = *signer_info.key;
+    spool.authority         = *authority_info.key;
     spool.name              = args.name;
     spool.state             = SpoolState::Created.into();
     spool.total_segments    = 0;
@@ -1,28 +1,30 @@
 use spool_api::prelude::*;
 use spool_api::instruction::spool::Finalize;
 use steel::*;
+use crate::spool::check_spool_authority;
 
 pub fn process_spool_finalize(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
     let _args = Finalize::try_from_bytes(data)?;
     let [
-        signer_info, 
+        payer_info,
+        authority_info, 
         spool_info,
         writer_info, 
         archive_info,
         system_program_info,
         rent_sysvar_info,
+        ownership_info @ ..,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    signer_info.is_signer()?;
+    payer_info.is_signer()?.is_writable()?;
+    authority_info.is_signer()?;
 
     let spool = spool_info
-        .as_account_mut::<Spool>(&spool_api::ID)?
-        .assert_mut_err(
-            |p| p.authority == *signer_info.key,
-            ProgramError::MissingRequiredSignature,
-        )?;
+        .as_account_mut::<Spool>(&spool_api::ID)?;
+
+    check_spool_authority(spool, spool_info, authority_info, ownership_info.first())?;
 
     let writer = writer_info
         .as_account_mut::<Writer>(&spool_api::ID)?
@@ -35,10 +37,9 @@ pub fn process_spool_finalize(accounts: &[AccountInfo<'_>], data: &[u8]) -> Prog
         .is_archive()?
         .as_account_mut::<Archive>(&spool_api::ID)?;
 
-    let (spool_address, _spool_bump) = spool_pda(*signer_info.key, &spool.name);
+    let spool_address = *spool_info.key;
     let (writer_address, _writer_bump) = writer_pda(spool_address);
 
-    spool_info.has_address(&spool_address)?;
     writer_info.has_address(&writer_address)?;
 
     system_program_info
@@ -66,8 +67,8 @@ pub fn process_spool_finalize(accounts: &[AccountInfo<'_>], data: &[u8]) -> Prog
     spool.state             = SpoolState::Finalized.into();
     spool.merkle_root       = writer.state.get_root().into();
 
-    // Close the writer and return rent to signer.
-    writer_info.close(signer_info)?;
+    // Close the writer and return rent to the payer.
+    writer_info.close(payer_info)?;
 
     FinalizeEvent {
         spool: spool.number,
@@ -4,6 +4,8 @@ pub mod update;
 pub mod finalize;
 pub mod set_header;
 pub mod subsidize;
+pub mod ownership;
+pub mod authority;
 
 pub use create::*;
 pub use write::*;
@@ -11,3 +13,5 @@ pub use update::*;
 pub use finalize::*;
 pub use set_header::*;
 pub use subsidize::*;
+pub use ownership::*;
+pub use authority::*;
@@ -0,0 +1,174 @@
+use solana_program::program_pack::Pack;
+use spl_token::state::Mint;
+use spool_api::prelude::*;
+use spool_api::instruction::spool::MintOwnershipToken;
+use steel::*;
+
+pub fn process_spool_mint_ownership_token(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let _args = MintOwnershipToken::try_from_bytes(data)?;
+    let [
+        payer_info,
+        authority_info,
+        spool_info,
+        mint_info,
+        metadata_info,
+        edition_info,
+        ata_info,
+        system_program_info,
+        token_program_info,
+        associated_token_program_info,
+        metadata_program_info,
+        rent_sysvar_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    payer_info.is_signer()?.is_writable()?;
+    authority_info.is_signer()?;
+
+    // Only the direct authority can mint the token; once minted, the authority moves to the
+    // mint and this instruction can't be called again.
+    let spool = spool_info
+        .as_account_mut::<Spool>(&spool_api::ID)?
+        .assert_mut_err(
+            |p| p.authority == *authority_info.key,
+            ProgramError::MissingRequiredSignature,
+        )?;
+
+    let (spool_address, spool_bump) = spool_pda(*authority_info.key, &spool.name);
+    let (mint_address, mint_bump) = ownership_pda(spool_address);
+    let (metadata_address, _metadata_bump) = metadata_pda(mint_address);
+    let (edition_address, _edition_bump) = edition_pda(mint_address);
+
+    spool_info.has_address(&spool_address)?;
+
+    mint_info
+        .is_empty()?
+        .is_writable()?
+        .has_address(&mint_address)?;
+
+    metadata_info
+        .is_empty()?
+        .is_writable()?
+        .has_address(&metadata_address)?;
+
+    edition_info
+        .is_empty()?
+        .is_writable()?
+        .has_address(&edition_address)?;
+
+    ata_info
+        .is_empty()?
+        .is_writable()?;
+
+    system_program_info
+        .is_program(&system_program::ID)?;
+    token_program_info
+        .is_program(&spl_token::ID)?;
+    associated_token_program_info
+        .is_program(&spl_associated_token_account::ID)?;
+    metadata_program_info
+        .is_program(&mpl_token_metadata::ID)?;
+    rent_sysvar_info
+        .is_sysvar(&sysvar::rent::ID)?;
+
+    check_condition(
+        spool.state.ne(&u64::from(SpoolState::Unknown)),
+        SpoolError::UnexpectedState,
+    )?;
+
+    let name = spool.name;
+    let spool_seeds: &[&[u8]] = &[SPOOL, authority_info.key.as_ref(), &name];
+
+    // Initialize the ownership mint, with the spool as its mint authority.
+    allocate_account_with_bump(
+        mint_info,
+        system_program_info,
+        payer_info,
+        Mint::LEN,
+        &spl_token::ID,
+        &[OWNERSHIP, spool_address.as_ref()],
+        mint_bump,
+    )?;
+    initialize_mint_signed_with_bump(
+        mint_info,
+        spool_info,
+        None,
+        token_program_info,
+        rent_sysvar_info,
+        0,
+        &[OWNERSHIP, spool_address.as_ref()],
+        mint_bump,
+    )?;
+
+    // Mint the single token to the authority.
+    create_associated_token_account(
+        payer_info,
+        authority_info,
+        ata_info,
+        mint_info,
+        system_program_info,
+        token_program_info,
+        associated_token_program_info,
+    )?;
+
+    mint_to_signed_with_bump(
+        mint_info,
+        ata_info,
+        spool_info,
+        token_program_info,
+        1,
+        spool_seeds,
+        spool_bump,
+    )?;
+
+    // Initialize the token metadata, pointing at the spool.
+    mpl_token_metadata::instructions::CreateMetadataAccountV3Cpi {
+        __program: metadata_program_info,
+        metadata: metadata_info,
+        mint: mint_info,
+        mint_authority: spool_info,
+        payer: payer_info,
+        update_authority: (spool_info, true),
+        system_program: system_program_info,
+        rent: Some(rent_sysvar_info),
+        __args: mpl_token_metadata::instructions::CreateMetadataAccountV3InstructionArgs {
+            data: mpl_token_metadata::types::DataV2 {
+                name: from_name(&name),
+                symbol: OWNERSHIP_SYMBOL.to_string(),
+                uri: format!("{OWNERSHIP_URI_PREFIX}{spool_address}"),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            is_mutable: false,
+            collection_details: None,
+        },
+    }
+    .invoke_signed(&[&[SPOOL, authority_info.key.as_ref(), &name, &[spool_bump]]])?;
+
+    // Lock the supply at one by handing the mint authority to the master edition.
+    mpl_token_metadata::instructions::CreateMasterEditionV3Cpi {
+        __program: metadata_program_info,
+        edition: edition_info,
+        mint: mint_info,
+        update_authority: spool_info,
+        mint_authority: spool_info,
+        payer: payer_info,
+        metadata: metadata_info,
+        token_program: token_program_info,
+        system_program: system_program_info,
+        rent: Some(rent_sysvar_info),
+        __args: mpl_token_metadata::instructions::CreateMasterEditionV3InstructionArgs {
+            max_supply: Some(0),
+        },
+    }
+    .invoke_signed(&[&[SPOOL, authority_info.key.as_ref(), &name, &[spool_bump]]])?;
+
+    // From here on, whoever holds the token controls the spool.
+    let spool = spool_info.as_account_mut::<Spool>(&spool_api::ID)?;
+    spool.authority = mint_address;
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use spool_api::prelude::*;
 use steel::*;
+use crate::spool::check_spool_authority;
 
 pub fn process_spool_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
     let current_slot = Clock::get()?.slot;
@@ -7,6 +8,7 @@ pub fn process_spool_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
         signer_info, 
         spool_info,
         writer_info,
+        ownership_info @ ..,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -14,11 +16,9 @@ pub fn process_spool_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
     signer_info.is_signer()?;
 
     let spool = spool_info
-        .as_account_mut::<Spool>(&spool_api::ID)?
-        .assert_mut_err(
-            |p| p.authority == *signer_info.key,
-            ProgramError::MissingRequiredSignature,
-        )?;
+        .as_account_mut::<Spool>(&spool_api::ID)?;
+
+    check_spool_authority(spool, spool_info, signer_info, ownership_info.first())?;
 
     let writer = writer_info
         .as_account_mut::<Writer>(&spool_api::ID)?
@@ -27,10 +27,9 @@ pub fn process_spool_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
             ProgramError::InvalidAccountData,
         )?;
 
-    let (spool_address, _spool_bump) = spool_pda(*signer_info.key, &spool.name);
+    let spool_address = *spool_info.key;
     let (writer_address, _writer_bump) = writer_pda(spool_address);
 
-    spool_info.has_address(&spool_address)?;
     writer_info.has_address(&writer_address)?;
         
     check_condition(
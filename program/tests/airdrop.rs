@@ -9,6 +9,7 @@ use solana_sdk::{
     transaction::Transaction,
     pubkey::Pubkey,
     signature::Keypair,
+    clock::Clock,
 };
 use litesvm::{types::TransactionResult, LiteSVM};
 use spool_api::prelude::*;
@@ -39,18 +40,42 @@ fn test_airdrop() {
     let ata_balance = get_ata_balance(&svm, &beneficiary_ata);
     assert_eq!(ata_balance, airdrop_amount, "ATA balance should match airdropped amount");
 
-    // Test airdrop with large amount
-    let large_amount = 1_000_000 * ONE_SPOOL; // 1,000,000 SPOOL
-    perform_airdrop(&mut svm, &payer, beneficiary_ata, large_amount);
-    let expected_balance = airdrop_amount + large_amount;
+    // The claim is tracked per signer
+    let (faucet_claim_address, _bump) = faucet_claim_pda(payer.pubkey());
+    let faucet_claim = get_faucet_claim(&svm, &faucet_claim_address);
+    assert_eq!(faucet_claim.authority, payer.pubkey());
+    assert_eq!(faucet_claim.claimed, airdrop_amount);
+
+    // Test airdrop during the cooldown (should fail)
+    let result = try_airdrop(&mut svm, &payer, beneficiary_ata, ONE_SPOOL);
+    assert!(result.is_err(), "Airdrop during the cooldown should fail");
+
+    // Test airdrop above the remaining allowance (should fail)
+    advance_clock(&mut svm, FAUCET_COOLDOWN_SECONDS);
+    let remaining = FAUCET_CAP - airdrop_amount;
+    let result = try_airdrop(&mut svm, &payer, beneficiary_ata, remaining + 1);
+    assert!(result.is_err(), "Airdrop above the allowance should fail");
+
+    // Test airdrop of the full remaining allowance
+    perform_airdrop(&mut svm, &payer, beneficiary_ata, remaining);
+    let expected_balance = airdrop_amount + remaining;
     let ata_balance = get_ata_balance(&svm, &beneficiary_ata);
-    assert_eq!(ata_balance, expected_balance, "ATA balance should include large airdrop");
+    assert_eq!(ata_balance, expected_balance, "ATA balance should include the remaining allowance");
 
     // Test airdrop with zero amount
+    advance_clock(&mut svm, FAUCET_COOLDOWN_SECONDS);
     perform_airdrop(&mut svm, &payer, beneficiary_ata, 0);
     let ata_balance = get_ata_balance(&svm, &beneficiary_ata);
     assert_eq!(ata_balance, expected_balance, "Zero-amount airdrop should not change balance");
 
+    // Test airdrop once the allowance is used up (should fail)
+    advance_clock(&mut svm, FAUCET_COOLDOWN_SECONDS);
+    let result = try_airdrop(&mut svm, &payer, beneficiary_ata, ONE_SPOOL);
+    assert!(result.is_err(), "Airdrop with no allowance left should fail");
+
+    // The allowance resets with the next window
+    advance_clock(&mut svm, FAUCET_WINDOW_SECONDS);
+
     // Test airdrop to a new beneficiary
     let new_beneficiary = Keypair::new();
     let new_beneficiary_ata = create_ata(&mut svm, &payer, &MINT_ADDRESS, &new_beneficiary.pubkey());
@@ -69,6 +94,48 @@ fn test_airdrop() {
     assert!(result.is_err(), "Airdrop to non-existent ATA should fail");
 }
 
+#[test]
+fn test_configure_faucet() {
+    // Setup environment
+    let (mut svm, payer) = setup_environment();
+    initialize_program(&mut svm, &payer);
+
+    let beneficiary_ata = create_ata(&mut svm, &payer, &MINT_ADDRESS, &payer.pubkey());
+
+    // Only the genesis spool authority can configure the faucet
+    let stranger = create_payer(&mut svm);
+    let result = try_configure_faucet(&mut svm, &stranger, ONE_SPOOL, 600, 0);
+    assert!(result.is_err(), "Non-admin should not configure the faucet");
+
+    // Invalid limits are rejected
+    let result = try_configure_faucet(&mut svm, &payer, ONE_SPOOL, 0, 0);
+    assert!(result.is_err(), "A zero-length window should be rejected");
+
+    // The admin lowers the cap and removes the cooldown
+    let result = try_configure_faucet(&mut svm, &payer, 2 * ONE_SPOOL, 600, 0);
+    assert!(result.is_ok(), "Admin should configure the faucet: {:?}", result.err());
+
+    let (faucet_config_address, _bump) = faucet_config_pda();
+    let account = svm.get_account(&faucet_config_address).expect("Faucet config should exist");
+    let faucet_config = FaucetConfig::unpack(&account.data).unwrap();
+    assert_eq!(faucet_config.cap, 2 * ONE_SPOOL);
+    assert_eq!(faucet_config.window_seconds, 600);
+    assert_eq!(faucet_config.cooldown_seconds, 0);
+
+    // The new limits apply right away
+    let result = try_airdrop(&mut svm, &payer, beneficiary_ata, 3 * ONE_SPOOL);
+    assert!(result.is_err(), "Airdrop above the configured cap should fail");
+    perform_airdrop(&mut svm, &payer, beneficiary_ata, ONE_SPOOL);
+    perform_airdrop(&mut svm, &payer, beneficiary_ata, ONE_SPOOL);
+    let result = try_airdrop(&mut svm, &payer, beneficiary_ata, ONE_SPOOL);
+    assert!(result.is_err(), "Airdrop past the configured cap should fail");
+
+    // The configured window resets the allowance
+    advance_clock(&mut svm, 600);
+    perform_airdrop(&mut svm, &payer, beneficiary_ata, 2 * ONE_SPOOL);
+    assert_eq!(get_ata_balance(&svm, &beneficiary_ata), 4 * ONE_SPOOL);
+}
+
 fn setup_environment() -> (LiteSVM, Keypair) {
     let mut svm = setup_svm();
     let payer = create_payer(&mut svm);
@@ -106,6 +173,19 @@ fn verify_treasury_ata(svm: &LiteSVM) {
     assert!(!account.data.is_empty(), "Treasury ATA data should not be empty");
 }
 
+fn get_faucet_claim(svm: &LiteSVM, address: &Pubkey) -> FaucetClaim {
+    let account = svm
+        .get_account(address)
+        .expect("Faucet claim account should exist");
+    *FaucetClaim::unpack(&account.data).unwrap()
+}
+
+fn advance_clock(svm: &mut LiteSVM, seconds: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += seconds;
+    svm.set_sysvar::<Clock>(&clock);
+}
+
 fn perform_airdrop(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -116,6 +196,21 @@ fn perform_airdrop(
     assert!(result.is_ok(), "Airdrop transaction failed: {:?}", result.err());
 }
 
+fn try_configure_faucet(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    cap: u64,
+    window_seconds: i64,
+    cooldown_seconds: i64,
+) -> TransactionResult {
+    let signer_pk = signer.pubkey();
+    svm.expire_blockhash();
+    let blockhash = svm.latest_blockhash();
+    let ix = instruction::program::build_configure_faucet_ix(signer_pk, cap, window_seconds, cooldown_seconds);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&signer_pk), &[signer], blockhash);
+    send_tx(svm, tx)
+}
+
 fn try_airdrop(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -123,6 +218,7 @@ fn try_airdrop(
     amount: u64,
 ) -> TransactionResult {
     let payer_pk = payer.pubkey();
+    svm.expire_blockhash();
     let blockhash = svm.latest_blockhash();
     let ix = instruction::program::build_airdrop_ix(payer_pk, beneficiary_ata, amount);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
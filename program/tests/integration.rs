@@ -494,7 +494,7 @@ fn create_spool(
 
     // Create spool
     let blockhash = svm.latest_blockhash();
-    let ix = instruction::spool::build_create_ix(payer_pk, spool_name);
+    let ix = instruction::spool::build_create_ix(payer_pk, payer_pk, spool_name);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&payer], blockhash);
     let res = send_tx(svm, tx);
     assert!(res.is_ok());
@@ -674,7 +674,7 @@ fn finalize_spool(
 
     // Finalize spool
     let blockhash = svm.latest_blockhash();
-    let ix = instruction::spool::build_finalize_ix(payer_pk, spool_address, writer_address);
+    let ix = instruction::spool::build_finalize_ix(payer_pk, payer_pk, spool_address, writer_address);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&payer], blockhash);
     let res = send_tx(svm, tx);
     assert!(res.is_ok());
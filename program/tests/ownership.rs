@@ -0,0 +1,139 @@
+#![cfg(test)]
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    signer::Signer,
+    transaction::Transaction,
+    pubkey::Pubkey,
+    signature::Keypair,
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use spool_api::prelude::*;
+use spool_api::instruction;
+
+#[test]
+fn test_ownership_token() {
+    // Setup environment
+    let (mut svm, payer) = setup_environment();
+
+    // Create a spool and write to it as the original authority
+    let spool_name = "ownership";
+    let (spool_address, _spool_bump) = spool_pda(payer.pubkey(), &to_name(spool_name));
+    let (writer_address, _writer_bump) = writer_pda(spool_address);
+    create_spool(&mut svm, &payer, spool_name);
+    assert!(try_write(&mut svm, &payer, spool_address, writer_address, None).is_ok());
+
+    // Mint the ownership token
+    mint_ownership_token(&mut svm, &payer, spool_address);
+    let (mint_address, _mint_bump) = ownership_pda(spool_address);
+    let payer_ata = get_ata_address(&mint_address, &payer.pubkey());
+    assert_eq!(get_ata_balance(&svm, &payer_ata), 1, "Holder should own the token");
+
+    let mint = get_mint(&svm, &mint_address);
+    assert_eq!(mint.supply, 1, "Ownership mint supply should be one");
+    assert_eq!(mint.decimals, 0, "Ownership mint should have no decimals");
+
+    let account = svm.get_account(&spool_address).unwrap();
+    let spool = Spool::unpack(&account.data).unwrap();
+    assert_eq!(spool.authority, mint_address, "Spool authority should move to the mint");
+
+    let (metadata_address, _metadata_bump) = metadata_pda(mint_address);
+    assert!(svm.get_account(&metadata_address).is_some(), "Metadata account should exist");
+
+    // Minting a second time should fail
+    let result = try_mint_ownership_token(&mut svm, &payer, spool_address);
+    assert!(result.is_err(), "Ownership token can only be minted once");
+
+    // Writing now requires the token account
+    let result = try_write(&mut svm, &payer, spool_address, writer_address, None);
+    assert!(result.is_err(), "Write without the token account should fail");
+    let result = try_write(&mut svm, &payer, spool_address, writer_address, Some(payer_ata));
+    assert!(result.is_ok(), "Write with the token account should succeed");
+
+    // Transfer the token to a new holder
+    let holder = create_payer(&mut svm);
+    let holder_ata = create_ata(&mut svm, &payer, &mint_address, &holder.pubkey());
+    transfer_token(&mut svm, &payer, payer_ata, holder_ata);
+
+    // Authority follows the token
+    let result = try_write(&mut svm, &payer, spool_address, writer_address, Some(payer_ata));
+    assert!(result.is_err(), "Previous holder should no longer control the spool");
+    let result = try_write(&mut svm, &holder, spool_address, writer_address, Some(payer_ata));
+    assert!(result.is_err(), "Holder can't present someone else's token account");
+    let result = try_write(&mut svm, &holder, spool_address, writer_address, Some(holder_ata));
+    assert!(result.is_ok(), "New holder should control the spool");
+}
+
+fn setup_environment() -> (LiteSVM, Keypair) {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    (svm, payer)
+}
+
+fn create_spool(svm: &mut LiteSVM, payer: &Keypair, spool_name: &str) {
+    let payer_pk = payer.pubkey();
+    let ix = instruction::spool::build_create_ix(payer_pk, payer_pk, spool_name);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+    let res = send_tx(svm, tx);
+    assert!(res.is_ok(), "Spool creation failed");
+}
+
+fn mint_ownership_token(svm: &mut LiteSVM, payer: &Keypair, spool_address: Pubkey) {
+    let result = try_mint_ownership_token(svm, payer, spool_address);
+    assert!(result.is_ok(), "Minting the ownership token failed: {:?}", result.err());
+}
+
+fn try_mint_ownership_token(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    spool_address: Pubkey,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let ix = instruction::spool::build_mint_ownership_token_ix(payer_pk, payer_pk, spool_address);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+    send_tx(svm, tx)
+}
+
+fn try_write(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    spool_address: Pubkey,
+    writer_address: Pubkey,
+    token_account: Option<Pubkey>,
+) -> TransactionResult {
+    let signer_pk = signer.pubkey();
+    let data = b"<ownership_segment_data>";
+    let mut ix = instruction::spool::build_write_ix(signer_pk, spool_address, writer_address, data);
+    if let Some(token_account) = token_account {
+        ix = instruction::spool::with_ownership_token(ix, token_account);
+    }
+    svm.expire_blockhash();
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&signer_pk), &[signer], blockhash);
+    send_tx(svm, tx)
+}
+
+fn transfer_token(
+    svm: &mut LiteSVM,
+    owner: &Keypair,
+    source: Pubkey,
+    destination: Pubkey,
+) {
+    let owner_pk = owner.pubkey();
+    let ix = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &source,
+        &destination,
+        &owner_pk,
+        &[],
+        1,
+    )
+    .unwrap();
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&owner_pk), &[owner], blockhash);
+    let res = send_tx(svm, tx);
+    assert!(res.is_ok(), "Token transfer failed");
+}
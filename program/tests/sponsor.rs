@@ -0,0 +1,84 @@
+#![cfg(test)]
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    signer::Signer,
+    transaction::Transaction,
+    signature::Keypair,
+};
+use litesvm::LiteSVM;
+use spool_api::prelude::*;
+use spool_api::instruction;
+
+#[test]
+fn test_sponsored_spool() {
+    // Setup environment; the authority never holds any SOL
+    let (mut svm, sponsor) = setup_environment();
+    let authority = Keypair::new();
+    let sponsor_pk = sponsor.pubkey();
+    let authority_pk = authority.pubkey();
+
+    let spool_name = "sponsored";
+    let (spool_address, _spool_bump) = spool_pda(authority_pk, &to_name(spool_name));
+    let (writer_address, _writer_bump) = writer_pda(spool_address);
+
+    // Create the spool, paid for by the sponsor
+    let sponsor_balance = svm.get_balance(&sponsor_pk).unwrap();
+    let ix = instruction::spool::build_create_ix(sponsor_pk, authority_pk, spool_name);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&sponsor_pk), &[&sponsor, &authority], blockhash);
+    let res = send_tx(&mut svm, tx);
+    assert!(res.is_ok(), "Sponsored spool creation failed");
+
+    let spool_rent = svm.get_account(&spool_address).unwrap().lamports;
+    let writer_rent = svm.get_account(&writer_address).unwrap().lamports;
+    let sponsor_spent = sponsor_balance - svm.get_balance(&sponsor_pk).unwrap();
+    assert!(sponsor_spent >= spool_rent + writer_rent, "Sponsor should fund both accounts");
+    assert!(svm.get_account(&authority_pk).is_none(), "Authority should not need an account");
+
+    // The spool belongs to the authority, not the sponsor
+    let account = svm.get_account(&spool_address).unwrap();
+    let spool = Spool::unpack(&account.data).unwrap();
+    assert_eq!(spool.authority, authority_pk);
+
+    // Writes are signed by the authority, with the sponsor paying the fee
+    let ix = instruction::spool::build_write_ix(authority_pk, spool_address, writer_address, b"<sponsored>");
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&sponsor_pk), &[&sponsor, &authority], blockhash);
+    let res = send_tx(&mut svm, tx);
+    assert!(res.is_ok(), "Sponsored write failed");
+
+    // The sponsor alone can't write to the spool
+    let ix = instruction::spool::build_write_ix(sponsor_pk, spool_address, writer_address, b"<sponsor>");
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&sponsor_pk), &[&sponsor], blockhash);
+    let res = send_tx(&mut svm, tx);
+    assert!(res.is_err(), "Sponsor should not control the spool");
+
+    // A payer that doesn't sign can't collect the writer's rent on finalize
+    let thief = Keypair::new();
+    let mut ix = instruction::spool::build_finalize_ix(thief.pubkey(), authority_pk, spool_address, writer_address);
+    ix.accounts[0].is_signer = false;
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&sponsor_pk), &[&sponsor, &authority], blockhash);
+    let res = send_tx(&mut svm, tx);
+    assert!(res.is_err(), "Finalize should require the payer to sign");
+    assert!(svm.get_account(&thief.pubkey()).is_none(), "Unsigned payer should not receive any rent");
+
+    // Finalize signed by the authority, the sponsor gets the writer's rent back
+    let sponsor_balance = svm.get_balance(&sponsor_pk).unwrap();
+    let ix = instruction::spool::build_finalize_ix(sponsor_pk, authority_pk, spool_address, writer_address);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&sponsor_pk), &[&sponsor, &authority], blockhash);
+    let res = send_tx(&mut svm, tx);
+    assert!(res.is_ok(), "Sponsored finalize failed");
+    assert!(svm.get_account(&writer_address).is_none(), "Writer should be closed");
+    assert!(svm.get_balance(&sponsor_pk).unwrap() > sponsor_balance, "Sponsor should receive the writer's rent");
+}
+
+fn setup_environment() -> (LiteSVM, Keypair) {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    (svm, payer)
+}